@@ -0,0 +1,232 @@
+//! Counts the bus transactions the unbuffered graphics modes need for typical scenes
+//!
+//! Run with `cargo bench --bench transactions`, add `--features async` to include
+//! `AsyncGraphicsMode`. The "per pixel" column draws every pixel with `set_pixel`, the "1 pixel
+//! chunks" column streams one pixel per transfer, both matching the behaviour before chunked
+//! streaming.
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use embedded_graphics::image::{Image, ImageRaw};
+use embedded_graphics::mono_font::{ascii::FONT_6X10, MonoTextStyle, MonoTextStyleBuilder};
+use embedded_graphics::pixelcolor::{raw::RawU16, Rgb565};
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{Circle, Line, PrimitiveStyle, Rectangle};
+use embedded_graphics::text::Text;
+use ssd1351::builder::Builder;
+use ssd1351::mode::displaymode::DisplayModeTrait;
+use ssd1351::mode::{DirectGraphicsMode, GraphicsMode};
+
+/// Interface counting transactions and data bytes
+#[derive(Default)]
+struct Counter {
+    transactions: usize,
+    bytes: usize,
+}
+
+impl WriteOnlyDataCommand for Counter {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.count(cmd);
+        Ok(())
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.count(buf);
+        Ok(())
+    }
+}
+
+impl Counter {
+    fn count(&mut self, data: DataFormat<'_>) {
+        self.transactions += 1;
+        if let DataFormat::U8(bytes) = data {
+            self.bytes += bytes.len();
+        }
+    }
+}
+
+/// Draws every pixel with `set_pixel`
+struct PerPixel(GraphicsMode<Counter>);
+
+impl OriginDimensions for PerPixel {
+    fn size(&self) -> Size {
+        self.0.size()
+    }
+}
+
+impl DrawTarget for PerPixel {
+    type Color = Rgb565;
+    type Error = ();
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let bb = self.bounding_box();
+        for Pixel(pos, color) in pixels.into_iter().filter(|Pixel(pos, _)| bb.contains(*pos)) {
+            self.0
+                .set_pixel(pos.x as u32, pos.y as u32, RawU16::from(color).into_inner());
+        }
+        Ok(())
+    }
+}
+
+const TEXT: &str = "The quick brown fox\njumps over the lazy dog";
+
+const IMAGE_SIZE: u32 = 64;
+
+fn image_data() -> Vec<u8> {
+    (0..IMAGE_SIZE * IMAGE_SIZE)
+        .flat_map(|i| ((i * 37) as u16).to_be_bytes())
+        .collect()
+}
+
+const SCENES: [&str; 4] = ["text", "transparent text", "64x64 image", "shapes"];
+
+fn draw_scene<D: DrawTarget<Color = Rgb565>>(scene: &str, d: &mut D) {
+    match scene {
+        "text" => {
+            let style = MonoTextStyleBuilder::new()
+                .font(&FONT_6X10)
+                .text_color(Rgb565::WHITE)
+                .background_color(Rgb565::BLACK)
+                .build();
+            let _ = Text::new(TEXT, Point::new(0, 10), style).draw(d);
+        }
+        "transparent text" => {
+            let style = MonoTextStyle::new(&FONT_6X10, Rgb565::YELLOW);
+            let _ = Text::new(TEXT, Point::new(0, 10), style).draw(d);
+        }
+        "64x64 image" => {
+            let data = image_data();
+            let raw = ImageRaw::<Rgb565>::new(&data, IMAGE_SIZE);
+            let _ = Image::new(&raw, Point::new(32, 32)).draw(d);
+        }
+        _ => {
+            let _ = Rectangle::new(Point::new(10, 10), Size::new(100, 100))
+                .into_styled(PrimitiveStyle::with_fill(Rgb565::BLUE))
+                .draw(d);
+            let _ = Circle::new(Point::new(20, 20), 80)
+                .into_styled(PrimitiveStyle::with_stroke(Rgb565::RED, 1))
+                .draw(d);
+            let _ = Line::new(Point::new(0, 127), Point::new(127, 0))
+                .into_styled(PrimitiveStyle::with_stroke(Rgb565::GREEN, 1))
+                .draw(d);
+        }
+    }
+}
+
+fn count<T, F>(mut target: T, scene: &str, release: F) -> Counter
+where
+    T: DrawTarget<Color = Rgb565>,
+    F: FnOnce(T) -> Counter,
+{
+    draw_scene(scene, &mut target);
+    release(target)
+}
+
+#[cfg(feature = "async")]
+fn count_async(scene: &str) -> Counter {
+    use embedded_graphics::primitives::PointsIter;
+    use ssd1351::async_builder::AsyncBuilder;
+    use ssd1351::async_interface::BlockingAdapter;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    /// Records the draw calls of a scene to replay them on the async mode
+    enum Call {
+        Pixels(Vec<Pixel<Rgb565>>),
+        Fill(Rectangle, Vec<Rgb565>),
+    }
+
+    #[derive(Default)]
+    struct Recorder(Vec<Call>);
+
+    impl OriginDimensions for Recorder {
+        fn size(&self) -> Size {
+            Size::new(128, 128)
+        }
+    }
+
+    impl DrawTarget for Recorder {
+        type Color = Rgb565;
+        type Error = ();
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            self.0.push(Call::Pixels(pixels.into_iter().collect()));
+            Ok(())
+        }
+
+        fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Self::Color>,
+        {
+            let colors = colors.into_iter().take(area.points().count()).collect();
+            self.0.push(Call::Fill(*area, colors));
+            Ok(())
+        }
+    }
+
+    let mut recorder = Recorder::default();
+    draw_scene(scene, &mut recorder);
+
+    let mut display =
+        AsyncBuilder::new().connect_interface(BlockingAdapter::new(Counter::default()));
+    block_on(async {
+        for call in recorder.0 {
+            let _ = match call {
+                Call::Pixels(pixels) => display.draw_iter_async(pixels).await,
+                Call::Fill(area, colors) => display.fill_contiguous_async(&area, colors).await,
+            };
+        }
+    });
+    display.release().release().release()
+}
+
+fn main() {
+    println!(
+        "{:<18} {:>22} {:>22} {:>22}",
+        "scene", "per pixel", "1 pixel chunks", "256 byte chunks"
+    );
+
+    for scene in SCENES {
+        let per_pixel = count(
+            PerPixel(Builder::new().connect_interface(Counter::default()).into()),
+            scene,
+            |t| t.0.release().0.release(),
+        );
+        let single: DirectGraphicsMode<_, 2> =
+            Builder::new().connect_interface(Counter::default()).into();
+        let single = count(single, scene, |t| t.release().0.release());
+        let chunked: GraphicsMode<_> = Builder::new().connect_interface(Counter::default()).into();
+        let chunked = count(chunked, scene, |t| t.release().0.release());
+
+        let cell = |c: &Counter| format!("{} ({} B)", c.transactions, c.bytes);
+        println!(
+            "{:<18} {:>22} {:>22} {:>22}",
+            scene,
+            cell(&per_pixel),
+            cell(&single),
+            cell(&chunked)
+        );
+
+        #[cfg(feature = "async")]
+        {
+            let chunked = count_async(scene);
+            println!("{:<18} {:>68}", "  async", cell(&chunked));
+        }
+    }
+}
@@ -32,7 +32,7 @@ use static_cell::StaticCell;
 // Import the async SSD1351 modules
 use ssd1351::{
     async_builder::AsyncBuilder,
-    async_interface::AsyncWriteOnlyDataCommand,
+    async_interface::BlockingAdapter,
     mode::AsyncGraphicsMode,
     prelude::*,
     properties::{DisplayRotation, DisplaySize},
@@ -42,41 +42,6 @@ use display_interface_spi::SPIInterface;
 // Static allocations for async usage
 static EXECUTOR: StaticCell<embassy_executor::Executor> = StaticCell::new();
 
-// Async SPI interface adapter for Embassy
-pub struct AsyncSPIInterface<SPI> {
-    interface: SPIInterface<SPI, Output<'static>, Output<'static>>,
-}
-
-impl<SPI> AsyncSPIInterface<SPI> {
-    pub fn new(
-        spi: SPI,
-        dc: Output<'static>,
-        cs: Output<'static>,
-    ) -> Self {
-        let interface = SPIInterface::new(spi, dc, cs);
-        Self { interface }
-    }
-}
-
-impl<SPI> AsyncWriteOnlyDataCommand for AsyncSPIInterface<SPI>
-where
-    SPI: embedded_hal::spi::SpiDevice + Send,
-{
-    async fn send_commands(&mut self, cmd: display_interface::DataFormat<'_>) -> Result<(), display_interface::DisplayError> {
-        // In a real implementation, you'd use async SPI here
-        // For now, we'll use the blocking implementation as a fallback
-        use display_interface::WriteOnlyDataCommand;
-        self.interface.send_commands(cmd)
-    }
-    
-    async fn send_data(&mut self, buf: display_interface::DataFormat<'_>) -> Result<(), display_interface::DisplayError> {
-        // In a real implementation, you'd use async SPI here
-        // For now, we'll use the blocking implementation as a fallback
-        use display_interface::WriteOnlyDataCommand;
-        self.interface.send_data(buf)
-    }
-}
-
 #[main]
 async fn main(_spawner: Spawner) {
     println!("Starting async SSD1351 example!");
@@ -101,8 +66,8 @@ async fn main(_spawner: Spawner) {
         
     let spi_device = ExclusiveDevice::new(spi, cs, embassy_time::Delay);
     
-    // Create async display interface
-    let async_interface = AsyncSPIInterface::new(spi_device, dc, cs);
+    // Create async display interface, running the blocking SPI interface on the executor
+    let async_interface = BlockingAdapter::new(SPIInterface::new(spi_device, dc));
     
     // Build async display
     let mut display: AsyncGraphicsMode<_> = AsyncBuilder::new()
@@ -17,50 +17,17 @@ use esp_hal::{
     timer::timg::TimerGroup,
 };
 use esp_hal::time::Rate;
-use embedded_hal::spi::SpiDevice;
-use embedded_hal::digital::OutputPin;
 use fugit::RateExtU32;
 use esp_println::println;
 
 // Import our async SSD1351 modules
 use ssd1351::{
     async_builder::AsyncBuilder,
-    async_interface::AsyncWriteOnlyDataCommand,
+    async_interface::BlockingAdapter,
     mode::AsyncGraphicsMode,
     properties::{DisplayRotation, DisplaySize},
 };
 use display_interface_spi::SPIInterface;
-use display_interface::{DataFormat, DisplayError};
-
-// Create an async-compatible SPI interface wrapper
-pub struct AsyncSPIInterface<SPI, DC> {
-    interface: SPIInterface<SPI, DC>,
-}
-
-impl<SPI, DC> AsyncSPIInterface<SPI, DC> {
-    pub fn new(spi: SPI, dc: DC) -> Self {
-        let interface = SPIInterface::new(spi, dc);
-        Self { interface }
-    }
-}
-
-impl<SPI, DC> AsyncWriteOnlyDataCommand for AsyncSPIInterface<SPI, DC>
-where
-    SPI: SpiDevice,
-    DC: OutputPin,
-{
-    async fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
-        // For now, use the blocking implementation
-        // In a real async implementation, you'd use async SPI here
-        display_interface::WriteOnlyDataCommand::send_commands(&mut self.interface, cmd)
-    }
-    
-    async fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
-        // For now, use the blocking implementation
-        // In a real async implementation, you'd use async SPI here
-        display_interface::WriteOnlyDataCommand::send_data(&mut self.interface, buf)
-    }
-}
 
 #[esp_hal_embassy::main]
 async fn main(_spawner: Spawner) {
@@ -100,8 +67,8 @@ async fn main(_spawner: Spawner) {
     
     println!("Creating display interface...");
     
-    // Create async display interface
-    let async_interface = AsyncSPIInterface::new(spi_device, dc);
+    // Create async display interface, running the blocking SPI interface on the executor
+    let async_interface = BlockingAdapter::new(SPIInterface::new(spi_device, dc));
     
     // Build async display
     let mut display: AsyncGraphicsMode<_> = AsyncBuilder::new()
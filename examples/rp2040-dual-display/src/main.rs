@@ -23,8 +23,6 @@ use ssd1351::properties::{DisplayRotation, DisplaySize};
 use tinybmp::Bmp;
 use {defmt_rtt as _, panic_probe as _};
 
-// static mut BUF: [u8; 32768] = [0; 32768];
-
 #[embassy_executor::main]
 async fn main(_spawner: Spawner) {
     let p = embassy_rp::init(Default::default());
@@ -45,9 +43,9 @@ async fn main(_spawner: Spawner) {
     let interface = SPIInterface::new(spi_device, dc);
     let mut display_128: GraphicsMode<_> = Builder::new()
         .with_rotation(DisplayRotation::Rotate0)
-        .connect_interface(
-            interface, // , unsafe { &mut BUF}
-        )
+        // For a `BufferedGraphicsMode`, use `connect_interface_buffered` with framebuffer
+        // storage such as `[0u8; DisplaySize::Display128x128.buffer_size()]`
+        .connect_interface(interface)
         .into();
 
     display_128.reset(&mut rst, &mut Delay).unwrap();
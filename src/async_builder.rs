@@ -1,15 +1,27 @@
 //! Async interface factory for Embassy compatibility
 
-use crate::async_display::AsyncDisplay;
+use crate::async_display::{AsyncDisplay, RecoveryPolicy};
 use crate::async_interface::AsyncWriteOnlyDataCommand;
-use crate::mode::async_graphics::AsyncGraphicsMode;
+use crate::mode::async_buffered_graphics::AsyncBufferedGraphicsMode;
+use crate::mode::async_displaymode::AsyncDisplayMode;
+use crate::mode::async_double_buffered::AsyncDoubleBufferedGraphicsMode;
+use crate::mode::async_graphics::AsyncDirectGraphicsMode;
+#[cfg(feature = "graphics")]
+use crate::mode::async_mono::AsyncMonoGraphicsMode;
+use crate::mode::async_raw::AsyncRawMode;
+#[cfg(feature = "graphics")]
+use crate::mode::async_strip::AsyncStripMode;
+#[cfg(feature = "graphics")]
+use crate::mode::strip::MAX_ROW_BYTES;
 use crate::properties::{DisplayRotation, DisplaySize};
+use display_interface::DisplayError;
 
 /// Async Builder struct. Driver options and interface are set using its methods.
 #[derive(Clone)]
 pub struct AsyncBuilder {
     display_size: DisplaySize,
     rotation: DisplayRotation,
+    recovery: RecoveryPolicy,
 }
 
 impl Default for AsyncBuilder {
@@ -24,6 +36,7 @@ impl AsyncBuilder {
         Self {
             display_size: DisplaySize::Display128x128,
             rotation: DisplayRotation::Rotate0,
+            recovery: RecoveryPolicy::Report,
         }
     }
 
@@ -40,29 +53,111 @@ impl AsyncBuilder {
         Self { rotation, ..*self }
     }
 
-    #[cfg(feature = "buffered")]
+    /// Set what the async modes do when a flush fails. Defaults to [RecoveryPolicy::Report].
+    pub fn with_recovery_policy(&self, recovery: RecoveryPolicy) -> Self {
+        Self { recovery, ..*self }
+    }
+
     /// Finish the builder and use the given interface to communicate with the display
-    pub fn connect_interface<DI>(
+    pub fn connect_interface<DI>(&self, display_interface: DI) -> AsyncDirectGraphicsMode<DI>
+    where
+        DI: AsyncWriteOnlyDataCommand,
+    {
+        let display = self.display(display_interface);
+        AsyncDirectGraphicsMode::new(display)
+    }
+
+    /// Finish the builder with a framebuffer and use the given interface to communicate with the
+    /// display. The framebuffer must be exactly [DisplaySize::buffer_size] bytes long, otherwise
+    /// `DisplayError::OutOfBoundsError` is returned.
+    pub fn connect_interface_buffered<DI, BUF>(
         &self,
         display_interface: DI,
-        buffer: &'static mut [u8],
-    ) -> AsyncGraphicsMode<DI>
+        buffer: BUF,
+    ) -> Result<AsyncBufferedGraphicsMode<DI, BUF>, DisplayError>
     where
         DI: AsyncWriteOnlyDataCommand,
+        BUF: AsRef<[u8]> + AsMut<[u8]>,
     {
-        assert_eq!(buffer.len(), self.display_size.num_pixels() * 2);
-        let display = AsyncDisplay::new(display_interface, self.display_size, self.rotation);
-        AsyncGraphicsMode::new(display, buffer)
+        if buffer.as_ref().len() != self.display_size.buffer_size() {
+            return Err(DisplayError::OutOfBoundsError);
+        }
+        let display = self.display(display_interface);
+        Ok(AsyncBufferedGraphicsMode::new(display, buffer))
     }
 
-    #[cfg(not(feature = "buffered"))]
-    /// Finish the builder and use the given interface to communicate with the display
-    pub fn connect_interface<DI>(&self, display_interface: DI) -> AsyncGraphicsMode<DI>
+    /// Finish the builder with a front and a back framebuffer, sending one while drawing into the
+    /// other. Each framebuffer must be exactly [DisplaySize::buffer_size] bytes long, otherwise
+    /// `DisplayError::OutOfBoundsError` is returned.
+    pub fn connect_interface_double_buffered<DI, BUF>(
+        &self,
+        display_interface: DI,
+        front: BUF,
+        back: BUF,
+    ) -> Result<AsyncDoubleBufferedGraphicsMode<DI, BUF>, DisplayError>
     where
         DI: AsyncWriteOnlyDataCommand,
+        BUF: AsRef<[u8]> + AsMut<[u8]>,
     {
-        let display = AsyncDisplay::new(display_interface, self.display_size, self.rotation);
-        AsyncGraphicsMode::new(display)
+        let size = self.display_size.buffer_size();
+        if front.as_ref().len() != size || back.as_ref().len() != size {
+            return Err(DisplayError::OutOfBoundsError);
+        }
+        let display = self.display(display_interface);
+        Ok(AsyncDoubleBufferedGraphicsMode::new(display, front, back))
+    }
+
+    /// Finish the builder with a buffer holding a few rows for strip rendering. The buffer must
+    /// hold at least one row of 128 pixels (256 bytes), otherwise
+    /// `DisplayError::OutOfBoundsError` is returned.
+    #[cfg(feature = "graphics")]
+    pub fn connect_interface_strip<DI, BUF>(
+        &self,
+        display_interface: DI,
+        buffer: BUF,
+    ) -> Result<AsyncStripMode<DI, BUF>, DisplayError>
+    where
+        DI: AsyncWriteOnlyDataCommand,
+        BUF: AsRef<[u8]> + AsMut<[u8]>,
+    {
+        if buffer.as_ref().len() < MAX_ROW_BYTES {
+            return Err(DisplayError::OutOfBoundsError);
+        }
+        let display = self.display(display_interface);
+        Ok(AsyncStripMode::new(display, buffer))
+    }
+
+    /// Finish the builder with a one bit per pixel framebuffer for monochrome drawing. The
+    /// framebuffer must be exactly [DisplaySize::mono_buffer_size] bytes long, otherwise
+    /// `DisplayError::OutOfBoundsError` is returned.
+    #[cfg(feature = "graphics")]
+    pub fn connect_interface_mono<DI, BUF>(
+        &self,
+        display_interface: DI,
+        buffer: BUF,
+    ) -> Result<AsyncMonoGraphicsMode<DI, BUF>, DisplayError>
+    where
+        DI: AsyncWriteOnlyDataCommand,
+        BUF: AsRef<[u8]> + AsMut<[u8]>,
+    {
+        if buffer.as_ref().len() != self.display_size.mono_buffer_size() {
+            return Err(DisplayError::OutOfBoundsError);
+        }
+        let display = self.display(display_interface);
+        Ok(AsyncMonoGraphicsMode::new(display, buffer))
+    }
+
+    /// Finish the builder in [`AsyncRawMode`], to be turned into any mode without a framebuffer
+    /// with `into`, e.g. `AsyncGraphicsMode`
+    pub fn connect_interface_raw<DI>(
+        &self,
+        display_interface: DI,
+    ) -> AsyncDisplayMode<AsyncRawMode<DI>>
+    where
+        DI: AsyncWriteOnlyDataCommand,
+    {
+        let display = self.display(display_interface);
+        AsyncDisplayMode::<AsyncRawMode<DI>>::new(display, ())
     }
 
     /// Create an async display instance (without graphics mode)
@@ -70,6 +165,15 @@ impl AsyncBuilder {
     where
         DI: AsyncWriteOnlyDataCommand,
     {
-        AsyncDisplay::new(display_interface, self.display_size, self.rotation)
+        self.display(display_interface)
+    }
+
+    fn display<DI>(&self, display_interface: DI) -> AsyncDisplay<DI>
+    where
+        DI: AsyncWriteOnlyDataCommand,
+    {
+        let mut display = AsyncDisplay::new(display_interface, self.display_size, self.rotation);
+        display.set_recovery_policy(self.recovery);
+        display
     }
-}
\ No newline at end of file
+}
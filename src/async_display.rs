@@ -1,17 +1,136 @@
 //! Async container to store and set display properties
 
-use crate::async_interface::AsyncWriteOnlyDataCommand;
-use crate::command::Command;
+use crate::async_interface::{yield_now, AsyncWriteOnlyDataCommand};
+use crate::command::{Command, CommandBatch};
+use crate::display::{draw_area_batch, init_batch};
+use crate::geometry::{logical_dimensions, Area};
 use crate::properties::DisplayRotation;
 use crate::properties::DisplaySize;
 
+use core::future::{poll_fn, Future};
+use core::pin::pin;
+use core::task::Poll;
+
 use display_interface::{DataFormat, DisplayError};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::delay::DelayNs;
+
+/// Pulse the reset pin of the display, waiting on `delay`
+pub(crate) async fn hardware_reset<RST, DELAY>(
+    rst: &mut RST,
+    delay: &mut DELAY,
+) -> Result<(), RST::Error>
+where
+    RST: OutputPin,
+    DELAY: DelayNs,
+{
+    rst.set_high()?;
+    delay.delay_ms(1).await;
+    rst.set_low()?;
+    delay.delay_ms(10).await;
+    rst.set_high()?;
+    Ok(())
+}
+
+/// Error of an operation run with a timeout
+#[derive(Clone, Debug)]
+pub enum TimeoutError {
+    /// The operation didn't complete in time
+    TimedOut,
+    /// The operation failed
+    Display(DisplayError),
+}
+
+impl From<DisplayError> for TimeoutError {
+    fn from(error: DisplayError) -> Self {
+        TimeoutError::Display(error)
+    }
+}
+
+/// Run `op`, failing with `TimeoutError::TimedOut` if it takes longer than `timeout_ms`
+pub(crate) async fn timeout<DELAY, T>(
+    delay: &mut DELAY,
+    timeout_ms: u32,
+    op: impl Future<Output = Result<T, DisplayError>>,
+) -> Result<T, TimeoutError>
+where
+    DELAY: DelayNs,
+{
+    let (mut op, mut expired) = (pin!(op), pin!(delay.delay_ms(timeout_ms)));
+    poll_fn(|cx| {
+        if let Poll::Ready(result) = op.as_mut().poll(cx) {
+            return Poll::Ready(result.map_err(TimeoutError::Display));
+        }
+        expired
+            .as_mut()
+            .poll(cx)
+            .map(|_| Err(TimeoutError::TimedOut))
+    })
+    .await
+}
+
+/// Limit on how long each attempt of a recovered operation may take
+pub(crate) trait Deadline {
+    /// Error of an operation run within the limit
+    type Error: From<DisplayError>;
+
+    /// Run `op` within the limit
+    async fn run<T>(
+        &mut self,
+        op: impl Future<Output = Result<T, DisplayError>>,
+    ) -> Result<T, Self::Error>;
+}
+
+impl Deadline for () {
+    type Error = DisplayError;
+
+    async fn run<T>(
+        &mut self,
+        op: impl Future<Output = Result<T, DisplayError>>,
+    ) -> Result<T, DisplayError> {
+        op.await
+    }
+}
+
+impl<DELAY: DelayNs> Deadline for (&mut DELAY, u32) {
+    type Error = TimeoutError;
+
+    async fn run<T>(
+        &mut self,
+        op: impl Future<Output = Result<T, DisplayError>>,
+    ) -> Result<T, TimeoutError> {
+        timeout(self.0, self.1, op).await
+    }
+}
+
+/// What the async modes do when a flush fails, including when it times out
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RecoveryPolicy {
+    /// Return the error
+    #[default]
+    Report,
+    /// Try again, up to the given number of times, returning the last error if every attempt fails
+    Retry(u8),
+    /// Initialise the display again, restoring its size and rotation, then try once more. The
+    /// display isn't cleared, as the retried flush redraws every pixel, and the reset pin isn't
+    /// touched. A flush timeout limits the initialisation and the retried flush separately.
+    Reinit,
+}
 
 /// Async Display properties struct
+///
+/// Its futures, and those of the async modes built on it, are `Send` if the interface implements
+/// [`SendAsyncWriteOnlyDataCommand`](../async_interface/trait.SendAsyncWriteOnlyDataCommand.html).
+///
+/// Dropping a future halfway through a transfer leaves the display RAM position unknown. The
+/// next `draw` then sets the last draw area again first, and starts over at its beginning.
 pub struct AsyncDisplay<DI> {
     iface: DI,
     display_size: DisplaySize,
     display_rotation: DisplayRotation,
+    area: Area,
+    interrupted: bool,
+    recovery: RecoveryPolicy,
 }
 
 impl<DI> AsyncDisplay<DI>
@@ -28,6 +147,9 @@ where
             iface,
             display_size,
             display_rotation,
+            area: Area::panel(display_size),
+            interrupted: false,
+            recovery: RecoveryPolicy::Report,
         }
     }
 
@@ -39,50 +161,56 @@ where
     /// Initialise the display in column mode (i.e. a byte walks down a column of 8 pixels) with
     /// column 0 on the left and column _(display_width - 1)_ on the right.
     pub async fn init(&mut self) -> Result<(), DisplayError> {
-        let (_, display_height) = self.display_size.dimensions();
-
-        // TODO: Break up into nice bits so display modes can pick whathever they need
-        Command::CommandLock(0x12).send_async(&mut self.iface).await?;
-        Command::CommandLock(0xB1).send_async(&mut self.iface).await?;
-        Command::DisplayOn(false).send_async(&mut self.iface).await?;
-        Command::ClockDiv(0xF1).send_async(&mut self.iface).await?;
-        Command::MuxRatio(display_height - 1).send_async(&mut self.iface).await?;
-        Command::DisplayOffset(0).send_async(&mut self.iface).await?;
-        Command::StartLine(0).send_async(&mut self.iface).await?;
-        Command::SetGpio(0x00).send_async(&mut self.iface).await?;
-        Command::FunctionSelect(0x01).send_async(&mut self.iface).await?;
-        Command::SetVsl.send_async(&mut self.iface).await?;
-        Command::Contrast(0x8F).send_async(&mut self.iface).await?;
-        Command::ContrastCurrent(0x0F).send_async(&mut self.iface).await?;
-        Command::PreCharge(0x32).send_async(&mut self.iface).await?;
-        Command::PreCharge2(0x01).send_async(&mut self.iface).await?;
-        Command::Vcomh(0x05).send_async(&mut self.iface).await?;
-        Command::Invert(false).send_async(&mut self.iface).await?;
-
-        self.set_rotation(self.display_rotation).await?;
+        init_batch(self.display_size, self.display_rotation)?
+            .flush_async(&mut self.iface)
+            .await?;
         self.clear().await?;
         Command::DisplayOn(true).send_async(&mut self.iface).await?;
 
         Ok(())
     }
 
+    /// Send the init sequence and turn the display on, leaving the display RAM as it is
+    async fn reinit(&mut self) -> Result<(), DisplayError> {
+        init_batch(self.display_size, self.display_rotation)?
+            .flush_async(&mut self.iface)
+            .await?;
+        Command::DisplayOn(true).send_async(&mut self.iface).await
+    }
+
     /// Clear the display by setting all pixels to black
     pub async fn clear(&mut self) -> Result<(), DisplayError> {
         let (display_width, display_height) = self.display_size.dimensions();
-        self.set_draw_area((0, 0), (display_width, display_height)).await?;
+        self.set_area(&Area::panel(self.display_size)).await?;
+        self.interrupted = true;
         for _ in 0..(display_height as u32 * display_width as u32) {
             self.iface.send_data(DataFormat::U8(&[0x00, 0x00])).await?; // send 8 * 2 bits
         }
+        self.interrupted = false;
         Ok(())
     }
 
     /// Set the position in the framebuffer of the display where any sent data should be
     /// drawn. This method can be used for changing the affected area on the screen as well
     /// as (re-)setting the start point of the next `draw` call.
-    pub async fn set_draw_area(&mut self, start: (u8, u8), end: (u8, u8)) -> Result<(), DisplayError> {
-        Command::Column(start.0, end.0 - 1).send_async(&mut self.iface).await?;
-        Command::Row(start.1, end.1 - 1).send_async(&mut self.iface).await?;
-        Command::WriteRam.send_async(&mut self.iface).await?;
+    ///
+    /// `end` is exclusive. An empty area or one exceeding the display RAM is reported as
+    /// `DisplayError::OutOfBoundsError`.
+    pub async fn set_draw_area(
+        &mut self,
+        start: (u8, u8),
+        end: (u8, u8),
+    ) -> Result<(), DisplayError> {
+        self.set_area(&Area::from_bounds(start, end)?).await
+    }
+
+    /// Set the draw area of the display, cf. `set_draw_area`
+    pub async fn set_area(&mut self, area: &Area) -> Result<(), DisplayError> {
+        let mut batch = draw_area_batch(area)?;
+        self.area = *area;
+        self.interrupted = true;
+        batch.flush_async(&mut self.iface).await?;
+        self.interrupted = false;
         Ok(())
     }
 
@@ -90,7 +218,39 @@ where
     /// and advance the position accordingly. Cf. `set_draw_area` to modify the affected area by
     /// this method.
     pub async fn draw(&mut self, buffer: &[u8]) -> Result<(), DisplayError> {
+        self.resume().await?;
+        self.interrupted = true;
         self.iface.send_data(DataFormat::U8(buffer)).await?;
+        self.interrupted = false;
+        Ok(())
+    }
+
+    /// Send the data to the display as `draw`, `chunk_bytes` at a time, yielding to the executor
+    /// between chunks so other tasks get to run during large transfers. A `chunk_bytes` of 0 is
+    /// treated as 1.
+    pub async fn draw_chunked(
+        &mut self,
+        buffer: &[u8],
+        chunk_bytes: usize,
+    ) -> Result<(), DisplayError> {
+        self.resume().await?;
+        self.interrupted = true;
+        for (i, chunk) in buffer.chunks(chunk_bytes.max(1)).enumerate() {
+            if i > 0 {
+                yield_now().await;
+            }
+            self.iface.send_data(DataFormat::U8(chunk)).await?;
+        }
+        self.interrupted = false;
+        Ok(())
+    }
+
+    /// Set the draw area again if a transfer was dropped halfway through
+    async fn resume(&mut self) -> Result<(), DisplayError> {
+        if self.interrupted {
+            let area = self.area;
+            self.set_area(&area).await?;
+        }
         Ok(())
     }
 
@@ -101,12 +261,7 @@ where
 
     /// Get display dimensions, taking into account the current rotation of the display
     pub fn get_dimensions(&self) -> (u8, u8) {
-        let (w, h) = self.display_size.dimensions();
-
-        match self.display_rotation {
-            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (w, h),
-            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => (h, w),
-        }
+        logical_dimensions(self.display_size, self.display_rotation)
     }
 
     /// Get the display rotation
@@ -115,24 +270,97 @@ where
     }
 
     /// Set the display rotation
-    pub async fn set_rotation(&mut self, display_rotation: DisplayRotation) -> Result<(), DisplayError> {
+    pub async fn set_rotation(
+        &mut self,
+        display_rotation: DisplayRotation,
+    ) -> Result<(), DisplayError> {
         self.display_rotation = display_rotation;
 
-        match display_rotation {
-            DisplayRotation::Rotate0 => {
-                Command::SetRemap(false, false, true).send_async(&mut self.iface).await?;
-            }
-            DisplayRotation::Rotate90 => {
-                Command::SetRemap(true, true, true).send_async(&mut self.iface).await?;
-            }
-            DisplayRotation::Rotate180 => {
-                Command::SetRemap(false, true, false).send_async(&mut self.iface).await?;
+        let mut batch = CommandBatch::<4>::new();
+        batch.push(display_rotation.remap())?;
+        batch.flush_async(&mut self.iface).await
+    }
+
+    /// Get the recovery policy the async modes apply to flushes
+    pub fn get_recovery_policy(&self) -> RecoveryPolicy {
+        self.recovery
+    }
+
+    /// Set the recovery policy the async modes apply to flushes
+    pub fn set_recovery_policy(&mut self, recovery: RecoveryPolicy) {
+        self.recovery = recovery;
+    }
+
+    /// Run `op` on the display, failing with `TimeoutError::TimedOut` if it doesn't complete
+    /// within `timeout_ms` milliseconds as measured by `delay`. The operation is dropped on timeout,
+    /// and the next `draw` sets the draw area again first.
+    ///
+    /// ```rust,ignore
+    /// display.with_timeout(&mut delay, 100, async |display| display.init().await).await?;
+    /// ```
+    pub async fn with_timeout<DELAY, T>(
+        &mut self,
+        delay: &mut DELAY,
+        timeout_ms: u32,
+        op: impl AsyncFnOnce(&mut Self) -> Result<T, DisplayError>,
+    ) -> Result<T, TimeoutError>
+    where
+        DELAY: DelayNs,
+    {
+        timeout(delay, timeout_ms, op(self)).await
+    }
+
+    /// Send a full frame from `buffer`, `chunk_bytes` at a time if given, each attempt within
+    /// `deadline`, recovering from errors as the recovery policy says. A buffer not holding
+    /// exactly one frame is reported as `DisplayError::OutOfBoundsError` before anything is sent.
+    pub(crate) async fn flush_frame<L>(
+        &mut self,
+        deadline: &mut L,
+        buffer: &[u8],
+        chunk_bytes: Option<usize>,
+    ) -> Result<(), L::Error>
+    where
+        L: Deadline,
+    {
+        if buffer.len() != self.display_size.buffer_size() {
+            return Err(DisplayError::OutOfBoundsError.into());
+        }
+
+        let mut result = deadline.run(self.send_frame(buffer, chunk_bytes)).await;
+        match self.recovery {
+            RecoveryPolicy::Report => {}
+            RecoveryPolicy::Retry(retries) => {
+                for _ in 0..retries {
+                    if result.is_ok() {
+                        break;
+                    }
+                    result = deadline.run(self.send_frame(buffer, chunk_bytes)).await;
+                }
             }
-            DisplayRotation::Rotate270 => {
-                Command::SetRemap(true, false, false).send_async(&mut self.iface).await?;
+            RecoveryPolicy::Reinit => {
+                if result.is_err() {
+                    deadline.run(self.reinit()).await?;
+                    result = deadline.run(self.send_frame(buffer, chunk_bytes)).await;
+                }
             }
-        };
+        }
+        result
+    }
 
-        Ok(())
+    async fn send_frame(
+        &mut self,
+        buffer: &[u8],
+        chunk_bytes: Option<usize>,
+    ) -> Result<(), DisplayError> {
+        self.set_area(&Area::panel(self.display_size)).await?;
+        match chunk_bytes {
+            Some(chunk_bytes) => self.draw_chunked(buffer, chunk_bytes).await,
+            None => self.draw(buffer).await,
+        }
     }
-}
\ No newline at end of file
+
+    /// Set the display RAM row shown at the top of the display, scrolling the display vertically
+    pub async fn set_start_line(&mut self, line: u8) -> Result<(), DisplayError> {
+        Command::StartLine(line).send_async(&mut self.iface).await
+    }
+}
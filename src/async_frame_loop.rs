@@ -0,0 +1,204 @@
+//! Frame pacing for async render loops
+//!
+//! [`FrameLoop`] renders and flushes the frames of an async display mode at a target frame rate.
+//! Frames are scheduled on a fixed grid, so the rate doesn't drift with the time taken by
+//! rendering and flushing. When a frame overruns, the frames it missed are skipped rather than
+//! rendered back to back. Time is measured by any [`Monotonic`] clock.
+//!
+//! Each frame ends as the mode's [`FrameFlush`] says: the buffered modes send their framebuffer,
+//! while the direct mode has drawn straight to the display and has nothing left to send.
+//!
+//! ```rust,ignore
+//! let mut frames = FrameLoop::new(EmbassyClock, 30);
+//! loop {
+//!     frames
+//!         .frame(&mut display, async |display| draw_scene(display))
+//!         .await?;
+//!     if frames.stats().frames % 30 == 0 {
+//!         info!("{} fps", frames.stats().fps());
+//!     }
+//! }
+//! ```
+
+use core::future::Future;
+
+use crate::async_interface::AsyncWriteOnlyDataCommand;
+use crate::mode::async_buffered_graphics::AsyncBufferedGraphicsMode;
+use crate::mode::async_double_buffered::AsyncDoubleBufferedGraphicsMode;
+use crate::mode::async_graphics::AsyncDirectGraphicsMode;
+use display_interface::DisplayError;
+
+/// Monotonic clock measuring and waiting for frames
+pub trait Monotonic {
+    /// Microseconds since a fixed point in time
+    fn now_us(&self) -> u64;
+
+    /// Wait until `now_us` reaches `deadline_us`, returning right away if it has
+    fn wait_until(&mut self, deadline_us: u64) -> impl Future<Output = ()>;
+}
+
+/// Display mode sending a rendered frame to the display at the end of a [`FrameLoop`] frame
+pub trait FrameFlush {
+    /// Send what was rendered since the last call to the display
+    fn flush_frame(&mut self) -> impl Future<Output = Result<(), DisplayError>>;
+}
+
+impl<DI, BUF> FrameFlush for AsyncBufferedGraphicsMode<DI, BUF>
+where
+    DI: AsyncWriteOnlyDataCommand,
+    BUF: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Flush the framebuffer
+    fn flush_frame(&mut self) -> impl Future<Output = Result<(), DisplayError>> {
+        self.flush()
+    }
+}
+
+impl<DI, BUF> FrameFlush for AsyncDoubleBufferedGraphicsMode<DI, BUF>
+where
+    DI: AsyncWriteOnlyDataCommand,
+    BUF: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Swap the buffers and send the frame rendered into the back buffer
+    async fn flush_frame(&mut self) -> Result<(), DisplayError> {
+        self.swap_and_flush(|_| ()).await.1
+    }
+}
+
+impl<DI, const CHUNK: usize> FrameFlush for AsyncDirectGraphicsMode<DI, CHUNK>
+where
+    DI: AsyncWriteOnlyDataCommand,
+{
+    /// Nothing to send, the frame was drawn straight to the display
+    fn flush_frame(&mut self) -> impl Future<Output = Result<(), DisplayError>> {
+        core::future::ready(Ok(()))
+    }
+}
+
+/// Monotonic clock on the Embassy timer
+#[cfg(feature = "embassy")]
+pub struct EmbassyClock;
+
+#[cfg(feature = "embassy")]
+impl Monotonic for EmbassyClock {
+    fn now_us(&self) -> u64 {
+        embassy_time::Instant::now().as_micros()
+    }
+
+    fn wait_until(&mut self, deadline_us: u64) -> impl Future<Output = ()> {
+        embassy_time::Timer::at(embassy_time::Instant::from_micros(deadline_us))
+    }
+}
+
+/// Timings of a [`FrameLoop`], in microseconds
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    /// Frames rendered
+    pub frames: u32,
+    /// Frames skipped because an earlier frame overran
+    pub skipped: u32,
+    /// Time the last frame took to render
+    pub render_us: u32,
+    /// Time the last frame took to flush
+    pub flush_us: u32,
+    /// Time between the starts of the last two frames
+    pub frame_us: u32,
+    /// Moving average of `frame_us`
+    pub average_frame_us: u32,
+}
+
+impl FrameStats {
+    /// Frames per second, from the average frame time. 0 until two frames were rendered.
+    pub fn fps(&self) -> u32 {
+        1_000_000u32.checked_div(self.average_frame_us).unwrap_or(0)
+    }
+}
+
+/// Render loop pacing the frames of a display, cf. the
+/// [module documentation](index.html)
+pub struct FrameLoop<C> {
+    clock: C,
+    period_us: u64,
+    next_us: Option<u64>,
+    last_start_us: Option<u64>,
+    stats: FrameStats,
+}
+
+impl<C> FrameLoop<C>
+where
+    C: Monotonic,
+{
+    /// Create a loop rendering `fps` frames per second, measured by `clock`. An `fps` of 0 is
+    /// treated as 1, and rates above 1,000,000 as 1,000,000.
+    pub fn new(clock: C, fps: u32) -> Self {
+        FrameLoop {
+            clock,
+            period_us: (1_000_000 / fps.max(1) as u64).max(1),
+            next_us: None,
+            last_start_us: None,
+            stats: FrameStats::default(),
+        }
+    }
+
+    /// Release the clock
+    pub fn release(self) -> C {
+        self.clock
+    }
+
+    /// Timings of the frames so far
+    pub fn stats(&self) -> FrameStats {
+        self.stats
+    }
+
+    /// Wait for the next frame, then draw it with `render` and flush it, returning what `render`
+    /// returned. If the loop is behind by a frame or more, the missed frames are skipped and the
+    /// frame starts right away.
+    ///
+    /// `render` is async so that the direct mode can await its draws. With a double buffered
+    /// display, `render` draws into [`back`](AsyncDoubleBufferedGraphicsMode::back), which is
+    /// sent once it returns.
+    pub async fn frame<D, R>(
+        &mut self,
+        display: &mut D,
+        render: impl AsyncFnOnce(&mut D) -> R,
+    ) -> Result<R, DisplayError>
+    where
+        D: FrameFlush,
+    {
+        let now = self.clock.now_us();
+        let mut deadline = self.next_us.unwrap_or(now);
+        if now < deadline {
+            self.clock.wait_until(deadline).await;
+        } else {
+            let missed = (now - deadline) / self.period_us;
+            self.stats.skipped = self.stats.skipped.saturating_add(missed as u32);
+            deadline += missed * self.period_us;
+        }
+        self.next_us = Some(deadline + self.period_us);
+
+        let start = self.clock.now_us();
+        let result = render(display).await;
+        let rendered = self.clock.now_us();
+        let flushed = display.flush_frame().await;
+        let end = self.clock.now_us();
+
+        self.stats.frames = self.stats.frames.saturating_add(1);
+        self.stats.render_us = elapsed(start, rendered);
+        self.stats.flush_us = elapsed(rendered, end);
+        if let Some(last_start) = self.last_start_us {
+            let frame_us = elapsed(last_start, start);
+            self.stats.frame_us = frame_us;
+            self.stats.average_frame_us = match self.stats.average_frame_us {
+                0 => frame_us,
+                average => average - average / 8 + frame_us / 8,
+            };
+        }
+        self.last_start_us = Some(start);
+
+        flushed.map(|_| result)
+    }
+}
+
+fn elapsed(from: u64, to: u64) -> u32 {
+    to.saturating_sub(from).min(u32::MAX as u64) as u32
+}
@@ -1,29 +1,352 @@
 //! Async display interface traits for Embassy compatibility
+//!
+//! [`AsyncWriteOnlyDataCommand`] makes no promise about its futures, which suits single threaded
+//! executors such as Embassy's. Interfaces whose futures are `Send` implement
+//! [`SendAsyncWriteOnlyDataCommand`] instead and get [`AsyncWriteOnlyDataCommand`] for free. All
+//! async modes then return `Send` futures for them, also in generic code bounded on
+//! [`SendAsyncWriteOnlyDataCommand`], so the display can be driven from a work stealing executor
+//! or held across `.await` in a `Send` task.
+//!
+//! `DataFormat`'s iterator variants borrow `dyn Iterator`, which isn't `Send`, so a `Send` future
+//! can't keep them across an `.await`. The driver only ever sends `DataFormat::U8`; interfaces
+//! typically report the other formats as `DisplayError::DataFormatNotImplemented`.
+//!
+//! Blocking `WriteOnlyDataCommand` interfaces, such as `display_interface_spi::SPIInterface`, are
+//! used through [`BlockingAdapter`].
+//!
+//! With the `alloc` feature, [`DynInterface`] and [`DynSendInterface`] box interfaces of
+//! different types behind one type.
 
-use display_interface::{DataFormat, DisplayError};
+use core::future::{poll_fn, Future};
+use core::task::Poll;
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
 
 /// Async version of WriteOnlyDataCommand trait for non-blocking communication
-/// 
+///
 /// This trait should be implemented by your async SPI interface to enable
-/// non-blocking communication with the SSD1351 display.
+/// non-blocking communication with the SSD1351 display. Implementations may use `async fn`.
 pub trait AsyncWriteOnlyDataCommand {
     /// Send commands to the display
-    async fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError>;
-    
+    fn send_commands(
+        &mut self,
+        cmd: DataFormat<'_>,
+    ) -> impl Future<Output = Result<(), DisplayError>>;
+
+    /// Send data to the display
+    fn send_data(&mut self, buf: DataFormat<'_>) -> impl Future<Output = Result<(), DisplayError>>;
+}
+
+/// Variant of [`AsyncWriteOnlyDataCommand`] whose futures are `Send`
+pub trait SendAsyncWriteOnlyDataCommand {
+    /// Send commands to the display
+    fn send_commands(
+        &mut self,
+        cmd: DataFormat<'_>,
+    ) -> impl Future<Output = Result<(), DisplayError>> + Send;
+
     /// Send data to the display
-    async fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError>;
+    fn send_data(
+        &mut self,
+        buf: DataFormat<'_>,
+    ) -> impl Future<Output = Result<(), DisplayError>> + Send;
 }
 
-// Provide a blanket implementation for compatibility with existing sync interfaces
 impl<T> AsyncWriteOnlyDataCommand for T
 where
-    T: display_interface::WriteOnlyDataCommand,
+    T: SendAsyncWriteOnlyDataCommand,
 {
-    async fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
-        display_interface::WriteOnlyDataCommand::send_commands(self, cmd)
+    fn send_commands(
+        &mut self,
+        cmd: DataFormat<'_>,
+    ) -> impl Future<Output = Result<(), DisplayError>> {
+        SendAsyncWriteOnlyDataCommand::send_commands(self, cmd)
     }
-    
-    async fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
-        display_interface::WriteOnlyDataCommand::send_data(self, buf)
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> impl Future<Output = Result<(), DisplayError>> {
+        SendAsyncWriteOnlyDataCommand::send_data(self, buf)
+    }
+}
+
+/// Adapter running a blocking `WriteOnlyDataCommand` interface behind the async traits
+///
+/// Every transfer blocks the executor until it is done. [`BlockingAdapter::new`] sends data in
+/// one go, and its futures are `Send` for any interface. [`BlockingAdapter::yielding`] splits data
+/// into chunks and yields to the executor between them, so large flushes don't starve other
+/// tasks; its futures are `Send` if the interface is.
+///
+/// ```rust
+/// # use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+/// # struct FakeInterface;
+/// # impl WriteOnlyDataCommand for FakeInterface {
+/// #     fn send_commands(&mut self, _: DataFormat<'_>) -> Result<(), DisplayError> { Ok(()) }
+/// #     fn send_data(&mut self, _: DataFormat<'_>) -> Result<(), DisplayError> { Ok(()) }
+/// # }
+/// use ssd1351::async_builder::AsyncBuilder;
+/// use ssd1351::async_interface::BlockingAdapter;
+/// use ssd1351::mode::AsyncGraphicsMode;
+///
+/// let display: AsyncGraphicsMode<_> =
+///     AsyncBuilder::new().connect_interface(BlockingAdapter::new(FakeInterface));
+/// // Yield to the executor after every 1 KiB of pixel data
+/// let display: AsyncGraphicsMode<_> =
+///     AsyncBuilder::new().connect_interface(BlockingAdapter::yielding(FakeInterface, 1024));
+/// ```
+pub struct BlockingAdapter<DI, Y = NoYield> {
+    iface: DI,
+    yielding: Y,
+}
+
+/// [`BlockingAdapter`] sending data in one go
+pub struct NoYield;
+
+/// [`BlockingAdapter`] yielding to the executor between chunks of data
+pub struct Yielding {
+    chunk: usize,
+}
+
+impl<DI> BlockingAdapter<DI> {
+    /// Wrap `iface`, sending data in one go
+    pub fn new(iface: DI) -> Self {
+        BlockingAdapter {
+            iface,
+            yielding: NoYield,
+        }
+    }
+}
+
+impl<DI> BlockingAdapter<DI, Yielding> {
+    /// Wrap `iface`, sending data in chunks of `chunk` bytes and yielding to the executor between
+    /// them. A `chunk` of 0 is treated as 1.
+    pub fn yielding(iface: DI, chunk: usize) -> Self {
+        BlockingAdapter {
+            iface,
+            yielding: Yielding {
+                chunk: chunk.max(1),
+            },
+        }
     }
-}
\ No newline at end of file
+}
+
+impl<DI, Y> BlockingAdapter<DI, Y> {
+    /// Release the wrapped interface
+    pub fn release(self) -> DI {
+        self.iface
+    }
+}
+
+// The transfer happens before the future is returned, so the future holds nothing but the result
+// and is `Send`.
+impl<DI> SendAsyncWriteOnlyDataCommand for BlockingAdapter<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    fn send_commands(
+        &mut self,
+        cmd: DataFormat<'_>,
+    ) -> impl Future<Output = Result<(), DisplayError>> + Send {
+        let result = self.iface.send_commands(cmd);
+        async move { result }
+    }
+
+    fn send_data(
+        &mut self,
+        buf: DataFormat<'_>,
+    ) -> impl Future<Output = Result<(), DisplayError>> + Send {
+        let result = self.iface.send_data(buf);
+        async move { result }
+    }
+}
+
+impl<DI> AsyncWriteOnlyDataCommand for BlockingAdapter<DI, Yielding>
+where
+    DI: WriteOnlyDataCommand,
+{
+    fn send_commands(
+        &mut self,
+        cmd: DataFormat<'_>,
+    ) -> impl Future<Output = Result<(), DisplayError>> {
+        let result = self.iface.send_commands(cmd);
+        async move { result }
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> impl Future<Output = Result<(), DisplayError>> {
+        // Only byte slices are split, other formats are sent right away so the future doesn't
+        // hold on to them
+        let bytes = match buf {
+            DataFormat::U8(bytes) => Ok(bytes),
+            buf => Err(self.iface.send_data(buf)),
+        };
+        async move {
+            let bytes = match bytes {
+                Ok(bytes) => bytes,
+                Err(result) => return result,
+            };
+            for (i, chunk) in bytes.chunks(self.yielding.chunk).enumerate() {
+                if i > 0 {
+                    yield_now().await;
+                }
+                self.iface.send_data(DataFormat::U8(chunk))?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Let the executor run other tasks before continuing
+pub(crate) async fn yield_now() {
+    let mut yielded = false;
+    poll_fn(|cx| {
+        if yielded {
+            Poll::Ready(())
+        } else {
+            yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+#[cfg(feature = "alloc")]
+pub use self::dyn_interface::{DynInterface, DynSendInterface};
+
+#[cfg(feature = "alloc")]
+mod dyn_interface {
+    use alloc::boxed::Box;
+    use core::future::Future;
+    use core::pin::Pin;
+
+    use super::{AsyncWriteOnlyDataCommand, SendAsyncWriteOnlyDataCommand};
+    use display_interface::{DataFormat, DisplayError};
+
+    type BoxFuture<'a> = Pin<Box<dyn Future<Output = Result<(), DisplayError>> + 'a>>;
+    type SendBoxFuture<'a> = Pin<Box<dyn Future<Output = Result<(), DisplayError>> + Send + 'a>>;
+
+    /// Object safe form of [`AsyncWriteOnlyDataCommand`], boxing the futures
+    trait ErasedInterface {
+        fn send_commands<'a: 'c, 'b: 'c, 'c>(&'a mut self, cmd: DataFormat<'b>) -> BoxFuture<'c>;
+        fn send_data<'a: 'c, 'b: 'c, 'c>(&'a mut self, buf: DataFormat<'b>) -> BoxFuture<'c>;
+    }
+
+    impl<T: AsyncWriteOnlyDataCommand> ErasedInterface for T {
+        fn send_commands<'a: 'c, 'b: 'c, 'c>(&'a mut self, cmd: DataFormat<'b>) -> BoxFuture<'c> {
+            Box::pin(AsyncWriteOnlyDataCommand::send_commands(self, cmd))
+        }
+
+        fn send_data<'a: 'c, 'b: 'c, 'c>(&'a mut self, buf: DataFormat<'b>) -> BoxFuture<'c> {
+            Box::pin(AsyncWriteOnlyDataCommand::send_data(self, buf))
+        }
+    }
+
+    /// Object safe form of [`SendAsyncWriteOnlyDataCommand`], boxing the futures
+    trait ErasedSendInterface: Send {
+        fn send_commands<'a: 'c, 'b: 'c, 'c>(
+            &'a mut self,
+            cmd: DataFormat<'b>,
+        ) -> SendBoxFuture<'c>;
+        fn send_data<'a: 'c, 'b: 'c, 'c>(&'a mut self, buf: DataFormat<'b>) -> SendBoxFuture<'c>;
+    }
+
+    impl<T: SendAsyncWriteOnlyDataCommand + Send> ErasedSendInterface for T {
+        fn send_commands<'a: 'c, 'b: 'c, 'c>(
+            &'a mut self,
+            cmd: DataFormat<'b>,
+        ) -> SendBoxFuture<'c> {
+            Box::pin(SendAsyncWriteOnlyDataCommand::send_commands(self, cmd))
+        }
+
+        fn send_data<'a: 'c, 'b: 'c, 'c>(&'a mut self, buf: DataFormat<'b>) -> SendBoxFuture<'c> {
+            Box::pin(SendAsyncWriteOnlyDataCommand::send_data(self, buf))
+        }
+    }
+
+    /// Boxed interface of any type implementing [`AsyncWriteOnlyDataCommand`], so displays on
+    /// different interfaces share one type. Every transfer allocates its future.
+    pub struct DynInterface<'a> {
+        inner: Box<dyn ErasedInterface + 'a>,
+    }
+
+    impl<'a> DynInterface<'a> {
+        /// Box `interface`
+        pub fn new<DI: AsyncWriteOnlyDataCommand + 'a>(interface: DI) -> Self {
+            DynInterface {
+                inner: Box::new(interface),
+            }
+        }
+    }
+
+    impl AsyncWriteOnlyDataCommand for DynInterface<'_> {
+        async fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+            self.inner.send_commands(cmd).await
+        }
+
+        async fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+            self.inner.send_data(buf).await
+        }
+    }
+
+    /// Boxed interface of any `Send` type implementing [`SendAsyncWriteOnlyDataCommand`], cf.
+    /// [`DynInterface`]. Displays using it are `Send` and return `Send` futures. The iterator
+    /// formats of `DataFormat` are reported as `DisplayError::DataFormatNotImplemented`.
+    pub struct DynSendInterface<'a> {
+        inner: Box<dyn ErasedSendInterface + 'a>,
+    }
+
+    impl<'a> DynSendInterface<'a> {
+        /// Box `interface`
+        pub fn new<DI: SendAsyncWriteOnlyDataCommand + Send + 'a>(interface: DI) -> Self {
+            DynSendInterface {
+                inner: Box::new(interface),
+            }
+        }
+    }
+
+    impl SendAsyncWriteOnlyDataCommand for DynSendInterface<'_> {
+        fn send_commands(
+            &mut self,
+            cmd: DataFormat<'_>,
+        ) -> impl Future<Output = Result<(), DisplayError>> + Send {
+            let cmd = SliceFormat::new(cmd);
+            async move { self.inner.send_commands(cmd?.into_format()).await }
+        }
+
+        fn send_data(
+            &mut self,
+            buf: DataFormat<'_>,
+        ) -> impl Future<Output = Result<(), DisplayError>> + Send {
+            let buf = SliceFormat::new(buf);
+            async move { self.inner.send_data(buf?.into_format()).await }
+        }
+    }
+
+    /// The `Send` formats of `DataFormat`, those over slices
+    enum SliceFormat<'a> {
+        U8(&'a [u8]),
+        U16(&'a [u16]),
+        U16BE(&'a mut [u16]),
+        U16LE(&'a mut [u16]),
+    }
+
+    impl<'a> SliceFormat<'a> {
+        /// Fails with `DataFormatNotImplemented` for the iterator formats
+        fn new(format: DataFormat<'a>) -> Result<Self, DisplayError> {
+            match format {
+                DataFormat::U8(bytes) => Ok(SliceFormat::U8(bytes)),
+                DataFormat::U16(words) => Ok(SliceFormat::U16(words)),
+                DataFormat::U16BE(words) => Ok(SliceFormat::U16BE(words)),
+                DataFormat::U16LE(words) => Ok(SliceFormat::U16LE(words)),
+                _ => Err(DisplayError::DataFormatNotImplemented),
+            }
+        }
+
+        fn into_format(self) -> DataFormat<'a> {
+            match self {
+                SliceFormat::U8(bytes) => DataFormat::U8(bytes),
+                SliceFormat::U16(words) => DataFormat::U16(words),
+                SliceFormat::U16BE(words) => DataFormat::U16BE(words),
+                SliceFormat::U16LE(words) => DataFormat::U16LE(words),
+            }
+        }
+    }
+}
@@ -0,0 +1,243 @@
+//! Framebuffer shared between async tasks, each drawing into a region of the display
+//!
+//! [`SharedDisplay`] puts an [`AsyncBufferedGraphicsMode`] behind an `embassy-sync` mutex. Tasks
+//! draw through [`Region`] handles: a locked region is a `DrawTarget` with its origin at the top
+//! left corner of the region, clipping everything drawn outside it. The rows changed by all
+//! regions are merged, and sent to the display by one owner task running
+//! [`run`](SharedDisplay::run), or on demand with [`flush`](SharedDisplay::flush).
+//!
+//! ```rust,ignore
+//! static DISPLAY: StaticCell<SharedDisplay<CriticalSectionRawMutex, Interface, [u8; FB_SIZE]>> =
+//!     StaticCell::new();
+//!
+//! display.init().await?;
+//! let shared = DISPLAY.init(SharedDisplay::new(display));
+//!
+//! spawner.must_spawn(clock(shared.region(Rectangle::new(Point::zero(), Size::new(128, 16)))));
+//! spawner.must_spawn(sensors(shared.region(Rectangle::new(Point::new(0, 16), Size::new(128, 96)))));
+//! // Send the changes of all regions as they come in
+//! shared.run().await?;
+//!
+//! #[embassy_executor::task]
+//! async fn clock(region: Region<'static, CriticalSectionRawMutex, Interface, [u8; FB_SIZE]>) {
+//!     loop {
+//!         let mut target = region.lock().await;
+//!         Text::new(&time(), Point::new(0, 10), style).draw(&mut target).unwrap();
+//!         drop(target);
+//!         Timer::after_secs(1).await;
+//!     }
+//! }
+//! ```
+
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::mutex::{Mutex, MutexGuard};
+use embassy_sync::signal::Signal;
+use embedded_graphics_core::pixelcolor::Rgb565;
+use embedded_graphics_core::prelude::{Dimensions, DrawTarget, OriginDimensions, Pixel, Size};
+use embedded_graphics_core::primitives::Rectangle;
+
+use crate::async_interface::AsyncWriteOnlyDataCommand;
+use crate::mode::async_buffered_graphics::AsyncBufferedGraphicsMode;
+use display_interface::DisplayError;
+
+/// Framebuffer and the rows changed since the last flush
+struct Inner<DI, BUF>
+where
+    DI: AsyncWriteOnlyDataCommand,
+{
+    display: AsyncBufferedGraphicsMode<DI, BUF>,
+    /// First and one past the last changed row
+    dirty: Option<(u8, u8)>,
+}
+
+/// Buffered display shared between tasks, cf. the [module documentation](index.html)
+pub struct SharedDisplay<M, DI, BUF>
+where
+    M: RawMutex,
+    DI: AsyncWriteOnlyDataCommand,
+{
+    inner: Mutex<M, Inner<DI, BUF>>,
+    changed: Signal<M, ()>,
+    bounds: Rectangle,
+}
+
+impl<M, DI, BUF> SharedDisplay<M, DI, BUF>
+where
+    M: RawMutex,
+    DI: AsyncWriteOnlyDataCommand,
+    BUF: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Share `display`. It should be initialised, and its framebuffer flushed, beforehand: only
+    /// rows drawn through regions are sent from then on.
+    pub fn new(display: AsyncBufferedGraphicsMode<DI, BUF>) -> Self {
+        let bounds = display.bounding_box();
+        SharedDisplay {
+            inner: Mutex::new(Inner {
+                display,
+                dirty: None,
+            }),
+            changed: Signal::new(),
+            bounds,
+        }
+    }
+
+    /// Stop sharing the display and release it
+    pub fn into_inner(self) -> AsyncBufferedGraphicsMode<DI, BUF> {
+        self.inner.into_inner().display
+    }
+
+    /// Handle for drawing into `area` of the display. Parts of the area outside the display are
+    /// clipped.
+    pub fn region(&self, area: Rectangle) -> Region<'_, M, DI, BUF> {
+        Region {
+            shared: self,
+            area,
+            clip: area.intersection(&self.bounds),
+        }
+    }
+
+    /// Send the rows changed since the last flush to the display. Rows that fail to send are sent
+    /// again on the next flush.
+    pub async fn flush(&self) -> Result<(), DisplayError> {
+        let mut inner = self.inner.lock().await;
+        if let Some((top, bottom)) = inner.dirty {
+            inner.display.flush_rows(top, bottom - top).await?;
+            inner.dirty = None;
+        }
+        Ok(())
+    }
+
+    /// Wait until a region draws something
+    pub async fn wait_for_changes(&self) {
+        self.changed.wait().await
+    }
+
+    /// Flush the changes of all regions as they come in, until a flush fails. Meant to be run by
+    /// the task owning the display.
+    pub async fn run(&self) -> Result<(), DisplayError> {
+        loop {
+            self.wait_for_changes().await;
+            self.flush().await?;
+        }
+    }
+}
+
+/// Handle for drawing into a region of a [`SharedDisplay`]
+pub struct Region<'a, M, DI, BUF>
+where
+    M: RawMutex,
+    DI: AsyncWriteOnlyDataCommand,
+{
+    shared: &'a SharedDisplay<M, DI, BUF>,
+    area: Rectangle,
+    clip: Rectangle,
+}
+
+impl<M, DI, BUF> Clone for Region<'_, M, DI, BUF>
+where
+    M: RawMutex,
+    DI: AsyncWriteOnlyDataCommand,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M, DI, BUF> Copy for Region<'_, M, DI, BUF>
+where
+    M: RawMutex,
+    DI: AsyncWriteOnlyDataCommand,
+{
+}
+
+impl<'a, M, DI, BUF> Region<'a, M, DI, BUF>
+where
+    M: RawMutex,
+    DI: AsyncWriteOnlyDataCommand,
+    BUF: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// The area of the display this region covers
+    pub fn area(&self) -> Rectangle {
+        self.area
+    }
+
+    /// Lock the display to draw into the region. Other tasks wait until the returned target is
+    /// dropped.
+    pub async fn lock(&self) -> RegionTarget<'a, M, DI, BUF> {
+        RegionTarget {
+            inner: self.shared.inner.lock().await,
+            changed: &self.shared.changed,
+            area: self.area,
+            clip: self.clip,
+        }
+    }
+
+    /// Send the rows changed by all regions to the display, cf. [`SharedDisplay::flush`]
+    pub async fn flush(&self) -> Result<(), DisplayError> {
+        self.shared.flush().await
+    }
+}
+
+/// Region of a [`SharedDisplay`] locked for drawing, with its origin at the top left corner of
+/// the region
+pub struct RegionTarget<'a, M, DI, BUF>
+where
+    M: RawMutex,
+    DI: AsyncWriteOnlyDataCommand,
+{
+    inner: MutexGuard<'a, M, Inner<DI, BUF>>,
+    changed: &'a Signal<M, ()>,
+    area: Rectangle,
+    clip: Rectangle,
+}
+
+impl<M, DI, BUF> DrawTarget for RegionTarget<'_, M, DI, BUF>
+where
+    M: RawMutex,
+    DI: AsyncWriteOnlyDataCommand,
+    BUF: AsRef<[u8]> + AsMut<[u8]>,
+{
+    type Color = Rgb565;
+    type Error = ();
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let (origin, clip) = (self.area.top_left, self.clip);
+        let mut rows: Option<(i32, i32)> = None;
+        let pixels = pixels
+            .into_iter()
+            .map(|Pixel(pos, color)| Pixel(pos + origin, color))
+            .filter(|Pixel(pos, _)| clip.contains(*pos))
+            .inspect(|Pixel(pos, _)| {
+                rows = Some(match rows {
+                    Some((top, bottom)) => (top.min(pos.y), bottom.max(pos.y + 1)),
+                    None => (pos.y, pos.y + 1),
+                })
+            });
+
+        let inner = &mut *self.inner;
+        inner.display.draw_iter(pixels)?;
+
+        if let Some((top, bottom)) = rows {
+            let (top, bottom) = (top as u8, bottom as u8);
+            inner.dirty = Some(match inner.dirty {
+                Some((dirty_top, dirty_bottom)) => (dirty_top.min(top), dirty_bottom.max(bottom)),
+                None => (top, bottom),
+            });
+            self.changed.signal(());
+        }
+        Ok(())
+    }
+}
+
+impl<M, DI, BUF> OriginDimensions for RegionTarget<'_, M, DI, BUF>
+where
+    M: RawMutex,
+    DI: AsyncWriteOnlyDataCommand,
+{
+    fn size(&self) -> Size {
+        self.area.size
+    }
+}
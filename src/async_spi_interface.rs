@@ -1,227 +1,144 @@
 //! True async SPI interface for SSD1351 using embedded_hal_async
+//!
+//! Word formats are converted into a chunk buffer of `CHUNK` bytes owned by the interface, and
+//! every full chunk is sent with a single `SpiDevice::write`, so DMA capable buses move whole
+//! chunks at a time. Byte slices are sent as they are, in one transfer.
+//!
+//! ```rust,ignore
+//! // 256 byte chunks
+//! let interface = AsyncSPIInterface::new(spi_device, dc);
+//! // 1 KiB chunks, fewer transfers for large fills and images
+//! let interface: AsyncSPIInterface<_, _, 1024> = AsyncSPIInterface::new(spi_device, dc);
+//! ```
 
 use display_interface::{DataFormat, DisplayError};
-use embedded_hal_async::spi::SpiDevice;
 use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiDevice;
 
 use crate::async_interface::AsyncWriteOnlyDataCommand;
 
 /// True async SPI interface that uses embedded_hal_async::spi::SpiDevice
-pub struct AsyncSPIInterface<SPI, DC> {
+pub struct AsyncSPIInterface<SPI, DC, const CHUNK: usize = 256> {
     spi: SPI,
     dc: DC,
+    buffer: [u8; CHUNK],
 }
 
-impl<SPI, DC> AsyncSPIInterface<SPI, DC> {
+impl<SPI, DC, const CHUNK: usize> AsyncSPIInterface<SPI, DC, CHUNK> {
+    /// Create a new interface from an SPI device and the data/command pin
     pub fn new(spi: SPI, dc: DC) -> Self {
-        Self { spi, dc }
+        const { assert!(CHUNK >= 2, "CHUNK must hold at least one word") };
+        Self {
+            spi,
+            dc,
+            buffer: [0; CHUNK],
+        }
     }
 }
 
-impl<SPI, DC> AsyncWriteOnlyDataCommand for AsyncSPIInterface<SPI, DC>
+impl<SPI, DC, const CHUNK: usize> AsyncSPIInterface<SPI, DC, CHUNK>
 where
     SPI: SpiDevice,
     DC: OutputPin,
 {
-    async fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
-        // Set DC low for command mode
-        self.dc.set_low().map_err(|_| DisplayError::DCError)?;
-        
-        match cmd {
-            DataFormat::U8(data) => {
-                self.spi.write(data).await.map_err(|_| DisplayError::BusWriteError)?;
-            }
-            DataFormat::U16(data) => {
-                // Convert u16 data to bytes and send
-                let mut buffer = [0u8; 2];
-                for word in data {
-                    buffer[0] = (*word >> 8) as u8;
-                    buffer[1] = (*word & 0xFF) as u8;
-                    self.spi.write(&buffer).await.map_err(|_| DisplayError::BusWriteError)?;
-                }
-            }
-            DataFormat::U16BE(data) => {
-                // Big-endian u16 data
-                let mut buffer = [0u8; 2];
-                for word in data {
-                    buffer[0] = (*word >> 8) as u8;
-                    buffer[1] = (*word & 0xFF) as u8;
-                    self.spi.write(&buffer).await.map_err(|_| DisplayError::BusWriteError)?;
-                }
-            }
-            DataFormat::U16LE(data) => {
-                // Little-endian u16 data
-                let mut buffer = [0u8; 2];
-                for word in data {
-                    buffer[0] = (*word & 0xFF) as u8;
-                    buffer[1] = (*word >> 8) as u8;
-                    self.spi.write(&buffer).await.map_err(|_| DisplayError::BusWriteError)?;
-                }
-            }
-            DataFormat::U8Iter(iter) => {
-                // Handle iterator efficiently by collecting into chunks
-                let mut buffer = [0u8; 64]; // Small buffer for chunked writes
-                let mut count = 0;
-                
-                for byte in iter {
-                    buffer[count] = byte;
-                    count += 1;
-                    
-                    if count == buffer.len() {
-                        self.spi.write(&buffer).await.map_err(|_| DisplayError::BusWriteError)?;
-                        count = 0;
-                    }
-                }
-                
-                // Send remaining bytes
-                if count > 0 {
-                    self.spi.write(&buffer[..count]).await.map_err(|_| DisplayError::BusWriteError)?;
-                }
-            }
-            DataFormat::U16BEIter(iter) => {
-                let mut buffer = [0u8; 128]; // Buffer for chunked writes (64 u16 values)
-                let mut count = 0;
-                
-                for word in iter {
-                    buffer[count] = (word >> 8) as u8;
-                    buffer[count + 1] = (word & 0xFF) as u8;
-                    count += 2;
-                    
-                    if count == buffer.len() {
-                        self.spi.write(&buffer).await.map_err(|_| DisplayError::BusWriteError)?;
-                        count = 0;
-                    }
-                }
-                
-                // Send remaining bytes
-                if count > 0 {
-                    self.spi.write(&buffer[..count]).await.map_err(|_| DisplayError::BusWriteError)?;
-                }
-            }
-            DataFormat::U16LEIter(iter) => {
-                let mut buffer = [0u8; 128]; // Buffer for chunked writes
-                let mut count = 0;
-                
-                for word in iter {
-                    buffer[count] = (word & 0xFF) as u8;
-                    buffer[count + 1] = (word >> 8) as u8;
-                    count += 2;
-                    
-                    if count == buffer.len() {
-                        self.spi.write(&buffer).await.map_err(|_| DisplayError::BusWriteError)?;
-                        count = 0;
-                    }
-                }
-                
-                // Send remaining bytes
-                if count > 0 {
-                    self.spi.write(&buffer[..count]).await.map_err(|_| DisplayError::BusWriteError)?;
-                }
-            }
-            _ => {
-                // Handle any additional DataFormat variants
-                return Err(DisplayError::DataFormatNotImplemented);
-            }
+    /// Send `data` with the data/command pin at `dc`, the path shared by commands and data
+    async fn send(&mut self, dc: bool, data: DataFormat<'_>) -> Result<(), DisplayError> {
+        if dc {
+            self.dc.set_high()
+        } else {
+            self.dc.set_low()
+        }
+        .map_err(|_| DisplayError::DCError)?;
+
+        match data {
+            DataFormat::U8(bytes) => self.write(bytes).await,
+            DataFormat::U16(words) => {
+                self.write_words(words.iter().copied(), u16::to_ne_bytes)
+                    .await
+            }
+            DataFormat::U16BE(words) => {
+                self.write_words(words.iter().copied(), u16::to_be_bytes)
+                    .await
+            }
+            DataFormat::U16LE(words) => {
+                self.write_words(words.iter().copied(), u16::to_le_bytes)
+                    .await
+            }
+            DataFormat::U8Iter(bytes) => self.write_bytes(bytes).await,
+            DataFormat::U16BEIter(words) => self.write_words(words, u16::to_be_bytes).await,
+            DataFormat::U16LEIter(words) => self.write_words(words, u16::to_le_bytes).await,
+            _ => Err(DisplayError::DataFormatNotImplemented),
         }
-        
-        Ok(())
     }
-    
-    async fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
-        // Set DC high for data mode
-        self.dc.set_high().map_err(|_| DisplayError::DCError)?;
-        
-        // Reuse the same logic as send_commands but with DC high
-        match buf {
-            DataFormat::U8(data) => {
-                self.spi.write(data).await.map_err(|_| DisplayError::BusWriteError)?;
-            }
-            DataFormat::U16(data) => {
-                let mut buffer = [0u8; 2];
-                for word in data {
-                    buffer[0] = (*word >> 8) as u8;
-                    buffer[1] = (*word & 0xFF) as u8;
-                    self.spi.write(&buffer).await.map_err(|_| DisplayError::BusWriteError)?;
-                }
-            }
-            DataFormat::U16BE(data) => {
-                let mut buffer = [0u8; 2];
-                for word in data {
-                    buffer[0] = (*word >> 8) as u8;
-                    buffer[1] = (*word & 0xFF) as u8;
-                    self.spi.write(&buffer).await.map_err(|_| DisplayError::BusWriteError)?;
-                }
-            }
-            DataFormat::U16LE(data) => {
-                let mut buffer = [0u8; 2];
-                for word in data {
-                    buffer[0] = (*word & 0xFF) as u8;
-                    buffer[1] = (*word >> 8) as u8;
-                    self.spi.write(&buffer).await.map_err(|_| DisplayError::BusWriteError)?;
-                }
-            }
-            DataFormat::U8Iter(iter) => {
-                let mut buffer = [0u8; 64];
-                let mut count = 0;
-                
-                for byte in iter {
-                    buffer[count] = byte;
-                    count += 1;
-                    
-                    if count == buffer.len() {
-                        self.spi.write(&buffer).await.map_err(|_| DisplayError::BusWriteError)?;
-                        count = 0;
-                    }
-                }
-                
-                if count > 0 {
-                    self.spi.write(&buffer[..count]).await.map_err(|_| DisplayError::BusWriteError)?;
-                }
-            }
-            DataFormat::U16BEIter(iter) => {
-                let mut buffer = [0u8; 128];
-                let mut count = 0;
-                
-                for word in iter {
-                    buffer[count] = (word >> 8) as u8;
-                    buffer[count + 1] = (word & 0xFF) as u8;
-                    count += 2;
-                    
-                    if count == buffer.len() {
-                        self.spi.write(&buffer).await.map_err(|_| DisplayError::BusWriteError)?;
-                        count = 0;
-                    }
-                }
-                
-                if count > 0 {
-                    self.spi.write(&buffer[..count]).await.map_err(|_| DisplayError::BusWriteError)?;
-                }
-            }
-            DataFormat::U16LEIter(iter) => {
-                let mut buffer = [0u8; 128];
-                let mut count = 0;
-                
-                for word in iter {
-                    buffer[count] = (word & 0xFF) as u8;
-                    buffer[count + 1] = (word >> 8) as u8;
-                    count += 2;
-                    
-                    if count == buffer.len() {
-                        self.spi.write(&buffer).await.map_err(|_| DisplayError::BusWriteError)?;
-                        count = 0;
-                    }
-                }
-                
-                if count > 0 {
-                    self.spi.write(&buffer[..count]).await.map_err(|_| DisplayError::BusWriteError)?;
-                }
+
+    /// Send the bytes of `bytes` a chunk at a time
+    async fn write_bytes<I>(&mut self, mut bytes: I) -> Result<(), DisplayError>
+    where
+        I: Iterator<Item = u8>,
+    {
+        loop {
+            let mut len = 0;
+            for (slot, byte) in self.buffer.iter_mut().zip(&mut bytes) {
+                *slot = byte;
+                len += 1;
+            }
+            if len > 0 {
+                self.spi
+                    .write(&self.buffer[..len])
+                    .await
+                    .map_err(|_| DisplayError::BusWriteError)?;
+            }
+            if len < CHUNK {
+                return Ok(());
             }
-            _ => {
-                // Handle any additional DataFormat variants
-                return Err(DisplayError::DataFormatNotImplemented);
+        }
+    }
+
+    /// Send `words`, converted to bytes by `to_bytes`, a chunk at a time
+    async fn write_words<I>(
+        &mut self,
+        mut words: I,
+        to_bytes: fn(u16) -> [u8; 2],
+    ) -> Result<(), DisplayError>
+    where
+        I: Iterator<Item = u16>,
+    {
+        loop {
+            let mut len = 0;
+            for (slot, word) in self.buffer.chunks_exact_mut(2).zip(&mut words) {
+                slot.copy_from_slice(&to_bytes(word));
+                len += 2;
+            }
+            if len > 0 {
+                self.spi
+                    .write(&self.buffer[..len])
+                    .await
+                    .map_err(|_| DisplayError::BusWriteError)?;
+            }
+            if len < CHUNK / 2 * 2 {
+                return Ok(());
             }
         }
-        
-        Ok(())
     }
-}
\ No newline at end of file
+
+    async fn write(&mut self, bytes: &[u8]) -> Result<(), DisplayError> {
+        self.spi
+            .write(bytes)
+            .await
+            .map_err(|_| DisplayError::BusWriteError)
+    }
+}
+
+impl<SPI, DC, const CHUNK: usize> AsyncWriteOnlyDataCommand for AsyncSPIInterface<SPI, DC, CHUNK>
+where
+    SPI: SpiDevice,
+    DC: OutputPin,
+{
+    async fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.send(false, cmd).await
+    }
+
+    async fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.send(true, buf).await
+    }
+}
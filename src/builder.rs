@@ -2,10 +2,17 @@
 
 use super::display::Display;
 use super::mode::displaymode::DisplayMode;
+#[cfg(feature = "graphics")]
+use super::mode::displaymode::DisplayModeTrait;
+#[cfg(feature = "graphics")]
+use super::mode::indexed::{IndexFormat, IndexedGraphicsMode};
 use super::mode::raw::RawMode;
+#[cfg(feature = "graphics")]
+use super::mode::strip::MAX_ROW_BYTES;
 use super::properties::DisplayRotation;
 use super::properties::DisplaySize;
 
+use display_interface::DisplayError;
 use display_interface::WriteOnlyDataCommand;
 
 /// Builder struct. Driver options and interface are set using its methods.
@@ -45,28 +52,96 @@ impl Builder {
         Self { rotation, ..*self }
     }
 
-    #[cfg(feature = "buffered")]
     /// Finish the builder and use the given interface to communicate with the display
-    pub fn connect_interface<DI>(
+    pub fn connect_interface<DI>(&self, display_interface: DI) -> DisplayMode<RawMode<DI>>
+    where
+        DI: WriteOnlyDataCommand,
+    {
+        let properties = Display::new(display_interface, self.display_size, self.rotation);
+        DisplayMode::<RawMode<DI>>::new(properties, ())
+    }
+
+    /// Finish the builder with a framebuffer for
+    /// [`BufferedGraphicsMode`](../mode/buffered_graphics/struct.BufferedGraphicsMode.html). The
+    /// framebuffer must be exactly [DisplaySize::buffer_size] bytes long, otherwise
+    /// `DisplayError::OutOfBoundsError` is returned.
+    pub fn connect_interface_buffered<DI, BUF>(
         &self,
         display_interface: DI,
-        buffer: &'static mut [u8],
-    ) -> DisplayMode<RawMode<DI>>
+        buffer: BUF,
+    ) -> Result<DisplayMode<RawMode<DI, BUF>>, DisplayError>
     where
         DI: WriteOnlyDataCommand,
+        BUF: AsRef<[u8]> + AsMut<[u8]>,
     {
-        assert_eq!(buffer.len(), self.display_size.num_pixels() * 2);
+        if buffer.as_ref().len() != self.display_size.buffer_size() {
+            return Err(DisplayError::OutOfBoundsError);
+        }
         let properties = Display::new(display_interface, self.display_size, self.rotation);
-        DisplayMode::<RawMode<DI>>::new(properties, buffer)
+        Ok(DisplayMode::<RawMode<DI, BUF>>::new(properties, buffer))
     }
 
-    #[cfg(not(feature = "buffered"))]
-    /// Finish the builder and use the given interface to communicate with the display
-    pub fn connect_interface<DI>(&self, display_interface: DI) -> DisplayMode<RawMode<DI>>
+    /// Finish the builder with a buffer holding a few rows for
+    /// [`StripMode`](../mode/strip/struct.StripMode.html). The buffer must hold at least one row
+    /// of 128 pixels (256 bytes), otherwise `DisplayError::OutOfBoundsError` is returned.
+    #[cfg(feature = "graphics")]
+    pub fn connect_interface_strip<DI, BUF>(
+        &self,
+        display_interface: DI,
+        buffer: BUF,
+    ) -> Result<DisplayMode<RawMode<DI, BUF>>, DisplayError>
     where
         DI: WriteOnlyDataCommand,
+        BUF: AsRef<[u8]> + AsMut<[u8]>,
     {
+        if buffer.as_ref().len() < MAX_ROW_BYTES {
+            return Err(DisplayError::OutOfBoundsError);
+        }
+        let properties = Display::new(display_interface, self.display_size, self.rotation);
+        Ok(DisplayMode::<RawMode<DI, BUF>>::new(properties, buffer))
+    }
+
+    /// Finish the builder in
+    /// [`IndexedGraphicsMode`](../mode/indexed/struct.IndexedGraphicsMode.html) with a framebuffer
+    /// of `F::BITS` bits per pixel. The framebuffer must be exactly
+    /// [DisplaySize::indexed_buffer_size] bytes long for that format, otherwise
+    /// `DisplayError::OutOfBoundsError` is returned.
+    #[cfg(feature = "graphics")]
+    pub fn connect_interface_indexed<DI, BUF, F>(
+        &self,
+        display_interface: DI,
+        buffer: BUF,
+    ) -> Result<IndexedGraphicsMode<DI, BUF, F>, DisplayError>
+    where
+        DI: WriteOnlyDataCommand,
+        BUF: AsRef<[u8]> + AsMut<[u8]>,
+        F: IndexFormat,
+    {
+        if buffer.as_ref().len() != self.display_size.indexed_buffer_size(F::BITS) {
+            return Err(DisplayError::OutOfBoundsError);
+        }
+        let properties = Display::new(display_interface, self.display_size, self.rotation);
+        Ok(IndexedGraphicsMode::new(properties, buffer))
+    }
+
+    /// Finish the builder with a framebuffer for
+    /// [`MonoGraphicsMode`](../mode/mono/struct.MonoGraphicsMode.html). The framebuffer must be
+    /// exactly [DisplaySize::mono_buffer_size] bytes long, otherwise
+    /// `DisplayError::OutOfBoundsError` is returned.
+    #[cfg(feature = "graphics")]
+    pub fn connect_interface_mono<DI, BUF>(
+        &self,
+        display_interface: DI,
+        buffer: BUF,
+    ) -> Result<DisplayMode<RawMode<DI, BUF>>, DisplayError>
+    where
+        DI: WriteOnlyDataCommand,
+        BUF: AsRef<[u8]> + AsMut<[u8]>,
+    {
+        if buffer.as_ref().len() != self.display_size.mono_buffer_size() {
+            return Err(DisplayError::OutOfBoundsError);
+        }
         let properties = Display::new(display_interface, self.display_size, self.rotation);
-        DisplayMode::<RawMode<DI>>::new(properties)
+        Ok(DisplayMode::<RawMode<DI, BUF>>::new(properties, buffer))
     }
 }
@@ -0,0 +1,96 @@
+//! Offscreen drawing canvas
+//!
+//! A [`Canvas`] holds a small image in RAM, in the byte layout sent to the display. Draw on it with
+//! `embedded-graphics`, then push it to a window of an unbuffered display in one go with
+//! [`DirectGraphicsMode::blit`](../mode/graphics/struct.DirectGraphicsMode.html#method.blit).
+//!
+//! ```rust
+//! # use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+//! # struct FakeInterface;
+//! # impl WriteOnlyDataCommand for FakeInterface {
+//! #     fn send_commands(&mut self, _: DataFormat<'_>) -> Result<(), DisplayError> { Ok(()) }
+//! #     fn send_data(&mut self, _: DataFormat<'_>) -> Result<(), DisplayError> { Ok(()) }
+//! # }
+//! use embedded_graphics_core::pixelcolor::{Rgb565, RgbColor};
+//! use embedded_graphics_core::prelude::{DrawTarget, Point};
+//! use ssd1351::builder::Builder;
+//! use ssd1351::canvas::Canvas;
+//! use ssd1351::mode::GraphicsMode;
+//!
+//! let mut display: GraphicsMode<_> = Builder::new().connect_interface(FakeInterface).into();
+//!
+//! let mut widget = Canvas::<32, 16>::new();
+//! widget.clear(Rgb565::BLUE).unwrap();
+//! display.blit(&widget, Point::new(8, 8)).unwrap();
+//! ```
+
+extern crate embedded_graphics_core;
+use self::embedded_graphics_core::pixelcolor::{raw::RawU16, Rgb565};
+use self::embedded_graphics_core::prelude::{DrawTarget, OriginDimensions, Pixel, RawData, Size};
+
+/// Image of `W` by `H` pixels in RAM, stored row by row as big endian RGB565
+pub struct Canvas<const W: usize, const H: usize> {
+    pixels: [[[u8; 2]; W]; H],
+}
+
+impl<const W: usize, const H: usize> Canvas<W, H> {
+    /// Create a new canvas with all pixels black
+    pub const fn new() -> Self {
+        Canvas {
+            pixels: [[[0; 2]; W]; H],
+        }
+    }
+
+    /// Pixel data in the layout `Display::draw` expects
+    pub fn as_bytes(&self) -> &[u8] {
+        self.pixels.as_flattened().as_flattened()
+    }
+
+    /// Set the raw colour of a pixel. If the X and Y coordinates are out of the bounds of the
+    /// canvas, this method call is a noop.
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: u16) {
+        if let Some(pixel) = self
+            .pixels
+            .get_mut(y as usize)
+            .and_then(|row| row.get_mut(x as usize))
+        {
+            *pixel = color.to_be_bytes();
+        }
+    }
+}
+
+impl<const W: usize, const H: usize> Default for Canvas<W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const W: usize, const H: usize> DrawTarget for Canvas<W, H> {
+    type Color = Rgb565;
+    type Error = ();
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(pos, color) in pixels {
+            if let (Ok(x), Ok(y)) = (u32::try_from(pos.x), u32::try_from(pos.y)) {
+                self.set_pixel(x, y, RawU16::from(color).into_inner());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        let bytes = RawU16::from(color).into_inner().to_be_bytes();
+        self.pixels.as_flattened_mut().fill(bytes);
+        Ok(())
+    }
+}
+
+impl<const W: usize, const H: usize> OriginDimensions for Canvas<W, H> {
+    fn size(&self) -> Size {
+        Size::new(W as u32, H as u32)
+    }
+}
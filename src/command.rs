@@ -2,100 +2,403 @@ use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
 
 const REMAP_BASE: u8 = 0b00100100;
 
-pub enum Command {
-    /// Column address
+/// Maximum number of parameter bytes any command carries (the gamma look up table)
+pub const MAX_PARAMS: usize = 63;
+
+/// SSD1351 commands, as listed in the command tables of the datasheet
+#[derive(Clone, Copy)]
+pub enum Command<'a> {
+    /// Column address - start and end, 0..=127
     Column(u8, u8),
-    /// Row address
+    /// Row address - start and end, 0..=127
     Row(u8, u8),
-    /// CommandLock
+    /// CommandLock - one of 0x12, 0x16, 0xB0 or 0xB1
     CommandLock(u8),
-    /// DisplayOn
+    /// DisplayOn - sleep mode off (true) or on (false)
     DisplayOn(bool),
     /// WriteRam
     WriteRam,
-    /// ClockDiv
+    /// ReadRam
+    ReadRam,
+    /// ClockDiv - oscillator frequency in the upper nibble, divide ratio 0..=10 in the lower
     ClockDiv(u8),
-    /// MuxRatio
+    /// MuxRatio - 15..=127
     MuxRatio(u8),
     /// SetRemap - horizontal or vertical increment, SegmentRemap, Reverse scan direction
     SetRemap(bool, bool, bool),
-    /// Display Start Row
+    /// Display Start Row - 0..=127
     StartLine(u8),
-    /// DisplayOffset
+    /// DisplayOffset - 0..=127
     DisplayOffset(u8),
-    /// SetGpio
+    /// Entire display off, all pixels at GS0
+    AllOff,
+    /// Entire display on, all pixels at GS63
+    AllOn,
+    /// SetGpio - GPIO0 in bits 1:0, GPIO1 in bits 3:2
     SetGpio(u8),
-    /// FunctionSelect
+    /// FunctionSelect - VDD regulator in bit 0, parallel interface width in bits 7:6
     FunctionSelect(u8),
-    /// PreCharge
+    /// PreCharge - phase 1 period 2..=15 in the lower nibble, phase 2 period 3..=15 in the upper
     PreCharge(u8),
-    /// Vcomh
+    /// Display enhancement
+    DisplayEnhancement(bool),
+    /// Vcomh - 0..=7
     Vcomh(u8),
     /// NormalDisplayOn
     Invert(bool),
     /// Contrast
     Contrast(u8),
-    /// ContrastMaster
+    /// Contrast for colour A, B and C
+    ChannelContrast(u8, u8, u8),
+    /// ContrastMaster - 0..=15
     ContrastCurrent(u8),
     /// SetVsl
     SetVsl,
-    /// SetPrecharge
+    /// SetPrecharge - 1..=15
     PreCharge2(u8),
+    /// Pre-charge voltage - 0..=31
+    PreChargeVoltage(u8),
+    /// Gray scale pulse widths for GS1 to GS63, strictly increasing and at most 180
+    GammaTable(&'a [u8; MAX_PARAMS]),
+    /// Reset the gray scale table to the built-in linear one
+    LinearLut,
+    /// Horizontal scroll - offset per step (0x80 is invalid), start row, number of rows and
+    /// time interval 0..=3
+    HorizontalScroll(u8, u8, u8, u8),
+    /// Stop horizontal scrolling
+    StopScroll,
+    /// Start horizontal scrolling
+    StartScroll,
+    /// Enhanced driving scheme capability
+    EnhanceDriving(bool),
     // PhaseLength(u8)
 }
 
-impl Command {
+/// A command transformed into its opcode and parameter bytes
+pub struct EncodedCommand {
+    opcode: u8,
+    params: [u8; MAX_PARAMS],
+    len: usize,
+}
+
+impl EncodedCommand {
+    fn new(opcode: u8, params: &[u8]) -> Self {
+        let mut encoded = EncodedCommand {
+            opcode,
+            params: [0; MAX_PARAMS],
+            len: params.len(),
+        };
+        encoded.params[..params.len()].copy_from_slice(params);
+        encoded
+    }
+
+    /// Command byte, sent with DC low
+    pub fn opcode(&self) -> u8 {
+        self.opcode
+    }
+
+    /// Parameter bytes, sent with DC high
+    pub fn params(&self) -> &[u8] {
+        &self.params[..self.len]
+    }
+
+    /// Opcode and parameters as a pair
+    pub fn parts(&self) -> (u8, &[u8]) {
+        (self.opcode, self.params())
+    }
+}
+
+fn check(valid: bool) -> Result<(), DisplayError> {
+    if valid {
+        Ok(())
+    } else {
+        Err(DisplayError::OutOfBoundsError)
+    }
+}
+
+impl Command<'_> {
+    /// Transform the command into the bytes sent over the interface, checking parameters
+    /// against the ranges documented in the datasheet.
+    ///
+    /// ```rust
+    /// # use ssd1351::command::Command;
+    /// let encoded = Command::Column(0, 127).encode().unwrap();
+    /// assert_eq!(encoded.parts(), (0x15, &[0, 127][..]));
+    ///
+    /// assert!(Command::MuxRatio(128).encode().is_err());
+    /// ```
+    pub fn encode(&self) -> Result<EncodedCommand, DisplayError> {
+        let encoded = match *self {
+            Command::CommandLock(val) => {
+                check(matches!(val, 0x12 | 0x16 | 0xB0 | 0xB1))?;
+                EncodedCommand::new(0xFD, &[val])
+            }
+            Command::DisplayOn(val) => EncodedCommand::new(if val { 0xAF } else { 0xAE }, &[]),
+            Command::ClockDiv(val) => {
+                check(val & 0x0F <= 0x0A)?;
+                EncodedCommand::new(0xB3, &[val])
+            }
+            Command::MuxRatio(val) => {
+                check((15..=127).contains(&val))?;
+                EncodedCommand::new(0xCA, &[val])
+            }
+            Command::SetRemap(incr, remap, scan) => EncodedCommand::new(
+                0xA0,
+                &[REMAP_BASE | (incr as u8) | (remap as u8) << 1 | (scan as u8) << 4],
+            ),
+            Command::Column(start, end) => {
+                check(start <= end && end <= 127)?;
+                EncodedCommand::new(0x15, &[start, end])
+            }
+            Command::Row(start, end) => {
+                check(start <= end && end <= 127)?;
+                EncodedCommand::new(0x75, &[start, end])
+            }
+            Command::StartLine(val) => {
+                check(val <= 127)?;
+                EncodedCommand::new(0xA1, &[val])
+            }
+            Command::DisplayOffset(val) => {
+                check(val <= 127)?;
+                EncodedCommand::new(0xA2, &[val])
+            }
+            Command::AllOff => EncodedCommand::new(0xA4, &[]),
+            Command::AllOn => EncodedCommand::new(0xA5, &[]),
+            Command::SetGpio(val) => {
+                check(val <= 0x0F)?;
+                EncodedCommand::new(0xB5, &[val])
+            }
+            Command::FunctionSelect(val) => {
+                check(val & 0x3E == 0 && val >> 6 != 0b10)?;
+                EncodedCommand::new(0xAB, &[val])
+            }
+            Command::PreCharge(val) => {
+                check(val & 0x0F >= 2 && val >> 4 >= 3)?;
+                EncodedCommand::new(0xB1, &[val])
+            }
+            Command::DisplayEnhancement(val) => {
+                EncodedCommand::new(0xB2, &[if val { 0xA4 } else { 0x00 }, 0x00, 0x00])
+            }
+            Command::Vcomh(val) => {
+                check(val <= 0x07)?;
+                EncodedCommand::new(0xBE, &[val])
+            }
+            Command::Invert(val) => EncodedCommand::new(if val { 0xA7 } else { 0xA6 }, &[]),
+            Command::Contrast(val) => EncodedCommand::new(0xC1, &[0xC8, val, 0xC8]),
+            Command::ChannelContrast(a, b, c) => EncodedCommand::new(0xC1, &[a, b, c]),
+            Command::ContrastCurrent(val) => {
+                check(val <= 0x0F)?;
+                EncodedCommand::new(0xC7, &[val])
+            }
+            Command::SetVsl => EncodedCommand::new(0xB4, &[0xA0, 0xB5, 0x55]),
+            Command::PreCharge2(val) => {
+                check((1..=15).contains(&val))?;
+                EncodedCommand::new(0xB6, &[val])
+            }
+            Command::PreChargeVoltage(val) => {
+                check(val <= 0x1F)?;
+                EncodedCommand::new(0xBB, &[val])
+            }
+            Command::GammaTable(table) => {
+                check(table[MAX_PARAMS - 1] <= 180 && table.windows(2).all(|w| w[0] < w[1]))?;
+                EncodedCommand::new(0xB8, table)
+            }
+            Command::LinearLut => EncodedCommand::new(0xB9, &[]),
+            Command::HorizontalScroll(offset, start, rows, interval) => {
+                check(
+                    offset != 0x80
+                        && start <= 127
+                        && start as u16 + rows as u16 <= 128
+                        && interval <= 3,
+                )?;
+                EncodedCommand::new(0x96, &[offset, start, rows, 0x00, interval])
+            }
+            Command::StopScroll => EncodedCommand::new(0x9E, &[]),
+            Command::StartScroll => EncodedCommand::new(0x9F, &[]),
+            Command::EnhanceDriving(val) => {
+                EncodedCommand::new(0xD1, &[if val { 0xA2 } else { 0x82 }, 0x20])
+            }
+            Command::WriteRam => EncodedCommand::new(0x5C, &[]),
+            Command::ReadRam => EncodedCommand::new(0x5D, &[]),
+        };
+
+        Ok(encoded)
+    }
+
     /// Send command to SSD1351
     pub fn send<DI>(self, iface: &mut DI) -> Result<(), DisplayError>
     where
         DI: WriteOnlyDataCommand,
     {
-        // Transform command into a fixed size array of 7 u8 and the real length for sending
-        // TODO can we replace the use if the static buffers?
-        let (command, data, len) = match self {
-            Command::CommandLock(val) => (0xFD, [val, 0, 0, 0, 0, 0], 1),
-            Command::DisplayOn(val) => (if val { 0xAF } else { 0xAE }, [0, 0, 0, 0, 0, 0], 0),
-            Command::ClockDiv(val) => (0xB3, [val, 0, 0, 0, 0, 0], 1),
-            Command::MuxRatio(val) => (0xCA, [val, 0, 0, 0, 0, 0], 1),
-            Command::SetRemap(incr, remap, scan) => (
-                0xA0,
-                [
-                    REMAP_BASE | (incr as u8) | (remap as u8) << 1 | (scan as u8) << 4,
-                    0,
-                    0,
-                    0,
-                    0,
-                    0,
-                ],
-                1,
-            ),
-            Command::Column(start, end) => (0x15, [start, end, 0, 0, 0, 0], 2),
-            Command::Row(start, end) => (0x75, [start, end, 0, 0, 0, 0], 2),
-            Command::StartLine(val) => (0xA1, [val, 0, 0, 0, 0, 0], 1),
-            Command::DisplayOffset(val) => (0xA2, [val, 0, 0, 0, 0, 0], 1),
-            Command::SetGpio(val) => (0xB5, [val, 0, 0, 0, 0, 0], 1),
-            Command::FunctionSelect(val) => (0xAB, [val, 0, 0, 0, 0, 0], 1),
-            Command::PreCharge(val) => (0xB1, [val, 0, 0, 0, 0, 0], 1),
-            Command::Vcomh(val) => (0xBE, [val, 0, 0, 0, 0, 0], 1),
-            Command::Invert(val) => (if val { 0xA7 } else { 0xA6 }, [0, 0, 0, 0, 0, 0], 0),
-            Command::Contrast(val) => (0xC1, [0xC8, val, 0xC8, 0, 0, 0], 3),
-            Command::ContrastCurrent(val) => (0xC7, [val, 0, 0, 0, 0, 0], 1),
-            Command::SetVsl => (0xB4, [0xA0, 0xB5, 0x55, 0, 0, 0], 3),
-            Command::PreCharge2(val) => (0xB6, [val, 0, 0, 0, 0, 0], 1),
-            Command::WriteRam => (0x5C, [0, 0, 0, 0, 0, 0], 0),
-        };
+        let encoded = self.encode()?;
 
         // Send command over the interface
-        iface.send_commands(DataFormat::U8(&[command]))?;
+        iface.send_commands(DataFormat::U8(&[encoded.opcode()]))?;
 
-        if len > 0 {
-            iface.send_data(DataFormat::U8(&data[0..len]))?;
+        if !encoded.params().is_empty() {
+            iface.send_data(DataFormat::U8(encoded.params()))?;
         }
 
         Ok(())
     }
 }
 
+/// Header bit marking a run of parameter bytes, sent with DC high
+const DATA_RUN: u8 = 0x80;
+/// Longest run a single header can describe
+const MAX_RUN: usize = 0x7F;
+
+/// Fixed capacity queue of commands, flushed with one interface call per run of command or
+/// parameter bytes instead of one or two calls per command.
+///
+/// ```rust
+/// # use ssd1351::command::{Command, CommandBatch};
+/// let mut batch = CommandBatch::<16>::new();
+/// batch
+///     .push(Command::DisplayOn(false))
+///     .unwrap()
+///     .push(Command::ClockDiv(0xF1))
+///     .unwrap();
+///
+/// let mut runs = batch.runs();
+/// assert_eq!(runs.next(), Some((false, &[0xAE, 0xB3][..])));
+/// assert_eq!(runs.next(), Some((true, &[0xF1][..])));
+/// assert_eq!(runs.next(), None);
+/// ```
+pub struct CommandBatch<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+    /// Position of the header of the last run, if any
+    last_run: Option<usize>,
+}
+
+impl<const N: usize> Default for CommandBatch<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> CommandBatch<N> {
+    /// Create an empty batch
+    pub const fn new() -> Self {
+        CommandBatch {
+            buf: [0; N],
+            len: 0,
+            last_run: None,
+        }
+    }
+
+    /// Queue a command. Fails with `OutOfBoundsError` if the parameters are out of range or the
+    /// batch does not have room for the command, in which case the batch is left unchanged.
+    pub fn push(&mut self, command: Command<'_>) -> Result<&mut Self, DisplayError> {
+        let encoded = command.encode()?;
+        let (last_run, len) = (self.last_run, self.len);
+        // The only byte before `len` that appending changes is the header of the last run
+        let header = last_run.map(|pos| self.buf[pos]);
+
+        let appended = self
+            .append(false, &[encoded.opcode()])
+            .and_then(|_| self.append(true, encoded.params()));
+        if appended.is_err() {
+            if let (Some(pos), Some(header)) = (last_run, header) {
+                self.buf[pos] = header;
+            }
+            self.last_run = last_run;
+            self.len = len;
+        }
+
+        appended.map(|_| self)
+    }
+
+    /// Returns `true` if no commands are queued
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Drop all queued commands
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.last_run = None;
+    }
+
+    /// Iterate over the queued bytes, grouped into runs that share the same DC level. Each item
+    /// is `true` for parameter bytes and `false` for command bytes, followed by the bytes.
+    pub fn runs(&self) -> Runs<'_> {
+        Runs {
+            buf: &self.buf[..self.len],
+        }
+    }
+
+    /// Send all queued commands over the interface and empty the batch
+    pub fn flush<DI>(&mut self, iface: &mut DI) -> Result<(), DisplayError>
+    where
+        DI: WriteOnlyDataCommand,
+    {
+        for (data, bytes) in self.runs() {
+            if data {
+                iface.send_data(DataFormat::U8(bytes))?;
+            } else {
+                iface.send_commands(DataFormat::U8(bytes))?;
+            }
+        }
+        self.clear();
+
+        Ok(())
+    }
+
+    fn append(&mut self, data: bool, bytes: &[u8]) -> Result<(), DisplayError> {
+        let kind = if data { DATA_RUN } else { 0 };
+
+        for &byte in bytes {
+            let extend = match self.last_run {
+                Some(pos) => {
+                    let header = self.buf[pos];
+                    header & DATA_RUN == kind && ((header & !DATA_RUN) as usize) < MAX_RUN
+                }
+                None => false,
+            };
+
+            if extend {
+                if self.len == N {
+                    return Err(DisplayError::OutOfBoundsError);
+                }
+            } else {
+                if self.len + 2 > N {
+                    return Err(DisplayError::OutOfBoundsError);
+                }
+                self.buf[self.len] = kind;
+                self.last_run = Some(self.len);
+                self.len += 1;
+            }
+
+            if let Some(pos) = self.last_run {
+                self.buf[pos] += 1;
+            }
+            self.buf[self.len] = byte;
+            self.len += 1;
+        }
+
+        Ok(())
+    }
+}
+
+/// Iterator over the runs of a [CommandBatch]
+pub struct Runs<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Iterator for Runs<'a> {
+    type Item = (bool, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (&header, rest) = self.buf.split_first()?;
+        let (bytes, rest) = rest.split_at((header & !DATA_RUN) as usize);
+        self.buf = rest;
+
+        Some((header & DATA_RUN != 0, bytes))
+    }
+}
+
 // Transform command into a fixed size array of 7 u8 and the real length for sending
 // let (data, len) = match self {
 //     Command::Contrast(val) => ([0x81, val, 0, 0, 0, 0, 0], 2),
@@ -1,6 +1,7 @@
 //! Container to store and set display properties
 
-use crate::command::Command;
+use crate::command::{Command, CommandBatch};
+use crate::geometry::{logical_dimensions, Area};
 
 use crate::properties::DisplayRotation;
 use crate::properties::DisplaySize;
@@ -9,6 +10,71 @@ use display_interface::DataFormat;
 use display_interface::DisplayError;
 use display_interface::WriteOnlyDataCommand;
 
+/// Capacity of the batch holding the init sequence
+const INIT_BATCH: usize = 96;
+/// Capacity of the batch holding a draw area change
+const DRAW_AREA_BATCH: usize = 16;
+
+/// Queue the init sequence for a display of the given size and rotation
+pub(crate) fn init_batch(
+    display_size: DisplaySize,
+    display_rotation: DisplayRotation,
+) -> Result<CommandBatch<INIT_BATCH>, DisplayError> {
+    let (_, display_height) = display_size.dimensions();
+
+    let mut batch = CommandBatch::new();
+    // TODO: Break up into nice bits so display modes can pick whathever they need
+    batch
+        .push(Command::CommandLock(0x12))?
+        .push(Command::CommandLock(0xB1))?
+        .push(Command::DisplayOn(false))?
+        .push(Command::ClockDiv(0xF1))?
+        .push(Command::MuxRatio(display_height - 1))?
+        .push(Command::DisplayOffset(0))?
+        .push(Command::StartLine(0))?
+        .push(Command::SetGpio(0x00))?
+        .push(Command::FunctionSelect(0x01))?
+        .push(Command::SetVsl)?
+        .push(Command::Contrast(0x8F))?
+        .push(Command::ContrastCurrent(0x0F))?
+        // .push(Command::PhaseLength(0x32))?
+        // .push(Command::PreCharge(0x17))?
+        .push(Command::PreCharge(0x32))?
+        .push(Command::PreCharge2(0x01))?
+        .push(Command::Vcomh(0x05))?
+        .push(Command::Invert(false))?
+        .push(display_rotation.remap())?;
+
+    Ok(batch)
+}
+
+/// Queue the commands selecting a draw area
+pub(crate) fn draw_area_batch(area: &Area) -> Result<CommandBatch<DRAW_AREA_BATCH>, DisplayError> {
+    let (start, end) = (area.start(), area.end());
+    let mut batch = CommandBatch::new();
+    batch
+        .push(Command::Column(start.x(), end.x()))?
+        .push(Command::Row(start.y(), end.y()))?
+        .push(Command::WriteRam)?;
+
+    Ok(batch)
+}
+
+/// Draw area covering `rows` full rows of a frame `width` pixels wide starting at row `top`,
+/// in display RAM coordinates
+#[cfg(feature = "graphics")]
+pub(crate) fn rows_area(
+    rotation: DisplayRotation,
+    width: u8,
+    top: u8,
+    rows: u8,
+) -> ((u8, u8), (u8, u8)) {
+    match rotation {
+        DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => ((0, top), (width, top + rows)),
+        DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => ((top, 0), (top + rows, width)),
+    }
+}
+
 /// Display properties struct
 pub struct Display<DI> {
     iface: DI,
@@ -41,29 +107,7 @@ where
     /// Initialise the display in column mode (i.e. a byte walks down a column of 8 pixels) with
     /// column 0 on the left and column _(display_width - 1)_ on the right.
     pub fn init(&mut self) -> Result<(), DisplayError> {
-        let (_, display_height) = self.display_size.dimensions();
-
-        // TODO: Break up into nice bits so display modes can pick whathever they need
-        Command::CommandLock(0x12).send(&mut self.iface)?;
-        Command::CommandLock(0xB1).send(&mut self.iface)?;
-        Command::DisplayOn(false).send(&mut self.iface)?;
-        Command::ClockDiv(0xF1).send(&mut self.iface)?;
-        Command::MuxRatio(display_height - 1).send(&mut self.iface)?;
-        Command::DisplayOffset(0).send(&mut self.iface)?;
-        Command::StartLine(0).send(&mut self.iface)?;
-        Command::SetGpio(0x00).send(&mut self.iface)?;
-        Command::FunctionSelect(0x01).send(&mut self.iface)?;
-        Command::SetVsl.send(&mut self.iface)?;
-        Command::Contrast(0x8F).send(&mut self.iface)?;
-        Command::ContrastCurrent(0x0F).send(&mut self.iface)?;
-        // Command::PhaseLength(0x32).send(&mut self.iface)?;
-        // Command::PreCharge(0x17).send(&mut self.iface)?;
-        Command::PreCharge(0x32).send(&mut self.iface)?;
-        Command::PreCharge2(0x01).send(&mut self.iface)?;
-        Command::Vcomh(0x05).send(&mut self.iface)?;
-        Command::Invert(false).send(&mut self.iface)?;
-
-        self.set_rotation(self.display_rotation).unwrap();
+        init_batch(self.display_size, self.display_rotation)?.flush(&mut self.iface)?;
 
         self.clear()?;
 
@@ -75,7 +119,7 @@ where
     /// Clear the display by setting all pixels to black
     pub fn clear(&mut self) -> Result<(), DisplayError> {
         let (display_width, display_height) = self.display_size.dimensions();
-        self.set_draw_area((0, 0), (display_width, display_height))?;
+        self.set_area(&Area::panel(self.display_size))?;
         for _ in 0..(display_height as u32 * display_width as u32) {
             self.iface.send_data(DataFormat::U8(&[0x00, 0x00]))?; // send 8 * 2 bits
         }
@@ -85,11 +129,16 @@ where
     /// Set the position in the framebuffer of the display where any sent data should be
     /// drawn. This method can be used for changing the affected area on the screen as well
     /// as (re-)setting the start point of the next `draw` call.
+    ///
+    /// `end` is exclusive. An empty area or one exceeding the display RAM is reported as
+    /// `DisplayError::OutOfBoundsError`.
     pub fn set_draw_area(&mut self, start: (u8, u8), end: (u8, u8)) -> Result<(), DisplayError> {
-        Command::Column(start.0, end.0 - 1).send(&mut self.iface)?;
-        Command::Row(start.1, end.1 - 1).send(&mut self.iface)?;
-        Command::WriteRam.send(&mut self.iface)?;
-        Ok(())
+        self.set_area(&Area::from_bounds(start, end)?)
+    }
+
+    /// Set the draw area of the display, cf. `set_draw_area`
+    pub fn set_area(&mut self, area: &Area) -> Result<(), DisplayError> {
+        draw_area_batch(area)?.flush(&mut self.iface)
     }
 
     /// Send the data to the display for drawing at the current position in the framebuffer
@@ -108,11 +157,14 @@ where
     /// Get display dimensions, taking into account the current rotation of the display
     ///
     /// ```rust
+    /// # use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+    /// # use ssd1351::display::Display;
+    /// # use ssd1351::properties::{DisplayRotation, DisplaySize};
     /// # struct FakeInterface;
     /// #
-    /// # impl DisplayInterface for FakeInterface {
-    /// #     fn send_command(&mut self, cmd: u8) -> Result<(), ()> { Ok(()) }
-    /// #     fn send_data(&mut self, buf: &[u8]) -> Result<(), ()> { Ok(()) }
+    /// # impl WriteOnlyDataCommand for FakeInterface {
+    /// #     fn send_commands(&mut self, _: DataFormat<'_>) -> Result<(), DisplayError> { Ok(()) }
+    /// #     fn send_data(&mut self, _: DataFormat<'_>) -> Result<(), DisplayError> { Ok(()) }
     /// # }
     /// #
     /// # let interface = FakeInterface {};
@@ -133,12 +185,7 @@ where
     /// assert_eq!(rotated_disp.get_dimensions(), (128, 128));
     /// ```
     pub fn get_dimensions(&self) -> (u8, u8) {
-        let (w, h) = self.display_size.dimensions();
-
-        match self.display_rotation {
-            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (w, h),
-            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => (h, w),
-        }
+        logical_dimensions(self.display_size, self.display_rotation)
     }
 
     /// Get the display rotation
@@ -150,21 +197,13 @@ where
     pub fn set_rotation(&mut self, display_rotation: DisplayRotation) -> Result<(), DisplayError> {
         self.display_rotation = display_rotation;
 
-        match display_rotation {
-            DisplayRotation::Rotate0 => {
-                Command::SetRemap(false, false, true).send(&mut self.iface)?;
-            }
-            DisplayRotation::Rotate90 => {
-                Command::SetRemap(true, true, true).send(&mut self.iface)?;
-            }
-            DisplayRotation::Rotate180 => {
-                Command::SetRemap(false, true, false).send(&mut self.iface)?;
-            }
-            DisplayRotation::Rotate270 => {
-                Command::SetRemap(true, false, false).send(&mut self.iface)?;
-            }
-        };
+        let mut batch = CommandBatch::<4>::new();
+        batch.push(display_rotation.remap())?;
+        batch.flush(&mut self.iface)
+    }
 
-        Ok(())
+    /// Set the display RAM row shown at the top of the display, scrolling the display vertically
+    pub fn set_start_line(&mut self, line: u8) -> Result<(), DisplayError> {
+        Command::StartLine(line).send(&mut self.iface)
     }
 }
@@ -0,0 +1,185 @@
+//! Checked display geometry
+//!
+//! Positions sent to the display are in panel coordinates, i.e. display RAM columns and rows.
+//! The SSD1351 addresses 128 columns and 128 rows whatever the size of the panel, rows beyond the
+//! panel height are kept off screen and can be scrolled in with the start line.
+//!
+//! Drawing happens in logical coordinates, which depend on the display rotation. Mirroring for
+//! [`Rotate180`](DisplayRotation::Rotate180) and [`Rotate270`](DisplayRotation::Rotate270) is
+//! done by the controller through the remap settings, so the transform from logical to panel
+//! coordinates only swaps the axes for [`Rotate90`](DisplayRotation::Rotate90) and `Rotate270`.
+//!
+//! ```rust
+//! use ssd1351::geometry::{Area, Point};
+//! use ssd1351::properties::{DisplayRotation, DisplaySize};
+//!
+//! let size = DisplaySize::Display128x96;
+//! let point = Point::from_logical(10, 100, size, DisplayRotation::Rotate90).unwrap();
+//! assert_eq!((point.x(), point.y()), (100, 10));
+//!
+//! // The rotated display is only 96 pixels wide
+//! assert!(Point::from_logical(100, 10, size, DisplayRotation::Rotate90).is_err());
+//! // Empty areas are rejected
+//! assert!(Area::from_bounds((0, 0), (0, 10)).is_err());
+//! ```
+
+use crate::properties::{DisplayRotation, DisplaySize};
+use display_interface::DisplayError;
+
+/// Number of columns and rows of the display RAM
+const RAM_SIZE: u8 = 128;
+
+/// Position in panel coordinates
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Point {
+    x: u8,
+    y: u8,
+}
+
+impl Point {
+    /// Create a point at RAM column `x` and row `y`. Fails with `OutOfBoundsError` if either lies
+    /// outside the display RAM.
+    pub fn new(x: u8, y: u8) -> Result<Self, DisplayError> {
+        if x >= RAM_SIZE || y >= RAM_SIZE {
+            return Err(DisplayError::OutOfBoundsError);
+        }
+        Ok(Point { x, y })
+    }
+
+    /// Panel position of the logical pixel at (`x`, `y`) on a display of `size` with `rotation`.
+    /// Fails with `OutOfBoundsError` if the pixel lies outside the rotated display.
+    pub fn from_logical(
+        x: u32,
+        y: u32,
+        size: DisplaySize,
+        rotation: DisplayRotation,
+    ) -> Result<Self, DisplayError> {
+        let (width, height) = logical_dimensions(size, rotation);
+        if x >= width as u32 || y >= height as u32 {
+            return Err(DisplayError::OutOfBoundsError);
+        }
+
+        let (x, y) = (x as u8, y as u8);
+        Ok(match rotation {
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => Point { x, y },
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => Point { x: y, y: x },
+        })
+    }
+
+    /// Logical position of this point on a display with `rotation`, the inverse of
+    /// [`from_logical`](Self::from_logical)
+    pub fn to_logical(self, rotation: DisplayRotation) -> (u32, u32) {
+        match rotation {
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (self.x as u32, self.y as u32),
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => {
+                (self.y as u32, self.x as u32)
+            }
+        }
+    }
+
+    /// RAM column
+    pub fn x(self) -> u8 {
+        self.x
+    }
+
+    /// RAM row
+    pub fn y(self) -> u8 {
+        self.y
+    }
+}
+
+/// Non-empty rectangle in panel coordinates, from `start` to `end` inclusive
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Area {
+    start: Point,
+    end: Point,
+}
+
+impl Area {
+    /// Create an area from its top left and bottom right corners, both included. Fails with
+    /// `OutOfBoundsError` if `end` lies left of or above `start`.
+    pub fn new(start: Point, end: Point) -> Result<Self, DisplayError> {
+        if end.x < start.x || end.y < start.y {
+            return Err(DisplayError::OutOfBoundsError);
+        }
+        Ok(Area { start, end })
+    }
+
+    /// Create an area covering the RAM columns `start.0..end.0` and rows `start.1..end.1`. Fails
+    /// with `OutOfBoundsError` if the area is empty or exceeds the display RAM.
+    pub fn from_bounds(start: (u8, u8), end: (u8, u8)) -> Result<Self, DisplayError> {
+        if end.0 <= start.0 || end.1 <= start.1 {
+            return Err(DisplayError::OutOfBoundsError);
+        }
+        Area::new(
+            Point::new(start.0, start.1)?,
+            Point::new(end.0 - 1, end.1 - 1)?,
+        )
+    }
+
+    /// Area covering the whole panel of a display of `size`
+    pub fn panel(size: DisplaySize) -> Self {
+        let (width, height) = size.dimensions();
+        Area {
+            start: Point { x: 0, y: 0 },
+            end: Point {
+                x: width - 1,
+                y: height - 1,
+            },
+        }
+    }
+
+    /// Panel area of the logical rectangle at (`x`, `y`) of `width` by `height` pixels on a display
+    /// of `size` with `rotation`. Fails with `OutOfBoundsError` if the rectangle is empty or
+    /// doesn't fit on the rotated display.
+    pub fn from_logical(
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        size: DisplaySize,
+        rotation: DisplayRotation,
+    ) -> Result<Self, DisplayError> {
+        if width == 0 || height == 0 {
+            return Err(DisplayError::OutOfBoundsError);
+        }
+        let last = |start: u32, len: u32| {
+            start
+                .checked_add(len - 1)
+                .ok_or(DisplayError::OutOfBoundsError)
+        };
+        let start = Point::from_logical(x, y, size, rotation)?;
+        let end = Point::from_logical(last(x, width)?, last(y, height)?, size, rotation)?;
+        Area::new(start, end)
+    }
+
+    /// Top left corner
+    pub fn start(&self) -> Point {
+        self.start
+    }
+
+    /// Bottom right corner, included in the area
+    pub fn end(&self) -> Point {
+        self.end
+    }
+
+    /// Number of RAM columns covered
+    pub fn width(&self) -> u8 {
+        self.end.x - self.start.x + 1
+    }
+
+    /// Number of RAM rows covered
+    pub fn height(&self) -> u8 {
+        self.end.y - self.start.y + 1
+    }
+}
+
+/// Display dimensions, taking into account the rotation of the display
+pub(crate) fn logical_dimensions(size: DisplaySize, rotation: DisplayRotation) -> (u8, u8) {
+    let (w, h) = size.dimensions();
+
+    match rotation {
+        DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (w, h),
+        DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => (h, w),
+    }
+}
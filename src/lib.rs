@@ -1,22 +1,37 @@
 #![no_std]
 #![allow(clippy::result_unit_err)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
 extern crate embedded_hal as hal;
 
 pub mod builder;
+#[cfg(feature = "graphics")]
+pub mod canvas;
 pub mod command;
 pub mod display;
+pub mod geometry;
 pub mod mode;
 pub mod prelude;
 pub mod properties;
+#[cfg(feature = "images")]
+pub mod qoi;
+#[cfg(feature = "graphics")]
+mod raw;
+#[cfg(feature = "images")]
+pub mod rle;
 
 #[cfg(feature = "async")]
-pub mod async_interface;
+pub mod async_builder;
 #[cfg(feature = "async")]
-pub mod async_spi_interface;
+pub mod async_command;
 #[cfg(feature = "async")]
 pub mod async_display;
 #[cfg(feature = "async")]
-pub mod async_command;
+pub mod async_frame_loop;
 #[cfg(feature = "async")]
-pub mod async_builder;
+pub mod async_interface;
+#[cfg(all(feature = "embassy", feature = "graphics"))]
+pub mod async_shared;
+#[cfg(feature = "async")]
+pub mod async_spi_interface;
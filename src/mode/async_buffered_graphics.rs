@@ -0,0 +1,295 @@
+//! Async buffered graphics mode for Embassy compatibility
+
+use crate::async_display::{hardware_reset, timeout, AsyncDisplay, TimeoutError};
+use crate::async_interface::AsyncWriteOnlyDataCommand;
+use crate::mode::async_displaymode::AsyncDisplayModeTrait;
+use crate::properties::DisplayRotation;
+use display_interface::DisplayError;
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::delay::DelayNs;
+
+/// Async Graphics Mode for the display, drawing into a framebuffer
+pub struct AsyncBufferedGraphicsMode<DI, BUF>
+where
+    DI: AsyncWriteOnlyDataCommand,
+{
+    display: AsyncDisplay<DI>,
+    buffer: BUF,
+}
+
+impl<DI, BUF> AsyncDisplayModeTrait<DI, BUF> for AsyncBufferedGraphicsMode<DI, BUF>
+where
+    DI: AsyncWriteOnlyDataCommand,
+    BUF: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Create new AsyncBufferedGraphicsMode instance
+    fn new(display: AsyncDisplay<DI>, buffer: BUF) -> Self {
+        AsyncBufferedGraphicsMode::new(display, buffer)
+    }
+
+    /// Release all resources used by AsyncBufferedGraphicsMode
+    fn release(self) -> (AsyncDisplay<DI>, BUF) {
+        AsyncBufferedGraphicsMode::release(self)
+    }
+}
+
+impl<DI, BUF> AsyncBufferedGraphicsMode<DI, BUF>
+where
+    DI: AsyncWriteOnlyDataCommand,
+    BUF: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Create new AsyncBufferedGraphicsMode instance with buffer
+    pub fn new(display: AsyncDisplay<DI>, buffer: BUF) -> Self {
+        AsyncBufferedGraphicsMode { display, buffer }
+    }
+
+    /// Release all resources used by AsyncBufferedGraphicsMode
+    pub fn release(self) -> (AsyncDisplay<DI>, BUF) {
+        (self.display, self.buffer)
+    }
+
+    /// Clear the display
+    pub async fn clear(&mut self, flush: bool) -> Result<(), DisplayError> {
+        self.buffer.as_mut().fill(0);
+        if flush {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Reset display, waiting on `delay` asynchronously
+    pub async fn reset<RST, DELAY>(
+        &mut self,
+        rst: &mut RST,
+        delay: &mut DELAY,
+    ) -> Result<(), RST::Error>
+    where
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        hardware_reset(rst, delay).await
+    }
+
+    /// Reset display asynchronously, waiting on the Embassy timer
+    #[cfg(feature = "embassy")]
+    pub async fn reset_async<RST>(&mut self, rst: &mut RST) -> Result<(), RST::Error>
+    where
+        RST: OutputPin,
+    {
+        self.reset(rst, &mut embassy_time::Delay).await
+    }
+
+    /// Access the framebuffer
+    pub fn fb(&self) -> &[u8] {
+        self.buffer.as_ref()
+    }
+
+    /// Access the framebuffer
+    pub fn fb_mut(&mut self) -> &mut [u8] {
+        self.buffer.as_mut()
+    }
+
+    /// Set the raw colour of a pixel. If the X and Y coordinates are out of the bounds of the
+    /// display or the framebuffer, this method call is a noop.
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: u16) {
+        let (width, height) = self.display.get_dimensions();
+        if x >= width as u32 || y >= height as u32 {
+            return;
+        }
+        let idx = (y as usize * width as usize + x as usize) * 2;
+        if let Some(bytes) = self.buffer.as_mut().get_mut(idx..idx + 2) {
+            bytes.copy_from_slice(&color.to_be_bytes());
+        }
+    }
+
+    /// Send the framebuffer to the display. Errors are handled as the display's
+    /// [`RecoveryPolicy`](../../async_display/enum.RecoveryPolicy.html) says. A framebuffer not
+    /// holding exactly one frame, e.g. one handed over by mode switching, is reported as
+    /// `DisplayError::OutOfBoundsError`.
+    pub async fn flush(&mut self) -> Result<(), DisplayError> {
+        self.display
+            .flush_frame(&mut (), self.buffer.as_ref(), None)
+            .await
+    }
+
+    /// Send the framebuffer to the display as `flush`, `chunk_bytes` at a time, yielding to the
+    /// executor between chunks so other tasks get to run during the transfer
+    ///
+    /// The future can be dropped halfway through, e.g. when racing it against a timeout. The
+    /// next flush sends the whole framebuffer again.
+    pub async fn flush_chunked(&mut self, chunk_bytes: usize) -> Result<(), DisplayError> {
+        self.display
+            .flush_frame(&mut (), self.buffer.as_ref(), Some(chunk_bytes))
+            .await
+    }
+
+    /// Send the framebuffer to the display as `flush`, failing an attempt with
+    /// `TimeoutError::TimedOut` if it doesn't complete within `timeout_ms` milliseconds as
+    /// measured by `delay`. Timed out attempts are recovered from like any other error.
+    pub async fn flush_timeout<DELAY>(
+        &mut self,
+        delay: &mut DELAY,
+        timeout_ms: u32,
+    ) -> Result<(), TimeoutError>
+    where
+        DELAY: DelayNs,
+    {
+        self.display
+            .flush_frame(&mut (delay, timeout_ms), self.buffer.as_ref(), None)
+            .await
+    }
+
+    /// Run `op` on the mode, failing with `TimeoutError::TimedOut` if it doesn't complete
+    /// within `timeout_ms` milliseconds as measured by `delay`
+    pub async fn with_timeout<DELAY, T>(
+        &mut self,
+        delay: &mut DELAY,
+        timeout_ms: u32,
+        op: impl AsyncFnOnce(&mut Self) -> Result<T, DisplayError>,
+    ) -> Result<T, TimeoutError>
+    where
+        DELAY: DelayNs,
+    {
+        timeout(delay, timeout_ms, op(self)).await
+    }
+
+    /// Display is set up in column mode, i.e. a byte walks down a column of 8 pixels from
+    /// column 0 on the left, to column _n_ on the right
+    pub async fn init(&mut self) -> Result<(), DisplayError> {
+        self.display.init().await?;
+        Ok(())
+    }
+
+    /// Set the display rotation
+    pub async fn set_rotation(&mut self, rot: DisplayRotation) -> Result<(), DisplayError> {
+        self.display.set_rotation(rot).await
+    }
+
+    /// Get display dimensions, taking into account the current rotation of the display
+    pub fn get_dimensions(&self) -> (u8, u8) {
+        self.display.get_dimensions()
+    }
+}
+
+#[cfg(feature = "graphics")]
+extern crate embedded_graphics_core;
+#[cfg(feature = "graphics")]
+use self::embedded_graphics_core::pixelcolor::{raw::RawU16, Rgb565};
+#[cfg(feature = "graphics")]
+use self::embedded_graphics_core::prelude::{
+    Dimensions, DrawTarget, OriginDimensions, Pixel, RawData, Size,
+};
+#[cfg(feature = "graphics")]
+use self::embedded_graphics_core::primitives::Rectangle;
+#[cfg(all(feature = "embassy", feature = "graphics"))]
+use crate::display::rows_area;
+#[cfg(feature = "graphics")]
+use crate::raw::{self, RawPixel};
+
+#[cfg(feature = "graphics")]
+impl<DI, BUF> AsyncBufferedGraphicsMode<DI, BUF>
+where
+    DI: AsyncWriteOnlyDataCommand,
+    BUF: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Copy big endian RGB565 `data`, holding the pixels of `area` row by row, into the
+    /// framebuffer. Parts of the area outside the display are skipped. Data of the wrong length is
+    /// reported as `DisplayError::OutOfBoundsError`.
+    pub fn draw_raw(&mut self, area: &Rectangle, data: &[u8]) -> Result<(), DisplayError> {
+        self.draw_raw_pixels(area, data, None)
+    }
+
+    /// Copy RGB565 `data`, holding the pixels of `area` row by row, as `draw_raw`
+    pub fn draw_raw_u16(&mut self, area: &Rectangle, data: &[u16]) -> Result<(), DisplayError> {
+        self.draw_raw_pixels(area, data, None)
+    }
+
+    /// Copy big endian RGB565 `data` as `draw_raw`, leaving pixels of colour `key` transparent
+    pub fn draw_raw_keyed(
+        &mut self,
+        area: &Rectangle,
+        data: &[u8],
+        key: Rgb565,
+    ) -> Result<(), DisplayError> {
+        self.draw_raw_pixels(area, data, Some(key))
+    }
+
+    /// Copy RGB565 `data` as `draw_raw_u16`, leaving pixels of colour `key` transparent
+    pub fn draw_raw_u16_keyed(
+        &mut self,
+        area: &Rectangle,
+        data: &[u16],
+        key: Rgb565,
+    ) -> Result<(), DisplayError> {
+        self.draw_raw_pixels(area, data, Some(key))
+    }
+
+    /// Send `rows` rows of the framebuffer starting at row `top` to the display
+    #[cfg(feature = "embassy")]
+    pub(crate) async fn flush_rows(&mut self, top: u8, rows: u8) -> Result<(), DisplayError> {
+        let (width, _) = self.display.get_dimensions();
+        let stride = width as usize * 2;
+        let bytes = self
+            .buffer
+            .as_ref()
+            .get(top as usize * stride..(top as usize + rows as usize) * stride)
+            .ok_or(DisplayError::OutOfBoundsError)?;
+
+        let (start, end) = rows_area(self.display.get_rotation(), width, top, rows);
+        self.display.set_draw_area(start, end).await?;
+        self.display.draw(bytes).await
+    }
+
+    fn draw_raw_pixels<T: RawPixel>(
+        &mut self,
+        area: &Rectangle,
+        data: &[T],
+        key: Option<Rgb565>,
+    ) -> Result<(), DisplayError> {
+        let bounds = self.bounding_box();
+        let (stride, _) = self.display.get_dimensions();
+        raw::copy_to_framebuffer(
+            self.buffer.as_mut(),
+            stride as usize,
+            &bounds,
+            area,
+            data,
+            key.map(|key| RawU16::from(key).into_inner()),
+        )
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<DI, BUF> DrawTarget for AsyncBufferedGraphicsMode<DI, BUF>
+where
+    DI: AsyncWriteOnlyDataCommand,
+    BUF: AsRef<[u8]> + AsMut<[u8]>,
+{
+    type Color = Rgb565;
+    type Error = ();
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let bb = self.bounding_box();
+
+        pixels
+            .into_iter()
+            .filter(|Pixel(pos, _)| bb.contains(*pos))
+            .for_each(|Pixel(pos, color)| {
+                self.set_pixel(pos.x as u32, pos.y as u32, RawU16::from(color).into_inner())
+            });
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<DI: AsyncWriteOnlyDataCommand, BUF> OriginDimensions for AsyncBufferedGraphicsMode<DI, BUF> {
+    fn size(&self) -> Size {
+        let dim = self.display.get_dimensions();
+        Size::from((dim.0 as u32, dim.1 as u32))
+    }
+}
@@ -0,0 +1,46 @@
+//! Abstraction of different async operating modes for the SSD1351
+
+use crate::async_display::AsyncDisplay;
+use crate::async_interface::AsyncWriteOnlyDataCommand;
+
+/// Async display abstraction
+pub struct AsyncDisplayMode<MODE> {
+    pub display: MODE,
+}
+
+/// Trait with core functionality for async display switching, cf.
+/// [`DisplayModeTrait`](../displaymode/trait.DisplayModeTrait.html)
+///
+/// `BUF` is the framebuffer storage handed from mode to mode, e.g. `[u8; N]`,
+/// `&'static mut [u8]` or anything else implementing `AsMut<[u8]>`. Modes without a framebuffer
+/// use `()`.
+pub trait AsyncDisplayModeTrait<DI, BUF = ()> {
+    /// Allocate all required data and initialise display for display
+    fn new(display: AsyncDisplay<DI>, buffer: BUF) -> Self;
+
+    /// Release resources for reuse with different display
+    fn release(self) -> (AsyncDisplay<DI>, BUF);
+}
+
+impl<MODE> AsyncDisplayMode<MODE> {
+    /// Setup display to run in requested display
+    pub fn new<DI, BUF>(display: AsyncDisplay<DI>, buffer: BUF) -> Self
+    where
+        DI: AsyncWriteOnlyDataCommand,
+        MODE: AsyncDisplayModeTrait<DI, BUF>,
+    {
+        AsyncDisplayMode {
+            display: MODE::new(display, buffer),
+        }
+    }
+
+    /// Change into any display implementing AsyncDisplayModeTrait
+    pub fn into<DI, BUF, NMODE: AsyncDisplayModeTrait<DI, BUF>>(self) -> NMODE
+    where
+        DI: AsyncWriteOnlyDataCommand,
+        MODE: AsyncDisplayModeTrait<DI, BUF>,
+    {
+        let (display, buffer) = self.display.release();
+        NMODE::new(display, buffer)
+    }
+}
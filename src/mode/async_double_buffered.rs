@@ -0,0 +1,359 @@
+//! Async double buffered graphics mode
+//!
+//! Two framebuffers take turns: the front buffer is sent to the display while the next frame is
+//! drawn into the back buffer. With an interface doing DMA transfers, such as
+//! [`AsyncSPIInterface`](../../async_spi_interface/struct.AsyncSPIInterface.html) on an async SPI
+//! bus, drawing runs on the CPU during the transfer and frames follow each other close to the bus
+//! frame rate.
+//!
+//! ```rust,ignore
+//! let mut display = AsyncBuilder::new().connect_interface_double_buffered(
+//!     interface,
+//!     [0u8; FB_SIZE],
+//!     [0u8; FB_SIZE],
+//! )?;
+//!
+//! let mut frame = 0;
+//! loop {
+//!     // Send the frame drawn last time round while drawing the next one
+//!     let (_, sent) = display
+//!         .swap_and_flush(|back| draw_scene(back, frame))
+//!         .await;
+//!     sent?;
+//!     frame += 1;
+//! }
+//! ```
+
+use core::future::{poll_fn, Future};
+use core::pin::pin;
+use core::task::Poll;
+
+use crate::async_display::{hardware_reset, AsyncDisplay};
+use crate::async_interface::AsyncWriteOnlyDataCommand;
+use crate::mode::async_displaymode::AsyncDisplayModeTrait;
+use crate::properties::DisplayRotation;
+use display_interface::DisplayError;
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::delay::DelayNs;
+
+/// Async Graphics Mode for the display, drawing into one framebuffer while sending the other
+pub struct AsyncDoubleBufferedGraphicsMode<DI, BUF>
+where
+    DI: AsyncWriteOnlyDataCommand,
+{
+    display: AsyncDisplay<DI>,
+    buffers: [BUF; 2],
+    front: usize,
+}
+
+impl<DI, BUF> AsyncDisplayModeTrait<DI, [BUF; 2]> for AsyncDoubleBufferedGraphicsMode<DI, BUF>
+where
+    DI: AsyncWriteOnlyDataCommand,
+    BUF: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Create new AsyncDoubleBufferedGraphicsMode instance, the front buffer first
+    fn new(display: AsyncDisplay<DI>, [front, back]: [BUF; 2]) -> Self {
+        AsyncDoubleBufferedGraphicsMode::new(display, front, back)
+    }
+
+    /// Release all resources used by AsyncDoubleBufferedGraphicsMode, the front buffer first
+    fn release(self) -> (AsyncDisplay<DI>, [BUF; 2]) {
+        let (display, front, back) = AsyncDoubleBufferedGraphicsMode::release(self);
+        (display, [front, back])
+    }
+}
+
+impl<DI, BUF> AsyncDoubleBufferedGraphicsMode<DI, BUF>
+where
+    DI: AsyncWriteOnlyDataCommand,
+    BUF: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Create new AsyncDoubleBufferedGraphicsMode instance with a front and a back buffer
+    pub fn new(display: AsyncDisplay<DI>, front: BUF, back: BUF) -> Self {
+        AsyncDoubleBufferedGraphicsMode {
+            display,
+            buffers: [front, back],
+            front: 0,
+        }
+    }
+
+    /// Release all resources used by AsyncDoubleBufferedGraphicsMode, the front buffer first
+    pub fn release(self) -> (AsyncDisplay<DI>, BUF, BUF) {
+        let [a, b] = self.buffers;
+        if self.front == 0 {
+            (self.display, a, b)
+        } else {
+            (self.display, b, a)
+        }
+    }
+
+    /// Clear both buffers, and the display if `flush` is set
+    pub async fn clear(&mut self, flush: bool) -> Result<(), DisplayError> {
+        for buffer in &mut self.buffers {
+            buffer.as_mut().fill(0);
+        }
+        if flush {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Reset display, waiting on `delay` asynchronously
+    pub async fn reset<RST, DELAY>(
+        &mut self,
+        rst: &mut RST,
+        delay: &mut DELAY,
+    ) -> Result<(), RST::Error>
+    where
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        hardware_reset(rst, delay).await
+    }
+
+    /// Reset display asynchronously, waiting on the Embassy timer
+    #[cfg(feature = "embassy")]
+    pub async fn reset_async<RST>(&mut self, rst: &mut RST) -> Result<(), RST::Error>
+    where
+        RST: OutputPin,
+    {
+        self.reset(rst, &mut embassy_time::Delay).await
+    }
+
+    /// The back buffer, to draw the next frame into
+    pub fn back(&mut self) -> Frame<'_> {
+        let dimensions = self.display.get_dimensions();
+        Frame::new(self.buffers[self.front ^ 1].as_mut(), dimensions)
+    }
+
+    /// Access the front buffer, holding the frame last sent to the display
+    pub fn front(&self) -> &[u8] {
+        self.buffers[self.front].as_ref()
+    }
+
+    /// Swap the buffers, then send the new front buffer to the display while `draw` draws the
+    /// next frame into the new back buffer. Completes once both the transfer and `draw` are done,
+    /// returning what `draw` returned along with the result of the transfer.
+    ///
+    /// Transfer errors are handled as the display's
+    /// [`RecoveryPolicy`](../../async_display/enum.RecoveryPolicy.html) says. `draw` runs and the
+    /// buffers are swapped whatever the outcome, so a frame that failed to go out is followed by
+    /// the one drawn meanwhile.
+    ///
+    /// The back buffer handed to `draw` still holds the frame before the one being sent, so it
+    /// should redraw everything that changed over the last two frames, or clear it first.
+    pub async fn swap_and_flush<F, R>(&mut self, draw: F) -> (R, Result<(), DisplayError>)
+    where
+        F: FnOnce(&mut Frame<'_>) -> R,
+    {
+        self.front ^= 1;
+
+        let [a, b] = &mut self.buffers;
+        let (front, back) = if self.front == 0 { (a, b) } else { (b, a) };
+        let mut back = Frame::new(back.as_mut(), self.display.get_dimensions());
+        let (sent, drawn) = join(
+            self.display.flush_frame(&mut (), front.as_ref(), None),
+            async { draw(&mut back) },
+        )
+        .await;
+        (drawn, sent)
+    }
+
+    /// Send the front buffer to the display again. Errors are handled as the display's
+    /// [`RecoveryPolicy`](../../async_display/enum.RecoveryPolicy.html) says.
+    pub async fn flush(&mut self) -> Result<(), DisplayError> {
+        self.display
+            .flush_frame(&mut (), self.buffers[self.front].as_ref(), None)
+            .await
+    }
+
+    /// Display is set up in column mode, i.e. a byte walks down a column of 8 pixels from
+    /// column 0 on the left, to column _n_ on the right
+    pub async fn init(&mut self) -> Result<(), DisplayError> {
+        self.display.init().await?;
+        Ok(())
+    }
+
+    /// Set the display rotation
+    pub async fn set_rotation(&mut self, rot: DisplayRotation) -> Result<(), DisplayError> {
+        self.display.set_rotation(rot).await
+    }
+
+    /// Get display dimensions, taking into account the current rotation of the display
+    pub fn get_dimensions(&self) -> (u8, u8) {
+        self.display.get_dimensions()
+    }
+}
+
+/// Run two futures concurrently, polling `a` first so a transfer is under way before `b` runs
+async fn join<A, B>(a: A, b: B) -> (A::Output, B::Output)
+where
+    A: Future,
+    B: Future,
+{
+    let (mut a, mut b) = (pin!(a), pin!(b));
+    let (mut out_a, mut out_b) = (None, None);
+
+    poll_fn(|cx| {
+        if out_a.is_none() {
+            if let Poll::Ready(out) = a.as_mut().poll(cx) {
+                out_a = Some(out);
+            }
+        }
+        if out_b.is_none() {
+            if let Poll::Ready(out) = b.as_mut().poll(cx) {
+                out_b = Some(out);
+            }
+        }
+        match (out_a.take(), out_b.take()) {
+            (Some(a), Some(b)) => Poll::Ready((a, b)),
+            (a, b) => {
+                out_a = a;
+                out_b = b;
+                Poll::Pending
+            }
+        }
+    })
+    .await
+}
+
+/// Framebuffer of an [`AsyncDoubleBufferedGraphicsMode`] to draw a frame into
+pub struct Frame<'a> {
+    buffer: &'a mut [u8],
+    /// Display dimensions, taking into account the rotation of the display
+    dimensions: (u8, u8),
+}
+
+impl<'a> Frame<'a> {
+    fn new(buffer: &'a mut [u8], dimensions: (u8, u8)) -> Self {
+        Frame { buffer, dimensions }
+    }
+
+    /// Access the framebuffer
+    pub fn fb(&self) -> &[u8] {
+        self.buffer
+    }
+
+    /// Access the framebuffer
+    pub fn fb_mut(&mut self) -> &mut [u8] {
+        self.buffer
+    }
+
+    /// Set the raw colour of a pixel. If the X and Y coordinates are out of the bounds of the
+    /// display or the framebuffer, this method call is a noop.
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: u16) {
+        let (width, height) = self.dimensions;
+        if x >= width as u32 || y >= height as u32 {
+            return;
+        }
+        let idx = (y as usize * width as usize + x as usize) * 2;
+        if let Some(bytes) = self.buffer.get_mut(idx..idx + 2) {
+            bytes.copy_from_slice(&color.to_be_bytes());
+        }
+    }
+}
+
+#[cfg(feature = "graphics")]
+extern crate embedded_graphics_core;
+#[cfg(feature = "graphics")]
+use self::embedded_graphics_core::pixelcolor::{raw::RawU16, Rgb565};
+#[cfg(feature = "graphics")]
+use self::embedded_graphics_core::prelude::{
+    Dimensions, DrawTarget, OriginDimensions, Pixel, RawData, Size,
+};
+#[cfg(feature = "graphics")]
+use self::embedded_graphics_core::primitives::Rectangle;
+#[cfg(feature = "graphics")]
+use crate::raw::{self, RawPixel};
+
+#[cfg(feature = "graphics")]
+impl Frame<'_> {
+    /// Copy big endian RGB565 `data`, holding the pixels of `area` row by row, into the
+    /// framebuffer. Parts of the area outside the display are skipped. Data of the wrong length is
+    /// reported as `DisplayError::OutOfBoundsError`.
+    pub fn draw_raw(&mut self, area: &Rectangle, data: &[u8]) -> Result<(), DisplayError> {
+        self.draw_raw_pixels(area, data, None)
+    }
+
+    /// Copy RGB565 `data`, holding the pixels of `area` row by row, as `draw_raw`
+    pub fn draw_raw_u16(&mut self, area: &Rectangle, data: &[u16]) -> Result<(), DisplayError> {
+        self.draw_raw_pixels(area, data, None)
+    }
+
+    /// Copy big endian RGB565 `data` as `draw_raw`, leaving pixels of colour `key` transparent
+    pub fn draw_raw_keyed(
+        &mut self,
+        area: &Rectangle,
+        data: &[u8],
+        key: Rgb565,
+    ) -> Result<(), DisplayError> {
+        self.draw_raw_pixels(area, data, Some(key))
+    }
+
+    /// Copy RGB565 `data` as `draw_raw_u16`, leaving pixels of colour `key` transparent
+    pub fn draw_raw_u16_keyed(
+        &mut self,
+        area: &Rectangle,
+        data: &[u16],
+        key: Rgb565,
+    ) -> Result<(), DisplayError> {
+        self.draw_raw_pixels(area, data, Some(key))
+    }
+
+    fn draw_raw_pixels<T: RawPixel>(
+        &mut self,
+        area: &Rectangle,
+        data: &[T],
+        key: Option<Rgb565>,
+    ) -> Result<(), DisplayError> {
+        let bounds = self.bounding_box();
+        let (stride, _) = self.dimensions;
+        raw::copy_to_framebuffer(
+            self.buffer,
+            stride as usize,
+            &bounds,
+            area,
+            data,
+            key.map(|key| RawU16::from(key).into_inner()),
+        )
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl DrawTarget for Frame<'_> {
+    type Color = Rgb565;
+    type Error = ();
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let bb = self.bounding_box();
+
+        pixels
+            .into_iter()
+            .filter(|Pixel(pos, _)| bb.contains(*pos))
+            .for_each(|Pixel(pos, color)| {
+                self.set_pixel(pos.x as u32, pos.y as u32, RawU16::from(color).into_inner())
+            });
+
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        let bytes = RawU16::from(color).into_inner().to_be_bytes();
+        for pixel in self.buffer.chunks_exact_mut(2) {
+            pixel.copy_from_slice(&bytes);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl OriginDimensions for Frame<'_> {
+    fn size(&self) -> Size {
+        let dim = self.dimensions;
+        Size::from((dim.0 as u32, dim.1 as u32))
+    }
+}
@@ -1,108 +1,107 @@
 //! Async graphics mode for Embassy compatibility
+//!
+//! `embedded-graphics` can only draw synchronously, so drawing goes through
+//! [`draw_iter_async`](struct.AsyncDirectGraphicsMode.html#method.draw_iter_async),
+//! [`fill_contiguous_async`](struct.AsyncDirectGraphicsMode.html#method.fill_contiguous_async)
+//! and [`fill_solid_async`](struct.AsyncDirectGraphicsMode.html#method.fill_solid_async) instead.
+//! Pixel data is sent in chunks of `CHUNK` bytes, as in
+//! [`DirectGraphicsMode`](../graphics/struct.DirectGraphicsMode.html).
 
-use crate::async_display::AsyncDisplay;
+use crate::async_display::{hardware_reset, timeout, AsyncDisplay, TimeoutError};
 use crate::async_interface::AsyncWriteOnlyDataCommand;
+use crate::geometry::{self, Area};
+use crate::mode::async_displaymode::AsyncDisplayModeTrait;
 use crate::properties::DisplayRotation;
 use display_interface::DisplayError;
 
-#[cfg(feature = "async")]
-use embassy_time::{Timer, Duration};
 use embedded_hal::digital::OutputPin;
+use embedded_hal_async::delay::DelayNs;
 
-/// Async Graphics Mode for the display
-pub struct AsyncGraphicsMode<DI>
+/// Async Graphics Mode for the display, drawing directly to the display RAM
+pub struct AsyncDirectGraphicsMode<DI, const CHUNK: usize = 256>
 where
     DI: AsyncWriteOnlyDataCommand,
 {
     display: AsyncDisplay<DI>,
-    #[cfg(feature = "buffered")]
-    pub buffer: &'static mut [u8],
+    #[cfg(feature = "graphics")]
+    pointer: Option<RamPointer>,
 }
 
-impl<DI> AsyncGraphicsMode<DI>
+/// Async Graphics Mode for the display, alias of [AsyncDirectGraphicsMode]
+pub type AsyncGraphicsMode<DI> = AsyncDirectGraphicsMode<DI>;
+
+impl<DI, const CHUNK: usize> AsyncDisplayModeTrait<DI> for AsyncDirectGraphicsMode<DI, CHUNK>
 where
     DI: AsyncWriteOnlyDataCommand,
 {
-    #[cfg(not(feature = "buffered"))]
-    /// Create new AsyncGraphicsMode instance
-    pub fn new(display: AsyncDisplay<DI>) -> Self {
-        AsyncGraphicsMode { display }
+    /// Create new AsyncDirectGraphicsMode instance
+    fn new(display: AsyncDisplay<DI>, _: ()) -> Self {
+        AsyncDirectGraphicsMode::new(display)
     }
 
-    #[cfg(feature = "buffered")]
-    /// Create new AsyncGraphicsMode instance with buffer
-    pub fn new(display: AsyncDisplay<DI>, buffer: &'static mut [u8]) -> Self {
-        AsyncGraphicsMode { display, buffer }
+    /// Release all resources used by AsyncDirectGraphicsMode
+    fn release(self) -> (AsyncDisplay<DI>, ()) {
+        (AsyncDirectGraphicsMode::release(self), ())
     }
+}
 
-    #[cfg(not(feature = "buffered"))]
-    /// Release all resources used by AsyncGraphicsMode
-    pub fn release(self) -> AsyncDisplay<DI> {
-        self.display
+impl<DI, const CHUNK: usize> AsyncDirectGraphicsMode<DI, CHUNK>
+where
+    DI: AsyncWriteOnlyDataCommand,
+{
+    /// Create new AsyncDirectGraphicsMode instance
+    pub fn new(display: AsyncDisplay<DI>) -> Self {
+        AsyncDirectGraphicsMode {
+            display,
+            #[cfg(feature = "graphics")]
+            pointer: None,
+        }
     }
 
-    #[cfg(feature = "buffered")]
-    /// Release all resources used by AsyncGraphicsMode
-    pub fn release(self) -> (AsyncDisplay<DI>, &'static mut [u8]) {
-        (self.display, self.buffer)
+    /// Release all resources used by AsyncDirectGraphicsMode
+    pub fn release(self) -> AsyncDisplay<DI> {
+        self.display
     }
 
-    #[cfg(not(feature = "buffered"))]
     /// Clear the display
     pub async fn clear(&mut self) -> Result<(), DisplayError> {
+        self.forget_pointer();
         self.display.clear().await
     }
 
-    #[cfg(feature = "buffered")]
-    /// Clear the display
-    pub async fn clear(&mut self, flush: bool) -> Result<(), DisplayError> {
-        for i in 0..self.buffer.len() {
-            self.buffer[i] = 0u8;
-        }
-        if flush {
-            self.flush().await?;
-        }
-        Ok(())
+    /// Reset display, waiting on `delay` asynchronously
+    pub async fn reset<RST, DELAY>(
+        &mut self,
+        rst: &mut RST,
+        delay: &mut DELAY,
+    ) -> Result<(), RST::Error>
+    where
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        hardware_reset(rst, delay).await
     }
 
-    /// Reset display asynchronously
-    #[cfg(feature = "async")]
+    /// Reset display asynchronously, waiting on the Embassy timer
+    #[cfg(feature = "embassy")]
     pub async fn reset_async<RST>(&mut self, rst: &mut RST) -> Result<(), RST::Error>
     where
         RST: OutputPin,
     {
-        rst.set_high()?;
-        Timer::after(Duration::from_millis(1)).await;
-        rst.set_low()?;
-        Timer::after(Duration::from_millis(10)).await;
-        rst.set_high()?;
-        Ok(())
-    }
-
-    #[cfg(feature = "buffered")]
-    /// Access the framebuffer
-    pub fn fb(&self) -> &[u8] {
-        self.buffer
-    }
-
-    #[cfg(feature = "buffered")]
-    /// Access the framebuffer
-    pub fn fb_mut(&mut self) -> &mut [u8] {
-        self.buffer
+        self.reset(rst, &mut embassy_time::Delay).await
     }
 
-    #[cfg(not(feature = "buffered"))]
     /// Turn a pixel on or off. A non-zero `value` is treated as on, `0` as off. If the X and Y
     /// coordinates are out of the bounds of the display, this method call is a noop.
     pub async fn set_pixel(&mut self, x: u32, y: u32, color: u16) -> Result<(), DisplayError> {
-        let (display_width, display_height) = self.display.get_size().dimensions();
-        let rot = self.display.get_rotation();
-        let (nx, ny) = match rot {
-            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (x, y),
-            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => (y, x),
+        self.forget_pointer();
+        let size = self.display.get_size();
+        let Ok(point) = geometry::Point::from_logical(x, y, size, self.display.get_rotation())
+        else {
+            return Ok(());
         };
         self.display
-            .set_draw_area((nx as u8, ny as u8), (display_width, display_height))
+            .set_area(&Area::new(point, Area::panel(size).end())?)
             .await?;
         self.display
             .draw(&[(color >> 8) as u8, color as u8])
@@ -110,34 +109,17 @@ where
         Ok(())
     }
 
-    #[cfg(feature = "buffered")]
-    /// Turn a pixel on or off. A non-zero `value` is treated as on, `0` as off. If the X and Y
-    /// coordinates are out of the bounds of the display, this method call is a noop.
-    pub fn set_pixel(&mut self, x: u32, y: u32, color: u16) {
-        // set bytes in buffer
-        self.buffer[(y as usize * 128usize + x as usize) * 2] = (color >> 8) as u8;
-        self.buffer[((y as usize * 128usize + x as usize) * 2) + 1usize] = color as u8;
-    }
-
-    #[cfg(feature = "buffered")]
-    pub async fn flush(&mut self) -> Result<(), DisplayError> {
-        let (display_width, display_height) = self.display.get_size().dimensions();
-        self.display
-            .set_draw_area((0, 0), (display_width, display_height))
-            .await?;
-        self.display.draw(self.buffer).await?;
-        Ok(())
-    }
-
     /// Display is set up in column mode, i.e. a byte walks down a column of 8 pixels from
     /// column 0 on the left, to column _n_ on the right
     pub async fn init(&mut self) -> Result<(), DisplayError> {
+        self.forget_pointer();
         self.display.init().await?;
         Ok(())
     }
 
     /// Set the display rotation
     pub async fn set_rotation(&mut self, rot: DisplayRotation) -> Result<(), DisplayError> {
+        self.forget_pointer();
         self.display.set_rotation(rot).await
     }
 
@@ -145,22 +127,356 @@ where
     pub fn get_dimensions(&self) -> (u8, u8) {
         self.display.get_dimensions()
     }
+
+    /// Run `op` on the mode, failing with `TimeoutError::TimedOut` if it doesn't complete
+    /// within `timeout_ms` milliseconds as measured by `delay`. The operation is dropped on
+    /// timeout, and the next one sets its draw area again.
+    ///
+    /// ```rust,ignore
+    /// display
+    ///     .with_timeout(&mut delay, 50, async |display| {
+    ///         display.fill_solid_async(&area, Rgb565::RED).await
+    ///     })
+    ///     .await?;
+    /// ```
+    pub async fn with_timeout<DELAY, T>(
+        &mut self,
+        delay: &mut DELAY,
+        timeout_ms: u32,
+        op: impl AsyncFnOnce(&mut Self) -> Result<T, DisplayError>,
+    ) -> Result<T, TimeoutError>
+    where
+        DELAY: DelayNs,
+    {
+        timeout(delay, timeout_ms, op(self)).await
+    }
+
+    /// Forget where the display RAM pointer is, so the next run sets its draw window
+    fn forget_pointer(&mut self) {
+        #[cfg(feature = "graphics")]
+        {
+            self.pointer = None;
+        }
+    }
 }
 
 #[cfg(feature = "graphics")]
 extern crate embedded_graphics_core;
 #[cfg(feature = "graphics")]
-use self::embedded_graphics_core::pixelcolor::Rgb565;
+use self::embedded_graphics_core::pixelcolor::{raw::RawU16, Rgb565};
 #[cfg(feature = "graphics")]
 use self::embedded_graphics_core::prelude::{
-    Dimensions, DrawTarget, OriginDimensions, Pixel, Size,
+    Dimensions, DrawTarget, OriginDimensions, Pixel, Point, PointsIter, RawData, Size,
 };
-#[cfg(all(feature = "graphics", not(feature = "buffered")))]
+#[cfg(feature = "graphics")]
 use self::embedded_graphics_core::primitives::Rectangle;
+#[cfg(feature = "graphics")]
+use crate::canvas::Canvas;
+#[cfg(feature = "graphics")]
+use crate::mode::graphics::{ram_area, ram_position, RamPointer, Run};
+#[cfg(feature = "images")]
+use crate::qoi::QoiDecoder;
+#[cfg(feature = "images")]
+use crate::raw::Span;
+#[cfg(feature = "graphics")]
+use crate::raw::{self, RawPixel};
+#[cfg(feature = "images")]
+use crate::rle::RleDecoder;
+
+#[cfg(feature = "graphics")]
+impl<DI, const CHUNK: usize> AsyncDirectGraphicsMode<DI, CHUNK>
+where
+    DI: AsyncWriteOnlyDataCommand,
+{
+    /// Draw individual pixels, sending runs of pixels that are contiguous in display RAM
+    /// together. Pixels outside the display are ignored.
+    pub async fn draw_iter_async<I>(&mut self, pixels: I) -> Result<(), DisplayError>
+    where
+        I: IntoIterator<Item = Pixel<Rgb565>>,
+    {
+        self.draw_pixels(
+            pixels
+                .into_iter()
+                .map(|Pixel(pos, color)| (pos, RawU16::from(color).into_inner())),
+        )
+        .await
+    }
+
+    /// Draw pixels at logical positions, coalescing those contiguous in display RAM into runs.
+    /// Pixels outside the display are skipped.
+    async fn draw_pixels<I>(&mut self, pixels: I) -> Result<(), DisplayError>
+    where
+        I: IntoIterator<Item = (Point, u16)>,
+    {
+        let size = self.display.get_size();
+        let rotation = self.display.get_rotation();
+        let mut run = Run::<CHUNK>::new();
+
+        for (pos, color) in pixels {
+            let Some(position) = ram_position(size, rotation, pos) else {
+                continue;
+            };
+
+            if !run.push(position, color) {
+                self.write_run(&run).await?;
+                run.clear();
+                run.push(position, color);
+            }
+        }
+
+        if !run.is_empty() {
+            self.write_run(&run).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Fill `area` with `colors` in row-major order, as `DrawTarget::fill_contiguous`. The part
+    /// of the area outside the display is skipped.
+    pub async fn fill_contiguous_async<I>(
+        &mut self,
+        area: &Rectangle,
+        colors: I,
+    ) -> Result<(), DisplayError>
+    where
+        I: IntoIterator<Item = Rgb565>,
+    {
+        const { assert!(CHUNK >= 2, "CHUNK must hold at least one pixel") };
+        let drawable_area = area.intersection(&self.bounding_box());
+        self.pointer = None;
+
+        let rotation = self.display.get_rotation();
+        let Some(ram_area) = ram_area(self.display.get_size(), rotation, &drawable_area) else {
+            return Ok(());
+        };
+        self.display.set_area(&ram_area).await?;
+
+        // Stream the colours of the visible points in chunks
+        let mut chunk = [0u8; CHUNK];
+        let mut len = 0;
+        for color in area
+            .points()
+            .zip(colors)
+            .filter(|(pos, _)| drawable_area.contains(*pos))
+            .map(|(_, color)| RawU16::from(color).into_inner())
+        {
+            chunk[len..len + 2].copy_from_slice(&color.to_be_bytes());
+            len += 2;
+            if len + 2 > CHUNK {
+                self.display.draw(&chunk[..len]).await?;
+                len = 0;
+            }
+        }
+        if len > 0 {
+            self.display.draw(&chunk[..len]).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Fill `area` with a single colour
+    pub async fn fill_solid_async(
+        &mut self,
+        area: &Rectangle,
+        color: Rgb565,
+    ) -> Result<(), DisplayError> {
+        let drawable_area = area.intersection(&self.bounding_box());
+        self.pointer = None;
+
+        let rotation = self.display.get_rotation();
+        let Some(ram_area) = ram_area(self.display.get_size(), rotation, &drawable_area) else {
+            return Ok(());
+        };
+        self.display.set_area(&ram_area).await?;
+
+        let count = drawable_area.size.width as usize * drawable_area.size.height as usize;
+        let mut chunk = [0u8; CHUNK];
+        self.write_solid(&mut chunk, RawU16::from(color).into_inner(), count)
+            .await
+    }
+
+    /// Draw `canvas` with its top left corner at `top_left`, setting the draw window once. Parts
+    /// of the canvas outside the display are skipped.
+    pub async fn blit<const W: usize, const H: usize>(
+        &mut self,
+        canvas: &Canvas<W, H>,
+        top_left: Point,
+    ) -> Result<(), DisplayError> {
+        self.draw_raw(&Rectangle::new(top_left, canvas.size()), canvas.as_bytes())
+            .await
+    }
+
+    /// Draw big endian RGB565 `data`, holding the pixels of `area` row by row, setting the draw
+    /// window once. Parts of the area outside the display are skipped. Data of the wrong length is
+    /// reported as `DisplayError::OutOfBoundsError`.
+    pub async fn draw_raw(&mut self, area: &Rectangle, data: &[u8]) -> Result<(), DisplayError> {
+        self.draw_raw_pixels(area, data, None).await
+    }
+
+    /// Draw RGB565 `data`, holding the pixels of `area` row by row, as `draw_raw`
+    pub async fn draw_raw_u16(
+        &mut self,
+        area: &Rectangle,
+        data: &[u16],
+    ) -> Result<(), DisplayError> {
+        self.draw_raw_pixels(area, data, None).await
+    }
+
+    /// Draw big endian RGB565 `data` as `draw_raw`, leaving pixels of colour `key` transparent.
+    /// Each run of opaque pixels gets its own draw window.
+    pub async fn draw_raw_keyed(
+        &mut self,
+        area: &Rectangle,
+        data: &[u8],
+        key: Rgb565,
+    ) -> Result<(), DisplayError> {
+        self.draw_raw_pixels(area, data, Some(key)).await
+    }
+
+    /// Draw RGB565 `data` as `draw_raw_u16`, leaving pixels of colour `key` transparent
+    pub async fn draw_raw_u16_keyed(
+        &mut self,
+        area: &Rectangle,
+        data: &[u16],
+        key: Rgb565,
+    ) -> Result<(), DisplayError> {
+        self.draw_raw_pixels(area, data, Some(key)).await
+    }
+
+    /// Draw the QOI image `data` with its top left corner at `top_left`, decoding it row by row
+    /// into a window set once. Parts of the image outside the display are skipped, and decoding
+    /// stops after the last visible row. Malformed image data is reported as
+    /// `DisplayError::InvalidFormatError`.
+    #[cfg(feature = "images")]
+    pub async fn draw_qoi(&mut self, data: &[u8], top_left: Point) -> Result<(), DisplayError> {
+        const { assert!(CHUNK >= 2, "CHUNK must hold at least one pixel") };
+        let decoder = QoiDecoder::new(data)?;
+        self.pointer = None;
+        let area = Rectangle::new(top_left, Size::new(decoder.width(), decoder.height()));
+        let Some((window, source)) = raw::window(
+            &area,
+            &self.bounding_box(),
+            self.display.get_size(),
+            self.display.get_rotation(),
+        )?
+        else {
+            return Ok(());
+        };
+        self.display.set_area(&window).await?;
+
+        let mut chunk = [0u8; CHUNK];
+        let mut stream = raw::QoiStream::new(decoder, &source)?;
+        while let Some(bytes) = stream.next(&mut chunk)? {
+            self.display.draw(bytes).await?;
+        }
+
+        Ok(())
+    }
 
-// Note: embedded-graphics doesn't support async yet, so we keep the sync DrawTarget implementation
+    /// Draw the run-length encoded image `data` with its top left corner at `top_left` into a
+    /// window set once. Literal pixels are sent straight from `data`, runs as chunked fills. Parts
+    /// of the image outside the display are skipped, and decoding stops after the last visible row.
+    /// Malformed image data is reported as `DisplayError::InvalidFormatError`.
+    #[cfg(feature = "images")]
+    pub async fn draw_rle(&mut self, data: &[u8], top_left: Point) -> Result<(), DisplayError> {
+        let decoder = RleDecoder::new(data)?;
+        self.pointer = None;
+        let size = Size::new(decoder.width() as u32, decoder.height() as u32);
+        let Some((window, source)) = raw::window(
+            &Rectangle::new(top_left, size),
+            &self.bounding_box(),
+            self.display.get_size(),
+            self.display.get_rotation(),
+        )?
+        else {
+            return Ok(());
+        };
+        self.display.set_area(&window).await?;
+
+        let mut chunk = [0u8; CHUNK];
+        let mut stream = raw::RleStream::new(decoder, &source)?;
+        while let Some(span) = stream.next()? {
+            match span {
+                Span::Pixels(bytes) => self.display.draw(bytes).await?,
+                Span::Solid { color, len } => self.write_solid(&mut chunk, color, len).await?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Send `count` pixels of `color` at the RAM pointer, in chunks
+    async fn write_solid(
+        &mut self,
+        chunk: &mut [u8; CHUNK],
+        color: u16,
+        count: usize,
+    ) -> Result<(), DisplayError> {
+        const { assert!(CHUNK >= 2, "CHUNK must hold at least one pixel") };
+        for len in raw::solid(chunk, color, count) {
+            self.display.draw(&chunk[..len]).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn draw_raw_pixels<T: RawPixel>(
+        &mut self,
+        area: &Rectangle,
+        data: &[T],
+        key: Option<Rgb565>,
+    ) -> Result<(), DisplayError> {
+        const { assert!(CHUNK >= 2, "CHUNK must hold at least one pixel") };
+        raw::check_len(area, data)?;
+        self.pointer = None;
+        if let Some(key) = key {
+            let key = RawU16::from(key).into_inner();
+            let pixels = raw::opaque(area, &self.bounding_box(), data, key);
+            return self.draw_pixels(pixels).await;
+        }
+
+        let Some((window, source)) = raw::window(
+            area,
+            &self.bounding_box(),
+            self.display.get_size(),
+            self.display.get_rotation(),
+        )?
+        else {
+            return Ok(());
+        };
+        self.display.set_area(&window).await?;
+
+        let mut chunk = [0u8; CHUNK];
+        let mut stream = raw::raw_stream(data, area.size.width as usize, &source);
+        while let Some(bytes) = stream.next(&mut chunk) {
+            self.display.draw(bytes).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the pixels of a run, only setting a new draw window if the RAM pointer is not at the
+    /// start of the run already
+    async fn write_run(&mut self, run: &Run<CHUNK>) -> Result<(), DisplayError> {
+        let (window, pointer) = run.window(self.pointer, self.display.get_size().dimensions());
+
+        self.pointer = None;
+        if let Some((start, end)) = window {
+            self.display.set_draw_area(start, end).await?;
+        }
+        self.display.draw(run.bytes()).await?;
+        self.pointer = pointer;
+
+        Ok(())
+    }
+}
+
+// Note: embedded-graphics doesn't support async yet, so the sync DrawTarget implementation can't
+// reach the display. Use the `*_async` drawing methods instead.
 #[cfg(feature = "graphics")]
-impl<DI: AsyncWriteOnlyDataCommand> DrawTarget for AsyncGraphicsMode<DI> {
+impl<DI: AsyncWriteOnlyDataCommand, const CHUNK: usize> DrawTarget
+    for AsyncDirectGraphicsMode<DI, CHUNK>
+{
     type Color = Rgb565;
     type Error = ();
 
@@ -168,37 +484,18 @@ impl<DI: AsyncWriteOnlyDataCommand> DrawTarget for AsyncGraphicsMode<DI> {
     where
         I: IntoIterator<Item = Pixel<Self::Color>>,
     {
-        let bb = self.bounding_box();
-
-        pixels
-            .into_iter()
-            .filter(|Pixel(pos, _)| bb.contains(*pos))
-            .for_each(|Pixel(pos, color)| {
-                #[cfg(not(feature = "buffered"))]
-                {
-                    // For non-buffered mode, we can't easily make this async
-                    // Users should use the async set_pixel method directly
-                    let _ = pos;
-                    let _ = color;
-                }
-                #[cfg(feature = "buffered")]
-                {
-                    use self::embedded_graphics_core::pixelcolor::raw::RawU16;
-                    use self::embedded_graphics_core::prelude::RawData;
-                    self.set_pixel(pos.x as u32, pos.y as u32, RawU16::from(color).into_inner())
-                }
-            });
-
+        // For non-buffered mode, we can't easily make this async
+        // Users should use the async draw_iter_async method directly
+        let _ = pixels;
         Ok(())
     }
 
-    #[cfg(not(feature = "buffered"))]
     fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
     where
         I: IntoIterator<Item = Self::Color>,
     {
         // For async compatibility, this method is simplified for non-buffered mode
-        // Users should use async methods directly for better performance
+        // Users should use fill_contiguous_async for better performance
         let _ = area;
         let _ = colors;
         Ok(())
@@ -206,9 +503,11 @@ impl<DI: AsyncWriteOnlyDataCommand> DrawTarget for AsyncGraphicsMode<DI> {
 }
 
 #[cfg(feature = "graphics")]
-impl<DI: AsyncWriteOnlyDataCommand> OriginDimensions for AsyncGraphicsMode<DI> {
+impl<DI: AsyncWriteOnlyDataCommand, const CHUNK: usize> OriginDimensions
+    for AsyncDirectGraphicsMode<DI, CHUNK>
+{
     fn size(&self) -> Size {
-        let dim = self.display.get_size().dimensions();
+        let dim = self.display.get_dimensions();
         Size::from((dim.0 as u32, dim.1 as u32))
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,241 @@
+//! Async monochrome graphics mode for Embassy compatibility
+//!
+//! See [`MonoGraphicsMode`](../mono/struct.MonoGraphicsMode.html) for how the framebuffer is
+//! laid out and flushed.
+
+use crate::async_display::{hardware_reset, AsyncDisplay};
+use crate::async_interface::AsyncWriteOnlyDataCommand;
+use crate::display::rows_area;
+use crate::mode::async_displaymode::AsyncDisplayModeTrait;
+use crate::mode::mono::{expand_row, next_run, row_mask, set_bit, ALL_ROWS};
+use crate::mode::strip::MAX_ROW_BYTES;
+use crate::properties::DisplayRotation;
+use display_interface::DisplayError;
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::delay::DelayNs;
+
+extern crate embedded_graphics_core;
+use self::embedded_graphics_core::pixelcolor::{BinaryColor, Rgb565, RgbColor};
+use self::embedded_graphics_core::prelude::{
+    Dimensions, DrawTarget, OriginDimensions, Pixel, Size,
+};
+use self::embedded_graphics_core::primitives::Rectangle;
+
+/// Async monochrome graphics mode for the display
+pub struct AsyncMonoGraphicsMode<DI, BUF>
+where
+    DI: AsyncWriteOnlyDataCommand,
+{
+    display: AsyncDisplay<DI>,
+    buffer: BUF,
+    foreground: Rgb565,
+    background: Rgb565,
+    dirty: u128,
+}
+
+impl<DI, BUF> AsyncDisplayModeTrait<DI, BUF> for AsyncMonoGraphicsMode<DI, BUF>
+where
+    DI: AsyncWriteOnlyDataCommand,
+    BUF: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Create new AsyncMonoGraphicsMode instance
+    fn new(display: AsyncDisplay<DI>, buffer: BUF) -> Self {
+        AsyncMonoGraphicsMode::new(display, buffer)
+    }
+
+    /// Release all resources used by AsyncMonoGraphicsMode
+    fn release(self) -> (AsyncDisplay<DI>, BUF) {
+        AsyncMonoGraphicsMode::release(self)
+    }
+}
+
+impl<DI, BUF> AsyncMonoGraphicsMode<DI, BUF>
+where
+    DI: AsyncWriteOnlyDataCommand,
+    BUF: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Create new AsyncMonoGraphicsMode instance drawing white on black
+    pub fn new(display: AsyncDisplay<DI>, buffer: BUF) -> Self {
+        AsyncMonoGraphicsMode {
+            display,
+            buffer,
+            foreground: Rgb565::WHITE,
+            background: Rgb565::BLACK,
+            dirty: ALL_ROWS,
+        }
+    }
+
+    /// Release all resources used by AsyncMonoGraphicsMode
+    pub fn release(self) -> (AsyncDisplay<DI>, BUF) {
+        (self.display, self.buffer)
+    }
+
+    /// Clear the framebuffer to the background colour
+    pub async fn clear(&mut self, flush: bool) -> Result<(), DisplayError> {
+        self.buffer.as_mut().fill(0);
+        self.dirty = ALL_ROWS;
+        if flush {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Reset display, waiting on `delay` asynchronously
+    pub async fn reset<RST, DELAY>(
+        &mut self,
+        rst: &mut RST,
+        delay: &mut DELAY,
+    ) -> Result<(), RST::Error>
+    where
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        hardware_reset(rst, delay).await
+    }
+
+    /// Reset display asynchronously, waiting on the Embassy timer
+    #[cfg(feature = "embassy")]
+    pub async fn reset_async<RST>(&mut self, rst: &mut RST) -> Result<(), RST::Error>
+    where
+        RST: OutputPin,
+    {
+        self.reset(rst, &mut embassy_time::Delay).await
+    }
+
+    /// Access the framebuffer
+    pub fn fb(&self) -> &[u8] {
+        self.buffer.as_ref()
+    }
+
+    /// Access the framebuffer. Every row is sent on the next flush.
+    pub fn fb_mut(&mut self) -> &mut [u8] {
+        self.dirty = ALL_ROWS;
+        self.buffer.as_mut()
+    }
+
+    /// Get the foreground and background colours
+    pub fn colors(&self) -> (Rgb565, Rgb565) {
+        (self.foreground, self.background)
+    }
+
+    /// Set the colours `On` and `Off` pixels are shown in. Every row is sent on the next flush.
+    pub fn set_colors(&mut self, foreground: Rgb565, background: Rgb565) {
+        self.foreground = foreground;
+        self.background = background;
+        self.dirty = ALL_ROWS;
+    }
+
+    /// Turn a pixel on or off. If the X and Y coordinates are out of the bounds of the display,
+    /// this method call is a noop.
+    pub fn set_pixel(&mut self, x: u32, y: u32, on: bool) {
+        let (width, height) = self.display.get_dimensions();
+        if x >= width as u32 || y >= height as u32 {
+            return;
+        }
+        if set_bit(self.buffer.as_mut(), width, x, y, on) {
+            self.dirty |= row_mask(y, 1);
+        }
+    }
+
+    /// Send the rows changed since the last flush to the display. Fails with
+    /// `DisplayError::OutOfBoundsError` if the framebuffer is too small for the display.
+    pub async fn flush(&mut self) -> Result<(), DisplayError> {
+        let (width, height) = self.display.get_dimensions();
+        let stride = width as usize / 8;
+        if self.buffer.as_ref().len() < stride * height as usize {
+            return Err(DisplayError::OutOfBoundsError);
+        }
+
+        let rotation = self.display.get_rotation();
+        let mut row = [0u8; MAX_ROW_BYTES];
+        let mut top = 0;
+        while let Some((start, rows)) = next_run(self.dirty, top, height) {
+            let (area_start, area_end) = rows_area(rotation, width, start, rows);
+            self.display.set_draw_area(area_start, area_end).await?;
+
+            for y in start as usize..(start + rows) as usize {
+                let bits = &self.buffer.as_ref()[y * stride..(y + 1) * stride];
+                let pixels = expand_row(bits, width, self.foreground, self.background, &mut row);
+                self.display.draw(pixels).await?;
+            }
+
+            self.dirty &= !row_mask(start as u32, rows as u32);
+            top = start + rows;
+        }
+
+        self.dirty = 0;
+        Ok(())
+    }
+
+    /// Display is set up in column mode, i.e. a byte walks down a column of 8 pixels from
+    /// column 0 on the left, to column _n_ on the right
+    pub async fn init(&mut self) -> Result<(), DisplayError> {
+        self.display.init().await?;
+        self.dirty = ALL_ROWS;
+        Ok(())
+    }
+
+    /// Set the display rotation. Every row is sent on the next flush.
+    pub async fn set_rotation(&mut self, rot: DisplayRotation) -> Result<(), DisplayError> {
+        self.dirty = ALL_ROWS;
+        self.display.set_rotation(rot).await
+    }
+
+    /// Get display dimensions, taking into account the current rotation of the display
+    pub fn get_dimensions(&self) -> (u8, u8) {
+        self.display.get_dimensions()
+    }
+}
+
+impl<DI, BUF> DrawTarget for AsyncMonoGraphicsMode<DI, BUF>
+where
+    DI: AsyncWriteOnlyDataCommand,
+    BUF: AsRef<[u8]> + AsMut<[u8]>,
+{
+    type Color = BinaryColor;
+    type Error = ();
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let bb = self.bounding_box();
+
+        pixels
+            .into_iter()
+            .filter(|Pixel(pos, _)| bb.contains(*pos))
+            .for_each(|Pixel(pos, color)| {
+                self.set_pixel(pos.x as u32, pos.y as u32, color.is_on())
+            });
+
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let area = area.intersection(&self.bounding_box());
+
+        for y in area.rows() {
+            for x in area.columns() {
+                self.set_pixel(x as u32, y as u32, color.is_on());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.buffer
+            .as_mut()
+            .fill(if color.is_on() { 0xFF } else { 0x00 });
+        self.dirty = ALL_ROWS;
+        Ok(())
+    }
+}
+
+impl<DI: AsyncWriteOnlyDataCommand, BUF> OriginDimensions for AsyncMonoGraphicsMode<DI, BUF> {
+    fn size(&self) -> Size {
+        let dim = self.display.get_dimensions();
+        Size::from((dim.0 as u32, dim.1 as u32))
+    }
+}
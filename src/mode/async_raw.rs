@@ -0,0 +1,66 @@
+//! Async raw mode for coercion into richer driver types
+//!
+//! An async display driver instance without high level functionality used as a return type from
+//! the builder, cf. [`RawMode`](../raw/struct.RawMode.html). Used as a source to coerce the driver
+//! into richer async modes like [`AsyncGraphicsMode`](../async_graphics/index.html) and
+//! [`AsyncBufferedGraphicsMode`](../async_buffered_graphics/index.html), or to write pixel data to
+//! windows of the display RAM directly.
+
+use crate::async_display::AsyncDisplay;
+use crate::async_interface::AsyncWriteOnlyDataCommand;
+use crate::geometry::Area;
+use display_interface::DisplayError;
+
+use crate::mode::async_displaymode::AsyncDisplayModeTrait;
+
+/// Async raw display mode
+pub struct AsyncRawMode<DI, BUF = ()>
+where
+    DI: AsyncWriteOnlyDataCommand,
+{
+    pub display: AsyncDisplay<DI>,
+    /// Framebuffer storage handed on to buffered modes, `()` if there is none
+    pub buffer: BUF,
+}
+
+impl<DI, BUF> AsyncDisplayModeTrait<DI, BUF> for AsyncRawMode<DI, BUF>
+where
+    DI: AsyncWriteOnlyDataCommand,
+{
+    /// Create new AsyncRawMode instance
+    fn new(display: AsyncDisplay<DI>, buffer: BUF) -> Self {
+        AsyncRawMode { display, buffer }
+    }
+
+    /// Release all resources used by AsyncRawMode
+    fn release(self) -> (AsyncDisplay<DI>, BUF) {
+        (self.display, self.buffer)
+    }
+}
+
+impl<DI, BUF> AsyncRawMode<DI, BUF>
+where
+    DI: AsyncWriteOnlyDataCommand,
+{
+    /// Set the window of the display RAM written by `draw`
+    pub async fn set_area(&mut self, area: &Area) -> Result<(), DisplayError> {
+        self.display.set_area(area).await
+    }
+
+    /// Send big endian RGB565 pixel data to the current window, continuing where the last write
+    /// stopped and wrapping within the window
+    pub async fn draw(&mut self, data: &[u8]) -> Result<(), DisplayError> {
+        self.display.draw(data).await
+    }
+
+    /// Fill the panel `area` with big endian RGB565 `data`, holding its pixels in the order the
+    /// display RAM is written: along RAM rows, or along RAM columns when rotated by 90 or 270
+    /// degrees. Data of the wrong length is reported as `DisplayError::OutOfBoundsError`.
+    pub async fn draw_window(&mut self, area: &Area, data: &[u8]) -> Result<(), DisplayError> {
+        if data.len() != area.width() as usize * area.height() as usize * 2 {
+            return Err(DisplayError::OutOfBoundsError);
+        }
+        self.display.set_area(area).await?;
+        self.display.draw(data).await
+    }
+}
@@ -0,0 +1,128 @@
+//! Async strip rendering mode for Embassy compatibility
+//!
+//! See [`StripMode`](../strip/struct.StripMode.html) for how strip rendering works.
+
+use crate::async_display::{hardware_reset, AsyncDisplay};
+use crate::async_interface::AsyncWriteOnlyDataCommand;
+use crate::display::rows_area;
+use crate::mode::async_displaymode::AsyncDisplayModeTrait;
+use crate::mode::strip::{band_rows, Band};
+use crate::properties::DisplayRotation;
+use display_interface::DisplayError;
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::delay::DelayNs;
+
+/// Async strip rendering mode for the display
+pub struct AsyncStripMode<DI, BUF>
+where
+    DI: AsyncWriteOnlyDataCommand,
+{
+    display: AsyncDisplay<DI>,
+    buffer: BUF,
+}
+
+impl<DI, BUF> AsyncDisplayModeTrait<DI, BUF> for AsyncStripMode<DI, BUF>
+where
+    DI: AsyncWriteOnlyDataCommand,
+    BUF: AsMut<[u8]>,
+{
+    /// Create new AsyncStripMode instance
+    fn new(display: AsyncDisplay<DI>, buffer: BUF) -> Self {
+        AsyncStripMode::new(display, buffer)
+    }
+
+    /// Release all resources used by AsyncStripMode
+    fn release(self) -> (AsyncDisplay<DI>, BUF) {
+        AsyncStripMode::release(self)
+    }
+}
+
+impl<DI, BUF> AsyncStripMode<DI, BUF>
+where
+    DI: AsyncWriteOnlyDataCommand,
+    BUF: AsMut<[u8]>,
+{
+    /// Create new AsyncStripMode instance with buffer
+    pub fn new(display: AsyncDisplay<DI>, buffer: BUF) -> Self {
+        AsyncStripMode { display, buffer }
+    }
+
+    /// Release all resources used by AsyncStripMode
+    pub fn release(self) -> (AsyncDisplay<DI>, BUF) {
+        (self.display, self.buffer)
+    }
+
+    /// Render a full frame. `draw` is called once per band with a [`Band`] covering the next
+    /// rows of the display, and should draw the whole scene into it in display coordinates.
+    /// Pixels outside the band are discarded. Fails with `DisplayError::OutOfBoundsError` if the
+    /// buffer cannot hold a single row.
+    pub async fn render<F>(&mut self, mut draw: F) -> Result<(), DisplayError>
+    where
+        F: FnMut(&mut Band<'_>),
+    {
+        let (width, height) = self.display.get_dimensions();
+        let rotation = self.display.get_rotation();
+        let buffer = self.buffer.as_mut();
+        let band_rows = band_rows(buffer.len(), width)?;
+
+        let mut top = 0;
+        while top < height {
+            let rows = band_rows.min(height - top);
+            let mut band = Band::new(buffer, width, top, rows);
+            draw(&mut band);
+
+            let (start, end) = rows_area(rotation, width, top, rows);
+            self.display.set_draw_area(start, end).await?;
+            self.display.draw(band.into_bytes()).await?;
+
+            top += rows;
+        }
+
+        Ok(())
+    }
+
+    /// Clear the display
+    pub async fn clear(&mut self) -> Result<(), DisplayError> {
+        self.display.clear().await
+    }
+
+    /// Reset display, waiting on `delay` asynchronously
+    pub async fn reset<RST, DELAY>(
+        &mut self,
+        rst: &mut RST,
+        delay: &mut DELAY,
+    ) -> Result<(), RST::Error>
+    where
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        hardware_reset(rst, delay).await
+    }
+
+    /// Reset display asynchronously, waiting on the Embassy timer
+    #[cfg(feature = "embassy")]
+    pub async fn reset_async<RST>(&mut self, rst: &mut RST) -> Result<(), RST::Error>
+    where
+        RST: OutputPin,
+    {
+        self.reset(rst, &mut embassy_time::Delay).await
+    }
+
+    /// Display is set up in column mode, i.e. a byte walks down a column of 8 pixels from
+    /// column 0 on the left, to column _n_ on the right
+    pub async fn init(&mut self) -> Result<(), DisplayError> {
+        self.display.init().await?;
+        Ok(())
+    }
+
+    /// Set the display rotation
+    pub async fn set_rotation(&mut self, rot: DisplayRotation) -> Result<(), DisplayError> {
+        self.display.set_rotation(rot).await
+    }
+
+    /// Get display dimensions, taking into account the current rotation of the display
+    pub fn get_dimensions(&self) -> (u8, u8) {
+        self.display.get_dimensions()
+    }
+}
@@ -0,0 +1,224 @@
+//! Buffered graphics mode
+//!
+//! Drawing happens in a framebuffer in RAM which is sent to the display in one go by
+//! [`BufferedGraphicsMode::flush`]. The framebuffer needs
+//! [`DisplaySize::buffer_size`](../../properties/enum.DisplaySize.html#method.buffer_size)
+//! bytes and can be any storage implementing `AsRef<[u8]> + AsMut<[u8]>`.
+
+use crate::display::Display;
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+use hal::delay::DelayNs;
+use hal::digital::OutputPin;
+
+use crate::mode::displaymode::DisplayModeTrait;
+use crate::properties::DisplayRotation;
+
+/// Graphics Mode for the display, drawing into a framebuffer
+pub struct BufferedGraphicsMode<DI, BUF>
+where
+    DI: WriteOnlyDataCommand,
+{
+    display: Display<DI>,
+    buffer: BUF,
+}
+
+impl<DI, BUF> DisplayModeTrait<DI, BUF> for BufferedGraphicsMode<DI, BUF>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Create new BufferedGraphicsMode instance
+    fn new(display: Display<DI>, buffer: BUF) -> Self {
+        BufferedGraphicsMode { display, buffer }
+    }
+
+    /// Release all resources used by BufferedGraphicsMode
+    fn release(self) -> (Display<DI>, BUF) {
+        (self.display, self.buffer)
+    }
+}
+
+impl<DI, BUF> BufferedGraphicsMode<DI, BUF>
+where
+    DI: WriteOnlyDataCommand,
+    BUF: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Clear the display
+    pub fn clear(&mut self, flush: bool) -> Result<(), DisplayError> {
+        self.buffer.as_mut().fill(0);
+        if flush {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Reset display
+    pub fn reset<RST, DELAY>(&mut self, rst: &mut RST, delay: &mut DELAY) -> Result<(), RST::Error>
+    where
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        rst.set_high()?;
+        delay.delay_ms(1);
+        rst.set_low()?;
+        delay.delay_ms(10);
+        rst.set_high()?;
+        Ok(())
+    }
+
+    /// Access the framebuffer
+    pub fn fb(&self) -> &[u8] {
+        self.buffer.as_ref()
+    }
+
+    /// Access the framebuffer
+    pub fn fb_mut(&mut self) -> &mut [u8] {
+        self.buffer.as_mut()
+    }
+
+    /// Set the raw colour of a pixel. If the X and Y coordinates are out of the bounds of the
+    /// display or the framebuffer, this method call is a noop.
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: u16) {
+        let (width, height) = self.display.get_dimensions();
+        if x >= width as u32 || y >= height as u32 {
+            return;
+        }
+        let idx = (y as usize * width as usize + x as usize) * 2;
+        if let Some(bytes) = self.buffer.as_mut().get_mut(idx..idx + 2) {
+            bytes.copy_from_slice(&color.to_be_bytes());
+        }
+    }
+
+    /// Send the framebuffer to the display. A framebuffer not holding exactly one frame, e.g. one
+    /// handed over by mode switching, is reported as `DisplayError::OutOfBoundsError`.
+    pub fn flush(&mut self) -> Result<(), DisplayError> {
+        let size = self.display.get_size();
+        if self.buffer.as_ref().len() != size.buffer_size() {
+            return Err(DisplayError::OutOfBoundsError);
+        }
+        let (display_width, display_height) = size.dimensions();
+        self.display
+            .set_draw_area((0, 0), (display_width, display_height))?;
+        self.display.draw(self.buffer.as_ref())
+    }
+
+    /// Display is set up in column mode, i.e. a byte walks down a column of 8 pixels from
+    /// column 0 on the left, to column _n_ on the right
+    pub fn init(&mut self) -> Result<(), DisplayError> {
+        self.display.init()?;
+        Ok(())
+    }
+
+    /// Set the display rotation
+    pub fn set_rotation(&mut self, rot: DisplayRotation) -> Result<(), DisplayError> {
+        self.display.set_rotation(rot)
+    }
+
+    /// Get display dimensions, taking into account the current rotation of the display
+    pub fn get_dimensions(&self) -> (u8, u8) {
+        self.display.get_dimensions()
+    }
+}
+
+#[cfg(feature = "graphics")]
+extern crate embedded_graphics_core;
+#[cfg(feature = "graphics")]
+use self::embedded_graphics_core::pixelcolor::{raw::RawU16, Rgb565};
+#[cfg(feature = "graphics")]
+use self::embedded_graphics_core::prelude::{
+    Dimensions, DrawTarget, OriginDimensions, Pixel, RawData, Size,
+};
+#[cfg(feature = "graphics")]
+use self::embedded_graphics_core::primitives::Rectangle;
+#[cfg(feature = "graphics")]
+use crate::raw::{self, RawPixel};
+
+#[cfg(feature = "graphics")]
+impl<DI, BUF> BufferedGraphicsMode<DI, BUF>
+where
+    DI: WriteOnlyDataCommand,
+    BUF: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Copy big endian RGB565 `data`, holding the pixels of `area` row by row, into the
+    /// framebuffer. Parts of the area outside the display are skipped. Data of the wrong length is
+    /// reported as `DisplayError::OutOfBoundsError`.
+    pub fn draw_raw(&mut self, area: &Rectangle, data: &[u8]) -> Result<(), DisplayError> {
+        self.draw_raw_pixels(area, data, None)
+    }
+
+    /// Copy RGB565 `data`, holding the pixels of `area` row by row, as `draw_raw`
+    pub fn draw_raw_u16(&mut self, area: &Rectangle, data: &[u16]) -> Result<(), DisplayError> {
+        self.draw_raw_pixels(area, data, None)
+    }
+
+    /// Copy big endian RGB565 `data` as `draw_raw`, leaving pixels of colour `key` transparent
+    pub fn draw_raw_keyed(
+        &mut self,
+        area: &Rectangle,
+        data: &[u8],
+        key: Rgb565,
+    ) -> Result<(), DisplayError> {
+        self.draw_raw_pixels(area, data, Some(key))
+    }
+
+    /// Copy RGB565 `data` as `draw_raw_u16`, leaving pixels of colour `key` transparent
+    pub fn draw_raw_u16_keyed(
+        &mut self,
+        area: &Rectangle,
+        data: &[u16],
+        key: Rgb565,
+    ) -> Result<(), DisplayError> {
+        self.draw_raw_pixels(area, data, Some(key))
+    }
+
+    fn draw_raw_pixels<T: RawPixel>(
+        &mut self,
+        area: &Rectangle,
+        data: &[T],
+        key: Option<Rgb565>,
+    ) -> Result<(), DisplayError> {
+        let bounds = self.bounding_box();
+        let (stride, _) = self.display.get_dimensions();
+        raw::copy_to_framebuffer(
+            self.buffer.as_mut(),
+            stride as usize,
+            &bounds,
+            area,
+            data,
+            key.map(|key| RawU16::from(key).into_inner()),
+        )
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<DI, BUF> DrawTarget for BufferedGraphicsMode<DI, BUF>
+where
+    DI: WriteOnlyDataCommand,
+    BUF: AsRef<[u8]> + AsMut<[u8]>,
+{
+    type Color = Rgb565;
+    type Error = ();
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let bb = self.bounding_box();
+
+        pixels
+            .into_iter()
+            .filter(|Pixel(pos, _)| bb.contains(*pos))
+            .for_each(|Pixel(pos, color)| {
+                self.set_pixel(pos.x as u32, pos.y as u32, RawU16::from(color).into_inner())
+            });
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<DI: WriteOnlyDataCommand, BUF> OriginDimensions for BufferedGraphicsMode<DI, BUF> {
+    fn size(&self) -> Size {
+        let dim = self.display.get_dimensions();
+        Size::from((dim.0 as u32, dim.1 as u32))
+    }
+}
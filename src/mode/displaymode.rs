@@ -9,40 +9,24 @@ pub struct DisplayMode<MODE> {
 }
 
 /// Trait with core functionality for display switching
-pub trait DisplayModeTrait<DI> {
+///
+/// `BUF` is the framebuffer storage handed from mode to mode, e.g. `[u8; N]`,
+/// `&'static mut [u8]` or anything else implementing `AsMut<[u8]>`. Modes without a framebuffer
+/// use `()`.
+pub trait DisplayModeTrait<DI, BUF = ()> {
     /// Allocate all required data and initialise display for display
-    #[cfg(not(feature = "buffered"))]
-    fn new(display: Display<DI>) -> Self;
-
-    #[cfg(feature = "buffered")]
-    fn new(display: Display<DI>, buffer: &'static mut [u8]) -> Self;
+    fn new(display: Display<DI>, buffer: BUF) -> Self;
 
     /// Release resources for reuse with different display
-    #[cfg(not(feature = "buffered"))]
-    fn release(self) -> Display<DI>;
-
-    #[cfg(feature = "buffered")]
-    fn release(self) -> (Display<DI>, &'static mut [u8]);
+    fn release(self) -> (Display<DI>, BUF);
 }
 
 impl<MODE> DisplayMode<MODE> {
     /// Setup display to run in requested display
-    #[cfg(not(feature = "buffered"))]
-    pub fn new<DI>(display: Display<DI>) -> Self
-    where
-        DI: WriteOnlyDataCommand,
-        MODE: DisplayModeTrait<DI>,
-    {
-        DisplayMode {
-            display: MODE::new(display),
-        }
-    }
-
-    #[cfg(feature = "buffered")]
-    pub fn new<DI>(display: Display<DI>, buffer: &'static mut [u8]) -> Self
+    pub fn new<DI, BUF>(display: Display<DI>, buffer: BUF) -> Self
     where
         DI: WriteOnlyDataCommand,
-        MODE: DisplayModeTrait<DI>,
+        MODE: DisplayModeTrait<DI, BUF>,
     {
         DisplayMode {
             display: MODE::new(display, buffer),
@@ -51,21 +35,10 @@ impl<MODE> DisplayMode<MODE> {
 
     /// Change into any display implementing DisplayModeTrait
     // TODO: Figure out how to stay as generic DisplayMode but act as particular display
-    #[cfg(not(feature = "buffered"))]
-    pub fn into<DI, NMODE: DisplayModeTrait<DI>>(self) -> NMODE
-    where
-        DI: WriteOnlyDataCommand,
-        MODE: DisplayModeTrait<DI>,
-    {
-        let display = self.display.release();
-        NMODE::new(display)
-    }
-
-    #[cfg(feature = "buffered")]
-    pub fn into<DI, NMODE: DisplayModeTrait<DI>>(self) -> NMODE
+    pub fn into<DI, BUF, NMODE: DisplayModeTrait<DI, BUF>>(self) -> NMODE
     where
         DI: WriteOnlyDataCommand,
-        MODE: DisplayModeTrait<DI>,
+        MODE: DisplayModeTrait<DI, BUF>,
     {
         let (display, buffer) = self.display.release();
         NMODE::new(display, buffer)
@@ -1,46 +1,68 @@
+//! Unbuffered graphics mode
+//!
+//! Every pixel is sent straight to the display, so no RAM is needed for a framebuffer. See
+//! [`BufferedGraphicsMode`](../buffered_graphics/index.html) for the buffered alternative.
+//!
+//! Pixel data is collected in a stack buffer of `CHUNK` bytes (256 by default, a full row) and
+//! sent in as few transfers as possible. A smaller `CHUNK` saves stack at the cost of more bus
+//! transactions, e.g. `DirectGraphicsMode<DI, 64>`.
+
 use crate::display::Display;
+use crate::geometry::{self, Area};
 use display_interface::{DisplayError, WriteOnlyDataCommand};
 use hal::delay::DelayNs;
 use hal::digital::OutputPin;
 
 use crate::mode::displaymode::DisplayModeTrait;
 use crate::properties::DisplayRotation;
+#[cfg(feature = "graphics")]
+use crate::properties::DisplaySize;
 
-/// Graphics Mode for the display
-pub struct GraphicsMode<DI>
+/// Graphics Mode for the display, drawing directly to the display RAM
+pub struct DirectGraphicsMode<DI, const CHUNK: usize = 256>
 where
     DI: WriteOnlyDataCommand,
 {
     display: Display<DI>,
-    #[cfg(feature = "buffered")]
-    pub buffer: &'static mut [u8],
+    pointer: Option<RamPointer>,
 }
 
-impl<DI> DisplayModeTrait<DI> for GraphicsMode<DI>
+/// Direction the display RAM pointer moves in within a draw window
+#[cfg_attr(not(feature = "graphics"), allow(dead_code))]
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum RunDirection {
+    /// Window one row tall, the pointer moves right
+    Row,
+    /// Window one column wide, the pointer moves down
+    Column,
+}
+
+/// Where the display RAM pointer is after the last run written by `draw_iter`
+#[cfg_attr(not(feature = "graphics"), allow(dead_code))]
+#[derive(Clone, Copy)]
+pub(crate) struct RamPointer {
+    position: (u8, u8),
+    direction: RunDirection,
+}
+
+/// Graphics Mode for the display, alias of [DirectGraphicsMode]
+pub type GraphicsMode<DI> = DirectGraphicsMode<DI>;
+
+impl<DI, const CHUNK: usize> DisplayModeTrait<DI> for DirectGraphicsMode<DI, CHUNK>
 where
     DI: WriteOnlyDataCommand,
 {
-    #[cfg(not(feature = "buffered"))]
-    /// Create new GraphicsMode instance
-    fn new(display: Display<DI>) -> Self {
-        GraphicsMode { display }
-    }
-
-    #[cfg(feature = "buffered")]
-    fn new(display: Display<DI>, buffer: &'static mut [u8]) -> Self {
-        GraphicsMode { display, buffer }
-    }
-
-    #[cfg(not(feature = "buffered"))]
-    /// Release all resources used by GraphicsMode
-    fn release(self) -> Display<DI> {
-        self.display
+    /// Create new DirectGraphicsMode instance
+    fn new(display: Display<DI>, _: ()) -> Self {
+        DirectGraphicsMode {
+            display,
+            pointer: None,
+        }
     }
 
-    #[cfg(feature = "buffered")]
-    /// Release all resources used by GraphicsMode
-    fn release(self) -> (Display<DI>, &'static mut [u8]) {
-        (self.display, self.buffer)
+    /// Release all resources used by DirectGraphicsMode
+    fn release(self) -> (Display<DI>, ()) {
+        (self.display, ())
     }
 }
 
@@ -51,27 +73,16 @@ where
 //     }
 // }
 
-impl<DI> GraphicsMode<DI>
+impl<DI, const CHUNK: usize> DirectGraphicsMode<DI, CHUNK>
 where
     DI: WriteOnlyDataCommand,
 {
-    #[cfg(not(feature = "buffered"))]
     /// Clear the display
     pub fn clear(&mut self) {
+        self.pointer = None;
         self.display.clear().unwrap();
     }
 
-    #[cfg(feature = "buffered")]
-    /// Clear the display
-    pub fn clear(&mut self, flush: bool) {
-        for i in 0..self.buffer.len() {
-            self.buffer[i] = 0u8;
-        }
-        if flush {
-            self.flush();
-        }
-    }
-
     /// Reset display
     pub fn reset<RST, DELAY>(&mut self, rst: &mut RST, delay: &mut DELAY) -> Result<(), RST::Error>
     where
@@ -86,63 +97,33 @@ where
         Ok(())
     }
 
-    #[cfg(feature = "buffered")]
-    /// Access the framebuffer
-    pub fn fb(&self) -> &[u8] {
-        self.buffer
-    }
-
-    #[cfg(feature = "buffered")]
-    /// Access the framebuffer
-    pub fn fb_mut(&mut self) -> &mut [u8] {
-        self.buffer
-    }
-
-    #[cfg(not(feature = "buffered"))]
     /// Turn a pixel on or off. A non-zero `value` is treated as on, `0` as off. If the X and Y
     /// coordinates are out of the bounds of the display, this method call is a noop.
     pub fn set_pixel(&mut self, x: u32, y: u32, color: u16) {
-        let (display_width, display_height) = self.display.get_size().dimensions();
-        let rot = self.display.get_rotation();
-        let (nx, ny) = match rot {
-            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (x, y),
-            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => (y, x),
+        self.pointer = None;
+        let size = self.display.get_size();
+        let Ok(point) = geometry::Point::from_logical(x, y, size, self.display.get_rotation())
+        else {
+            return;
         };
-        self.display
-            .set_draw_area((nx as u8, ny as u8), (display_width, display_height))
-            .unwrap();
+        let window = Area::new(point, Area::panel(size).end()).unwrap();
+        self.display.set_area(&window).unwrap();
         self.display
             .draw(&[(color >> 8) as u8, color as u8])
             .unwrap();
     }
 
-    #[cfg(feature = "buffered")]
-    /// Turn a pixel on or off. A non-zero `value` is treated as on, `0` as off. If the X and Y
-    /// coordinates are out of the bounds of the display, this method call is a noop.
-    pub fn set_pixel(&mut self, x: u32, y: u32, color: u16) {
-        // set bytes in buffer
-        self.buffer[(y as usize * 128usize + x as usize) * 2] = (color >> 8) as u8;
-        self.buffer[((y as usize * 128usize + x as usize) * 2) + 1usize] = color as u8;
-    }
-
-    #[cfg(feature = "buffered")]
-    pub fn flush(&mut self) {
-        let (display_width, display_height) = self.display.get_size().dimensions();
-        self.display
-            .set_draw_area((0, 0), (display_width, display_height))
-            .unwrap();
-        self.display.draw(self.buffer).unwrap();
-    }
-
     /// Display is set up in column mode, i.e. a byte walks down a column of 8 pixels from
     /// column 0 on the left, to column _n_ on the right
     pub fn init(&mut self) -> Result<(), DisplayError> {
+        self.pointer = None;
         self.display.init()?;
         Ok(())
     }
 
     /// Set the display rotation
     pub fn set_rotation(&mut self, rot: DisplayRotation) -> Result<(), DisplayError> {
+        self.pointer = None;
         self.display.set_rotation(rot)
     }
 
@@ -150,6 +131,214 @@ where
     pub fn get_dimensions(&self) -> (u8, u8) {
         self.display.get_dimensions()
     }
+
+    /// Write the pixels of a run, only setting a new draw window if the RAM pointer is not at the
+    /// start of the run already
+    #[cfg(feature = "graphics")]
+    fn write_run(&mut self, run: &Run<CHUNK>) -> Result<(), DisplayError> {
+        let (window, pointer) = run.window(self.pointer, self.display.get_size().dimensions());
+
+        self.pointer = None;
+        if let Some((start, end)) = window {
+            self.display.set_draw_area(start, end)?;
+        }
+        self.display.draw(run.bytes())?;
+        self.pointer = pointer;
+
+        Ok(())
+    }
+
+    /// Draw `canvas` with its top left corner at `top_left`, setting the draw window once. Parts
+    /// of the canvas outside the display are skipped.
+    #[cfg(feature = "graphics")]
+    pub fn blit<const W: usize, const H: usize>(
+        &mut self,
+        canvas: &Canvas<W, H>,
+        top_left: EgPoint,
+    ) -> Result<(), DisplayError> {
+        self.draw_raw(&Rectangle::new(top_left, canvas.size()), canvas.as_bytes())
+    }
+
+    /// Draw big endian RGB565 `data`, holding the pixels of `area` row by row, setting the draw
+    /// window once. Parts of the area outside the display are skipped. Data of the wrong length is
+    /// reported as `DisplayError::OutOfBoundsError`.
+    #[cfg(feature = "graphics")]
+    pub fn draw_raw(&mut self, area: &Rectangle, data: &[u8]) -> Result<(), DisplayError> {
+        self.draw_raw_pixels(area, data, None)
+    }
+
+    /// Draw RGB565 `data`, holding the pixels of `area` row by row, as `draw_raw`
+    #[cfg(feature = "graphics")]
+    pub fn draw_raw_u16(&mut self, area: &Rectangle, data: &[u16]) -> Result<(), DisplayError> {
+        self.draw_raw_pixels(area, data, None)
+    }
+
+    /// Draw big endian RGB565 `data` as `draw_raw`, leaving pixels of colour `key` transparent.
+    /// Each run of opaque pixels gets its own draw window.
+    #[cfg(feature = "graphics")]
+    pub fn draw_raw_keyed(
+        &mut self,
+        area: &Rectangle,
+        data: &[u8],
+        key: Rgb565,
+    ) -> Result<(), DisplayError> {
+        self.draw_raw_pixels(area, data, Some(key))
+    }
+
+    /// Draw RGB565 `data` as `draw_raw_u16`, leaving pixels of colour `key` transparent
+    #[cfg(feature = "graphics")]
+    pub fn draw_raw_u16_keyed(
+        &mut self,
+        area: &Rectangle,
+        data: &[u16],
+        key: Rgb565,
+    ) -> Result<(), DisplayError> {
+        self.draw_raw_pixels(area, data, Some(key))
+    }
+
+    #[cfg(feature = "graphics")]
+    fn draw_raw_pixels<T: RawPixel>(
+        &mut self,
+        area: &Rectangle,
+        data: &[T],
+        key: Option<Rgb565>,
+    ) -> Result<(), DisplayError> {
+        const { assert!(CHUNK >= 2, "CHUNK must hold at least one pixel") };
+        raw::check_len(area, data)?;
+        self.pointer = None;
+        if let Some(key) = key {
+            let key = RawU16::from(key).into_inner();
+            let pixels = raw::opaque(area, &self.bounding_box(), data, key);
+            return self.draw_pixels(pixels);
+        }
+
+        let Some((window, source)) = raw::window(
+            area,
+            &self.bounding_box(),
+            self.display.get_size(),
+            self.display.get_rotation(),
+        )?
+        else {
+            return Ok(());
+        };
+        self.display.set_area(&window)?;
+
+        let mut chunk = [0u8; CHUNK];
+        let mut stream = raw::raw_stream(data, area.size.width as usize, &source);
+        while let Some(bytes) = stream.next(&mut chunk) {
+            self.display.draw(bytes)?;
+        }
+
+        Ok(())
+    }
+
+    /// Draw the QOI image `data` with its top left corner at `top_left`, decoding it row by row
+    /// into a window set once. Parts of the image outside the display are skipped, and decoding
+    /// stops after the last visible row. Malformed image data is reported as
+    /// `DisplayError::InvalidFormatError`.
+    #[cfg(feature = "images")]
+    pub fn draw_qoi(&mut self, data: &[u8], top_left: EgPoint) -> Result<(), DisplayError> {
+        const { assert!(CHUNK >= 2, "CHUNK must hold at least one pixel") };
+        let decoder = QoiDecoder::new(data)?;
+        self.pointer = None;
+        let area = Rectangle::new(top_left, Size::new(decoder.width(), decoder.height()));
+        let Some((window, source)) = raw::window(
+            &area,
+            &self.bounding_box(),
+            self.display.get_size(),
+            self.display.get_rotation(),
+        )?
+        else {
+            return Ok(());
+        };
+        self.display.set_area(&window)?;
+
+        let mut chunk = [0u8; CHUNK];
+        let mut stream = raw::QoiStream::new(decoder, &source)?;
+        while let Some(bytes) = stream.next(&mut chunk)? {
+            self.display.draw(bytes)?;
+        }
+
+        Ok(())
+    }
+
+    /// Draw the run-length encoded image `data` with its top left corner at `top_left` into a
+    /// window set once. Literal pixels are sent straight from `data`, runs as chunked fills. Parts
+    /// of the image outside the display are skipped, and decoding stops after the last visible row.
+    /// Malformed image data is reported as `DisplayError::InvalidFormatError`.
+    #[cfg(feature = "images")]
+    pub fn draw_rle(&mut self, data: &[u8], top_left: EgPoint) -> Result<(), DisplayError> {
+        let decoder = RleDecoder::new(data)?;
+        self.pointer = None;
+        let size = Size::new(decoder.width() as u32, decoder.height() as u32);
+        let Some((window, source)) = raw::window(
+            &Rectangle::new(top_left, size),
+            &self.bounding_box(),
+            self.display.get_size(),
+            self.display.get_rotation(),
+        )?
+        else {
+            return Ok(());
+        };
+        self.display.set_area(&window)?;
+
+        let mut chunk = [0u8; CHUNK];
+        let mut stream = raw::RleStream::new(decoder, &source)?;
+        while let Some(span) = stream.next()? {
+            match span {
+                Span::Pixels(bytes) => self.display.draw(bytes)?,
+                Span::Solid { color, len } => self.write_solid(&mut chunk, color, len)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Send `count` pixels of `color` at the RAM pointer, in chunks
+    #[cfg(feature = "graphics")]
+    fn write_solid(
+        &mut self,
+        chunk: &mut [u8; CHUNK],
+        color: u16,
+        count: usize,
+    ) -> Result<(), DisplayError> {
+        const { assert!(CHUNK >= 2, "CHUNK must hold at least one pixel") };
+        for len in raw::solid(chunk, color, count) {
+            self.display.draw(&chunk[..len])?;
+        }
+
+        Ok(())
+    }
+
+    /// Draw pixels at logical positions, coalescing those contiguous in display RAM into runs.
+    /// Pixels outside the display are skipped.
+    #[cfg(feature = "graphics")]
+    fn draw_pixels<I>(&mut self, pixels: I) -> Result<(), DisplayError>
+    where
+        I: IntoIterator<Item = (EgPoint, u16)>,
+    {
+        let size = self.display.get_size();
+        let rotation = self.display.get_rotation();
+        let mut run = Run::<CHUNK>::new();
+
+        for (pos, color) in pixels {
+            let Some(position) = ram_position(size, rotation, pos) else {
+                continue;
+            };
+
+            if !run.push(position, color) {
+                self.write_run(&run)?;
+                run.clear();
+                run.push(position, color);
+            }
+        }
+
+        if !run.is_empty() {
+            self.write_run(&run)?;
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(feature = "graphics")]
@@ -158,13 +347,166 @@ extern crate embedded_graphics_core;
 use self::embedded_graphics_core::pixelcolor::{raw::RawU16, Rgb565};
 #[cfg(feature = "graphics")]
 use self::embedded_graphics_core::prelude::{
-    Dimensions, DrawTarget, OriginDimensions, Pixel, RawData, Size,
+    Dimensions, DrawTarget, OriginDimensions, Pixel, Point as EgPoint, PointsIter, RawData, Size,
 };
-#[cfg(all(feature = "graphics", not(feature = "buffered")))]
-use self::embedded_graphics_core::{prelude::PointsIter, primitives::Rectangle};
+#[cfg(feature = "graphics")]
+use self::embedded_graphics_core::primitives::Rectangle;
+#[cfg(feature = "graphics")]
+use crate::canvas::Canvas;
+#[cfg(feature = "images")]
+use crate::qoi::QoiDecoder;
+#[cfg(feature = "images")]
+use crate::raw::Span;
+#[cfg(feature = "graphics")]
+use crate::raw::{self, RawPixel};
+#[cfg(feature = "images")]
+use crate::rle::RleDecoder;
+
+/// Draw window as start and exclusive end in display RAM coordinates
+#[cfg(feature = "graphics")]
+pub(crate) type Window = ((u8, u8), (u8, u8));
+
+/// Display RAM position of a pixel, `None` if it lies outside the display
+#[cfg(feature = "graphics")]
+pub(crate) fn ram_position(
+    size: DisplaySize,
+    rotation: DisplayRotation,
+    pos: EgPoint,
+) -> Option<(u8, u8)> {
+    let x = u32::try_from(pos.x).ok()?;
+    let y = u32::try_from(pos.y).ok()?;
+    let point = geometry::Point::from_logical(x, y, size, rotation).ok()?;
+    Some((point.x(), point.y()))
+}
+
+/// Draw area covering `area`, `None` if it is empty or doesn't lie within the display
+#[cfg(feature = "graphics")]
+pub(crate) fn ram_area(
+    size: DisplaySize,
+    rotation: DisplayRotation,
+    area: &Rectangle,
+) -> Option<Area> {
+    let x = u32::try_from(area.top_left.x).ok()?;
+    let y = u32::try_from(area.top_left.y).ok()?;
+    Area::from_logical(x, y, area.size.width, area.size.height, size, rotation).ok()
+}
 
+/// Pixels contiguous in display RAM, collected to be sent in one go. Holds up to `N / 2` pixels.
 #[cfg(feature = "graphics")]
-impl<DI: WriteOnlyDataCommand> DrawTarget for GraphicsMode<DI> {
+pub(crate) struct Run<const N: usize> {
+    start: (u8, u8),
+    len: usize,
+    direction: Option<RunDirection>,
+    bytes: [u8; N],
+}
+
+#[cfg(feature = "graphics")]
+impl<const N: usize> Run<N> {
+    pub(crate) fn new() -> Self {
+        const { assert!(N >= 2, "CHUNK must hold at least one pixel") };
+        Run {
+            start: (0, 0),
+            len: 0,
+            direction: None,
+            bytes: [0; N],
+        }
+    }
+
+    /// Append a pixel at display RAM `position`. Returns `false` if it doesn't continue the run or
+    /// the run is full.
+    pub(crate) fn push(&mut self, position: (u8, u8), color: u16) -> bool {
+        if self.len == 0 {
+            self.start = position;
+            self.direction = None;
+        } else {
+            let (x, y) = (self.start.0 as usize, self.start.1 as usize);
+            let (px, py) = (position.0 as usize, position.1 as usize);
+            let len = self.len;
+            let direction = match self.direction {
+                None if (px, py) == (x + 1, y) => RunDirection::Row,
+                None if (px, py) == (x, y + 1) => RunDirection::Column,
+                Some(RunDirection::Row) if (px, py) == (x + len, y) => RunDirection::Row,
+                Some(RunDirection::Column) if (px, py) == (x, y + len) => RunDirection::Column,
+                _ => return false,
+            };
+            if (self.len + 1) * 2 > N {
+                return false;
+            }
+            self.direction = Some(direction);
+        }
+
+        let i = self.len * 2;
+        self.bytes[i..i + 2].copy_from_slice(&color.to_be_bytes());
+        self.len += 1;
+        true
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    pub(crate) fn bytes(&self) -> &[u8] {
+        &self.bytes[..self.len * 2]
+    }
+
+    /// Draw window to set before sending the run, `None` if the RAM `pointer` is at its start
+    /// already, and where the pointer is after sending it. The window is one row tall or one
+    /// column wide, so the pointer moves the same way whichever address increment is in use.
+    pub(crate) fn window(
+        &self,
+        pointer: Option<RamPointer>,
+        ram_size: (u8, u8),
+    ) -> (Option<Window>, Option<RamPointer>) {
+        let (ram_width, ram_height) = ram_size;
+        let (x, y) = self.start;
+
+        let in_position = match pointer {
+            Some(pointer) => {
+                pointer.position == self.start
+                    && (self.len == 1 || self.direction == Some(pointer.direction))
+            }
+            None => false,
+        };
+
+        let direction = match pointer {
+            Some(pointer) if in_position => pointer.direction,
+            _ => self.direction.unwrap_or(RunDirection::Row),
+        };
+
+        let window = if in_position {
+            None
+        } else {
+            match direction {
+                RunDirection::Row => Some((self.start, (ram_width, y + 1))),
+                RunDirection::Column => Some((self.start, (x + 1, ram_height))),
+            }
+        };
+
+        // The pointer wraps back to the start of the window at its end
+        let next = match direction {
+            RunDirection::Row if x as usize + self.len < ram_width as usize => Some(RamPointer {
+                position: (x + self.len as u8, y),
+                direction,
+            }),
+            RunDirection::Column if y as usize + self.len < ram_height as usize => {
+                Some(RamPointer {
+                    position: (x, y + self.len as u8),
+                    direction,
+                })
+            }
+            _ => None,
+        };
+
+        (window, next)
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<DI: WriteOnlyDataCommand, const CHUNK: usize> DrawTarget for DirectGraphicsMode<DI, CHUNK> {
     type Color = Rgb565;
     type Error = ();
 
@@ -172,58 +514,74 @@ impl<DI: WriteOnlyDataCommand> DrawTarget for GraphicsMode<DI> {
     where
         I: IntoIterator<Item = Pixel<Self::Color>>,
     {
-        let bb = self.bounding_box();
-
-        pixels
-            .into_iter()
-            .filter(|Pixel(pos, _)| bb.contains(*pos))
-            .for_each(|Pixel(pos, color)| {
-                self.set_pixel(pos.x as u32, pos.y as u32, RawU16::from(color).into_inner())
-            });
-
-        Ok(())
+        self.draw_pixels(
+            pixels
+                .into_iter()
+                .map(|Pixel(pos, color)| (pos, RawU16::from(color).into_inner())),
+        )
+        .map_err(|_| ())
     }
 
-    #[cfg(not(feature = "buffered"))]
     fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
     where
         I: IntoIterator<Item = Self::Color>,
     {
+        const { assert!(CHUNK >= 2, "CHUNK must hold at least one pixel") };
         let drawable_area = area.intersection(&self.bounding_box());
+        self.pointer = None;
 
-        let rot = self.display.get_rotation();
-        let sx = drawable_area.top_left.x as u8;
-        let sy = drawable_area.top_left.y as u8;
-        let ex = (drawable_area.top_left.x as u32 + drawable_area.size.width) as u8;
-        let ey = (drawable_area.top_left.y as u32 + drawable_area.size.height) as u8;
-
-        // Set the draw area to the size of the rectangle
-        let (area_start, area_end) = match rot {
-            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => ((sx, sy), (ex, ey)),
-            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => ((sy, sx), (ey, ex)),
+        let rotation = self.display.get_rotation();
+        let Some(ram_area) = ram_area(self.display.get_size(), rotation, &drawable_area) else {
+            return Ok(());
         };
+        self.display.set_area(&ram_area).map_err(|_| ())?;
 
-        self.display.set_draw_area(area_start, area_end).unwrap();
-
-        // Get an iterator of colours as u16
-        // Check points for containment
-        area.points()
+        // Stream the colours of the visible points in chunks
+        let mut chunk = [0u8; CHUNK];
+        let mut len = 0;
+        for color in area
+            .points()
             .zip(colors)
             .filter(|(pos, _)| drawable_area.contains(*pos))
             .map(|(_, color)| RawU16::from(color).into_inner())
-            .for_each(|color| {
-                self.display
-                    .draw(&[(color >> 8) as u8, color as u8])
-                    .unwrap()
-            });
+        {
+            chunk[len..len + 2].copy_from_slice(&color.to_be_bytes());
+            len += 2;
+            if len + 2 > CHUNK {
+                self.display.draw(&chunk[..len]).map_err(|_| ())?;
+                len = 0;
+            }
+        }
+        if len > 0 {
+            self.display.draw(&chunk[..len]).map_err(|_| ())?;
+        }
 
         Ok(())
     }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let drawable_area = area.intersection(&self.bounding_box());
+        self.pointer = None;
+
+        let rotation = self.display.get_rotation();
+        let Some(ram_area) = ram_area(self.display.get_size(), rotation, &drawable_area) else {
+            return Ok(());
+        };
+        self.display.set_area(&ram_area).map_err(|_| ())?;
+
+        let count = drawable_area.size.width as usize * drawable_area.size.height as usize;
+        let mut chunk = [0u8; CHUNK];
+        self.write_solid(&mut chunk, RawU16::from(color).into_inner(), count)
+            .map_err(|_| ())
+    }
 }
 
-impl<DI: WriteOnlyDataCommand> OriginDimensions for GraphicsMode<DI> {
+#[cfg(feature = "graphics")]
+impl<DI: WriteOnlyDataCommand, const CHUNK: usize> OriginDimensions
+    for DirectGraphicsMode<DI, CHUNK>
+{
     fn size(&self) -> Size {
-        let dim = self.display.get_size().dimensions();
+        let dim = self.display.get_dimensions();
         Size::from((dim.0 as u32, dim.1 as u32))
     }
 }
@@ -0,0 +1,355 @@
+//! Indexed colour graphics mode
+//!
+//! The framebuffer stores palette indices instead of RGB565 colours, 4 or 8 bits per pixel,
+//! cutting its size to a quarter or half of [`BufferedGraphicsMode`]. Pixels are expanded to
+//! RGB565 through the palette while the framebuffer is streamed to the display, so swapping
+//! palette entries recolours the next flush without redrawing anything.
+//!
+//! ```rust,no_run
+//! # use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+//! # struct Iface;
+//! # impl WriteOnlyDataCommand for Iface {
+//! #     fn send_commands(&mut self, _: DataFormat<'_>) -> Result<(), DisplayError> { Ok(()) }
+//! #     fn send_data(&mut self, _: DataFormat<'_>) -> Result<(), DisplayError> { Ok(()) }
+//! # }
+//! # let interface = Iface;
+//! use embedded_graphics_core::pixelcolor::{Rgb565, RgbColor};
+//! use ssd1351::builder::Builder;
+//! use ssd1351::mode::Indexed4GraphicsMode;
+//! use ssd1351::properties::DisplaySize;
+//!
+//! const FB_SIZE: usize = DisplaySize::Display128x128.indexed_buffer_size(4);
+//!
+//! let mut display: Indexed4GraphicsMode<_, _> = Builder::new()
+//!     .connect_interface_indexed(interface, [0u8; FB_SIZE])
+//!     .unwrap();
+//!
+//! display.set_palette(&[Rgb565::BLACK, Rgb565::RED, Rgb565::BLUE]);
+//! display.set_index(10, 10, 1);
+//! display.flush().unwrap();
+//! ```
+//!
+//! [`BufferedGraphicsMode`]: ../buffered_graphics/struct.BufferedGraphicsMode.html
+
+use core::marker::PhantomData;
+use core::ops::Range;
+
+use crate::display::{rows_area, Display};
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+use hal::delay::DelayNs;
+use hal::digital::OutputPin;
+
+use crate::mode::displaymode::DisplayModeTrait;
+use crate::properties::DisplayRotation;
+
+extern crate embedded_graphics_core;
+use self::embedded_graphics_core::pixelcolor::{raw::RawU16, Rgb565, RgbColor};
+use self::embedded_graphics_core::prelude::{
+    Dimensions, DrawTarget, OriginDimensions, Pixel, RawData, Size,
+};
+use self::embedded_graphics_core::primitives::Rectangle;
+
+/// Bytes of expanded RGB565 data sent to the display per `draw` call while flushing
+const FLUSH_CHUNK: usize = 128;
+
+/// Pixel format of an indexed framebuffer
+pub trait IndexFormat {
+    /// Bits per pixel
+    const BITS: usize;
+
+    /// Palette storage, one entry per possible index
+    type Palette: AsRef<[Rgb565]> + AsMut<[Rgb565]>;
+
+    /// A palette with every entry black
+    fn black_palette() -> Self::Palette;
+}
+
+/// 4 bits per pixel, 16 colour palette
+pub struct Index4;
+
+impl IndexFormat for Index4 {
+    const BITS: usize = 4;
+    type Palette = [Rgb565; 16];
+
+    fn black_palette() -> Self::Palette {
+        [Rgb565::BLACK; 16]
+    }
+}
+
+/// 8 bits per pixel, 256 colour palette
+pub struct Index8;
+
+impl IndexFormat for Index8 {
+    const BITS: usize = 8;
+    type Palette = [Rgb565; 256];
+
+    fn black_palette() -> Self::Palette {
+        [Rgb565::BLACK; 256]
+    }
+}
+
+/// Indexed colour graphics mode for the display
+pub struct IndexedGraphicsMode<DI, BUF, F>
+where
+    DI: WriteOnlyDataCommand,
+    F: IndexFormat,
+{
+    display: Display<DI>,
+    buffer: BUF,
+    palette: F::Palette,
+    _format: PhantomData<F>,
+}
+
+/// Indexed colour graphics mode with 4 bits per pixel
+pub type Indexed4GraphicsMode<DI, BUF> = IndexedGraphicsMode<DI, BUF, Index4>;
+
+/// Indexed colour graphics mode with 8 bits per pixel
+pub type Indexed8GraphicsMode<DI, BUF> = IndexedGraphicsMode<DI, BUF, Index8>;
+
+impl<DI, BUF, F> DisplayModeTrait<DI, BUF> for IndexedGraphicsMode<DI, BUF, F>
+where
+    DI: WriteOnlyDataCommand,
+    F: IndexFormat,
+{
+    /// Create new IndexedGraphicsMode instance with an all black palette
+    fn new(display: Display<DI>, buffer: BUF) -> Self {
+        IndexedGraphicsMode {
+            display,
+            buffer,
+            palette: F::black_palette(),
+            _format: PhantomData,
+        }
+    }
+
+    /// Release all resources used by IndexedGraphicsMode
+    fn release(self) -> (Display<DI>, BUF) {
+        (self.display, self.buffer)
+    }
+}
+
+impl<DI, BUF, F> IndexedGraphicsMode<DI, BUF, F>
+where
+    DI: WriteOnlyDataCommand,
+    BUF: AsRef<[u8]> + AsMut<[u8]>,
+    F: IndexFormat,
+{
+    /// Clear the framebuffer to palette index 0
+    pub fn clear(&mut self, flush: bool) -> Result<(), DisplayError> {
+        self.buffer.as_mut().fill(0);
+        if flush {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Reset display
+    pub fn reset<RST, DELAY>(&mut self, rst: &mut RST, delay: &mut DELAY) -> Result<(), RST::Error>
+    where
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        rst.set_high()?;
+        delay.delay_ms(1);
+        rst.set_low()?;
+        delay.delay_ms(10);
+        rst.set_high()?;
+        Ok(())
+    }
+
+    /// Access the framebuffer
+    pub fn fb(&self) -> &[u8] {
+        self.buffer.as_ref()
+    }
+
+    /// Access the framebuffer
+    pub fn fb_mut(&mut self) -> &mut [u8] {
+        self.buffer.as_mut()
+    }
+
+    /// Get the palette
+    pub fn palette(&self) -> &[Rgb565] {
+        self.palette.as_ref()
+    }
+
+    /// Replace the start of the palette with `colors`. Entries past the palette size are
+    /// ignored.
+    pub fn set_palette(&mut self, colors: &[Rgb565]) {
+        self.palette
+            .as_mut()
+            .iter_mut()
+            .zip(colors)
+            .for_each(|(entry, color)| *entry = *color);
+    }
+
+    /// Set a single palette entry. Out of range indices are ignored.
+    pub fn set_palette_entry(&mut self, index: u8, color: Rgb565) {
+        if let Some(entry) = self.palette.as_mut().get_mut(index as usize) {
+            *entry = color;
+        }
+    }
+
+    /// Rotate the palette entries in `range` by `steps` positions, e.g. to animate colour
+    /// cycling. Takes effect on the next flush.
+    pub fn cycle_palette(&mut self, range: Range<usize>, steps: usize) {
+        if let Some(entries) = self.palette.as_mut().get_mut(range) {
+            if !entries.is_empty() {
+                entries.rotate_right(steps % entries.len());
+            }
+        }
+    }
+
+    /// Set the palette index of a pixel. If the X and Y coordinates are out of the bounds of the
+    /// display, this method call is a noop.
+    pub fn set_index(&mut self, x: u32, y: u32, index: u8) {
+        let (width, height) = self.display.get_dimensions();
+        if x >= width as u32 || y >= height as u32 {
+            return;
+        }
+
+        let pos = y as usize * width as usize + x as usize;
+        let buffer = self.buffer.as_mut();
+        match F::BITS {
+            4 => {
+                if let Some(byte) = buffer.get_mut(pos / 2) {
+                    let shift = 4 - (pos & 1) * 4;
+                    *byte = (*byte & !(0x0F << shift)) | ((index & 0x0F) << shift);
+                }
+            }
+            _ => {
+                if let Some(byte) = buffer.get_mut(pos) {
+                    *byte = index;
+                }
+            }
+        }
+    }
+
+    /// Expand the framebuffer through the palette and send it to the display. Fails with
+    /// `DisplayError::OutOfBoundsError` if the framebuffer is too small for the display.
+    pub fn flush(&mut self) -> Result<(), DisplayError> {
+        let (width, height) = self.display.get_dimensions();
+        let pixels = width as usize * height as usize;
+        let buffer = self.buffer.as_ref();
+        if buffer.len() < pixels * F::BITS / 8 {
+            return Err(DisplayError::OutOfBoundsError);
+        }
+
+        let (start, end) = rows_area(self.display.get_rotation(), width, 0, height);
+        self.display.set_draw_area(start, end)?;
+
+        let palette = self.palette.as_ref();
+        let mut chunk = [0u8; FLUSH_CHUNK];
+        let mut len = 0;
+        for pos in 0..pixels {
+            let index = match F::BITS {
+                4 => (buffer[pos / 2] >> (4 - (pos & 1) * 4)) & 0x0F,
+                _ => buffer[pos],
+            };
+            let color = RawU16::from(palette[index as usize]).into_inner();
+            chunk[len] = (color >> 8) as u8;
+            chunk[len + 1] = color as u8;
+            len += 2;
+
+            if len == FLUSH_CHUNK {
+                self.display.draw(&chunk)?;
+                len = 0;
+            }
+        }
+        if len > 0 {
+            self.display.draw(&chunk[..len])?;
+        }
+
+        Ok(())
+    }
+
+    /// Display is set up in column mode, i.e. a byte walks down a column of 8 pixels from
+    /// column 0 on the left, to column _n_ on the right
+    pub fn init(&mut self) -> Result<(), DisplayError> {
+        self.display.init()?;
+        Ok(())
+    }
+
+    /// Set the display rotation
+    pub fn set_rotation(&mut self, rot: DisplayRotation) -> Result<(), DisplayError> {
+        self.display.set_rotation(rot)
+    }
+
+    /// Get display dimensions, taking into account the current rotation of the display
+    pub fn get_dimensions(&self) -> (u8, u8) {
+        self.display.get_dimensions()
+    }
+
+    /// Palette index of the entry closest to `color`
+    fn lookup(&self, color: Rgb565) -> u8 {
+        let distance = |entry: &Rgb565| {
+            let dr = (entry.r() as i32 - color.r() as i32) * 2;
+            let dg = entry.g() as i32 - color.g() as i32;
+            let db = (entry.b() as i32 - color.b() as i32) * 2;
+            dr * dr + dg * dg + db * db
+        };
+
+        self.palette
+            .as_ref()
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, entry)| distance(entry))
+            .map_or(0, |(index, _)| index as u8)
+    }
+}
+
+/// Drawing with `Rgb565` colours stores the index of the closest palette entry, so colours
+/// should be drawn after the palette is set up.
+impl<DI, BUF, F> DrawTarget for IndexedGraphicsMode<DI, BUF, F>
+where
+    DI: WriteOnlyDataCommand,
+    BUF: AsRef<[u8]> + AsMut<[u8]>,
+    F: IndexFormat,
+{
+    type Color = Rgb565;
+    type Error = ();
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let bb = self.bounding_box();
+        let mut last: Option<(Rgb565, u8)> = None;
+
+        pixels
+            .into_iter()
+            .filter(|Pixel(pos, _)| bb.contains(*pos))
+            .for_each(|Pixel(pos, color)| {
+                let index = match last {
+                    Some((last_color, index)) if last_color == color => index,
+                    _ => self.lookup(color),
+                };
+                last = Some((color, index));
+                self.set_index(pos.x as u32, pos.y as u32, index)
+            });
+
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let area = area.intersection(&self.bounding_box());
+        let index = self.lookup(color);
+
+        for y in area.rows() {
+            for x in area.columns() {
+                self.set_index(x as u32, y as u32, index);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<DI, BUF, F> OriginDimensions for IndexedGraphicsMode<DI, BUF, F>
+where
+    DI: WriteOnlyDataCommand,
+    F: IndexFormat,
+{
+    fn size(&self) -> Size {
+        let dim = self.display.get_dimensions();
+        Size::from((dim.0 as u32, dim.1 as u32))
+    }
+}
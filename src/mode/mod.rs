@@ -4,17 +4,55 @@
 //! methods it exposes. Look at the modes below for more information on what they expose.
 
 // pub mod displaymode;
-pub mod graphics;
-// pub mod terminal;
+pub mod buffered_graphics;
 pub mod displaymode;
+pub mod graphics;
+#[cfg(feature = "graphics")]
+pub mod indexed;
+#[cfg(feature = "graphics")]
+pub mod mono;
 pub mod raw;
+#[cfg(feature = "graphics")]
+pub mod strip;
+#[cfg(feature = "graphics")]
+pub mod terminal;
 
+#[cfg(feature = "async")]
+pub mod async_buffered_graphics;
+#[cfg(feature = "async")]
+pub mod async_displaymode;
+#[cfg(feature = "async")]
+pub mod async_double_buffered;
 #[cfg(feature = "async")]
 pub mod async_graphics;
+#[cfg(all(feature = "async", feature = "graphics"))]
+pub mod async_mono;
+#[cfg(feature = "async")]
+pub mod async_raw;
+#[cfg(all(feature = "async", feature = "graphics"))]
+pub mod async_strip;
 
-pub use self::graphics::GraphicsMode;
-// pub use self::terminal::TerminalMode;
+pub use self::buffered_graphics::BufferedGraphicsMode;
+pub use self::graphics::{DirectGraphicsMode, GraphicsMode};
+#[cfg(feature = "graphics")]
+pub use self::indexed::{Indexed4GraphicsMode, Indexed8GraphicsMode, IndexedGraphicsMode};
+#[cfg(feature = "graphics")]
+pub use self::mono::MonoGraphicsMode;
 pub use self::raw::RawMode;
+#[cfg(feature = "graphics")]
+pub use self::strip::StripMode;
+#[cfg(feature = "graphics")]
+pub use self::terminal::TerminalMode;
 
 #[cfg(feature = "async")]
-pub use self::async_graphics::AsyncGraphicsMode;
+pub use self::async_buffered_graphics::AsyncBufferedGraphicsMode;
+#[cfg(feature = "async")]
+pub use self::async_double_buffered::AsyncDoubleBufferedGraphicsMode;
+#[cfg(feature = "async")]
+pub use self::async_graphics::{AsyncDirectGraphicsMode, AsyncGraphicsMode};
+#[cfg(all(feature = "async", feature = "graphics"))]
+pub use self::async_mono::AsyncMonoGraphicsMode;
+#[cfg(feature = "async")]
+pub use self::async_raw::AsyncRawMode;
+#[cfg(all(feature = "async", feature = "graphics"))]
+pub use self::async_strip::AsyncStripMode;
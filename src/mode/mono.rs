@@ -0,0 +1,285 @@
+//! Monochrome graphics mode
+//!
+//! The framebuffer stores one bit per pixel, 2 KiB for a 128x128 display. Pixels that are
+//! [`BinaryColor::On`] are sent as the foreground colour and [`BinaryColor::Off`] as the
+//! background colour, both of which can be changed at any time. Only rows touched since the
+//! last flush are sent to the display.
+
+use crate::display::{rows_area, Display};
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+use hal::delay::DelayNs;
+use hal::digital::OutputPin;
+
+use crate::mode::displaymode::DisplayModeTrait;
+use crate::mode::strip::MAX_ROW_BYTES;
+use crate::properties::DisplayRotation;
+
+extern crate embedded_graphics_core;
+use self::embedded_graphics_core::pixelcolor::{raw::RawU16, BinaryColor, Rgb565, RgbColor};
+use self::embedded_graphics_core::prelude::{
+    Dimensions, DrawTarget, OriginDimensions, Pixel, RawData, Size,
+};
+use self::embedded_graphics_core::primitives::Rectangle;
+
+/// Dirty mask with every row of the display set
+pub(crate) const ALL_ROWS: u128 = u128::MAX;
+
+/// Set the bit of pixel `(x, y)` in a framebuffer with `width` pixels per row, most significant
+/// bit first. Returns `false` if the buffer is too small.
+pub(crate) fn set_bit(buffer: &mut [u8], width: u8, x: u32, y: u32, on: bool) -> bool {
+    let stride = width as usize / 8;
+    let (byte, mask) = (y as usize * stride + x as usize / 8, 0x80 >> (x % 8));
+    match buffer.get_mut(byte) {
+        Some(byte) if on => *byte |= mask,
+        Some(byte) => *byte &= !mask,
+        None => return false,
+    }
+    true
+}
+
+/// Dirty mask for `rows` rows starting at `top`
+pub(crate) fn row_mask(top: u32, rows: u32) -> u128 {
+    match rows {
+        0 => 0,
+        128.. => ALL_ROWS << top,
+        _ => ((1u128 << rows) - 1) << top,
+    }
+}
+
+/// Next run of dirty rows at or below `top` in `dirty`, as `(top, rows)`
+pub(crate) fn next_run(dirty: u128, top: u8, height: u8) -> Option<(u8, u8)> {
+    if top >= height {
+        return None;
+    }
+    let remaining = dirty >> top;
+    if remaining == 0 {
+        return None;
+    }
+    let start = top as u32 + remaining.trailing_zeros();
+    if start >= height as u32 {
+        return None;
+    }
+    let rows = ((dirty >> start).trailing_ones()).min(height as u32 - start);
+    Some((start as u8, rows as u8))
+}
+
+/// Expand one framebuffer row into big endian RGB565 pixels
+pub(crate) fn expand_row<'a>(
+    bits: &[u8],
+    width: u8,
+    fg: Rgb565,
+    bg: Rgb565,
+    out: &'a mut [u8; MAX_ROW_BYTES],
+) -> &'a [u8] {
+    let (fg, bg) = (
+        RawU16::from(fg).into_inner().to_be_bytes(),
+        RawU16::from(bg).into_inner().to_be_bytes(),
+    );
+    for (x, pixel) in out.chunks_exact_mut(2).take(width as usize).enumerate() {
+        let on = bits[x / 8] & (0x80 >> (x % 8)) != 0;
+        pixel.copy_from_slice(if on { &fg } else { &bg });
+    }
+    &out[..width as usize * 2]
+}
+
+/// Monochrome graphics mode for the display
+pub struct MonoGraphicsMode<DI, BUF>
+where
+    DI: WriteOnlyDataCommand,
+{
+    display: Display<DI>,
+    buffer: BUF,
+    foreground: Rgb565,
+    background: Rgb565,
+    dirty: u128,
+}
+
+impl<DI, BUF> DisplayModeTrait<DI, BUF> for MonoGraphicsMode<DI, BUF>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Create new MonoGraphicsMode instance drawing white on black
+    fn new(display: Display<DI>, buffer: BUF) -> Self {
+        MonoGraphicsMode {
+            display,
+            buffer,
+            foreground: Rgb565::WHITE,
+            background: Rgb565::BLACK,
+            dirty: ALL_ROWS,
+        }
+    }
+
+    /// Release all resources used by MonoGraphicsMode
+    fn release(self) -> (Display<DI>, BUF) {
+        (self.display, self.buffer)
+    }
+}
+
+impl<DI, BUF> MonoGraphicsMode<DI, BUF>
+where
+    DI: WriteOnlyDataCommand,
+    BUF: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Clear the framebuffer to the background colour
+    pub fn clear(&mut self, flush: bool) -> Result<(), DisplayError> {
+        self.buffer.as_mut().fill(0);
+        self.dirty = ALL_ROWS;
+        if flush {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Reset display
+    pub fn reset<RST, DELAY>(&mut self, rst: &mut RST, delay: &mut DELAY) -> Result<(), RST::Error>
+    where
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        rst.set_high()?;
+        delay.delay_ms(1);
+        rst.set_low()?;
+        delay.delay_ms(10);
+        rst.set_high()?;
+        Ok(())
+    }
+
+    /// Access the framebuffer
+    pub fn fb(&self) -> &[u8] {
+        self.buffer.as_ref()
+    }
+
+    /// Access the framebuffer. Every row is sent on the next flush.
+    pub fn fb_mut(&mut self) -> &mut [u8] {
+        self.dirty = ALL_ROWS;
+        self.buffer.as_mut()
+    }
+
+    /// Get the foreground and background colours
+    pub fn colors(&self) -> (Rgb565, Rgb565) {
+        (self.foreground, self.background)
+    }
+
+    /// Set the colours `On` and `Off` pixels are shown in. Every row is sent on the next flush.
+    pub fn set_colors(&mut self, foreground: Rgb565, background: Rgb565) {
+        self.foreground = foreground;
+        self.background = background;
+        self.dirty = ALL_ROWS;
+    }
+
+    /// Turn a pixel on or off. If the X and Y coordinates are out of the bounds of the display,
+    /// this method call is a noop.
+    pub fn set_pixel(&mut self, x: u32, y: u32, on: bool) {
+        let (width, height) = self.display.get_dimensions();
+        if x >= width as u32 || y >= height as u32 {
+            return;
+        }
+        if set_bit(self.buffer.as_mut(), width, x, y, on) {
+            self.dirty |= row_mask(y, 1);
+        }
+    }
+
+    /// Send the rows changed since the last flush to the display. Fails with
+    /// `DisplayError::OutOfBoundsError` if the framebuffer is too small for the display.
+    pub fn flush(&mut self) -> Result<(), DisplayError> {
+        let (width, height) = self.display.get_dimensions();
+        let stride = width as usize / 8;
+        if self.buffer.as_ref().len() < stride * height as usize {
+            return Err(DisplayError::OutOfBoundsError);
+        }
+
+        let rotation = self.display.get_rotation();
+        let mut row = [0u8; MAX_ROW_BYTES];
+        let mut top = 0;
+        while let Some((start, rows)) = next_run(self.dirty, top, height) {
+            let (area_start, area_end) = rows_area(rotation, width, start, rows);
+            self.display.set_draw_area(area_start, area_end)?;
+
+            for y in start as usize..(start + rows) as usize {
+                let bits = &self.buffer.as_ref()[y * stride..(y + 1) * stride];
+                let pixels = expand_row(bits, width, self.foreground, self.background, &mut row);
+                self.display.draw(pixels)?;
+            }
+
+            self.dirty &= !row_mask(start as u32, rows as u32);
+            top = start + rows;
+        }
+
+        self.dirty = 0;
+        Ok(())
+    }
+
+    /// Display is set up in column mode, i.e. a byte walks down a column of 8 pixels from
+    /// column 0 on the left, to column _n_ on the right
+    pub fn init(&mut self) -> Result<(), DisplayError> {
+        self.display.init()?;
+        self.dirty = ALL_ROWS;
+        Ok(())
+    }
+
+    /// Set the display rotation. Every row is sent on the next flush.
+    pub fn set_rotation(&mut self, rot: DisplayRotation) -> Result<(), DisplayError> {
+        self.dirty = ALL_ROWS;
+        self.display.set_rotation(rot)
+    }
+
+    /// Get display dimensions, taking into account the current rotation of the display
+    pub fn get_dimensions(&self) -> (u8, u8) {
+        self.display.get_dimensions()
+    }
+}
+
+impl<DI, BUF> DrawTarget for MonoGraphicsMode<DI, BUF>
+where
+    DI: WriteOnlyDataCommand,
+    BUF: AsRef<[u8]> + AsMut<[u8]>,
+{
+    type Color = BinaryColor;
+    type Error = ();
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let bb = self.bounding_box();
+
+        pixels
+            .into_iter()
+            .filter(|Pixel(pos, _)| bb.contains(*pos))
+            .for_each(|Pixel(pos, color)| {
+                self.set_pixel(pos.x as u32, pos.y as u32, color.is_on())
+            });
+
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let area = area.intersection(&self.bounding_box());
+
+        for y in area.rows() {
+            for x in area.columns() {
+                self.set_pixel(x as u32, y as u32, color.is_on());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.buffer
+            .as_mut()
+            .fill(if color.is_on() { 0xFF } else { 0x00 });
+        self.dirty = ALL_ROWS;
+        Ok(())
+    }
+}
+
+impl<DI, BUF> OriginDimensions for MonoGraphicsMode<DI, BUF>
+where
+    DI: WriteOnlyDataCommand,
+{
+    fn size(&self) -> Size {
+        let dim = self.display.get_dimensions();
+        Size::from((dim.0 as u32, dim.1 as u32))
+    }
+}
@@ -2,7 +2,9 @@
 //!
 //! A display driver instance without high level functionality used as a return type from the
 //! builder. Used as a source to coerce the driver into richer modes like
-//! [`GraphicsMode`](../graphics/index.html) and [`TerminalMode`](../terminal/index.html).
+//! [`GraphicsMode`](../graphics/index.html),
+//! [`BufferedGraphicsMode`](../buffered_graphics/index.html) and
+//! [`TerminalMode`](../terminal/index.html).
 
 use crate::display::Display;
 use display_interface::WriteOnlyDataCommand;
@@ -10,39 +12,26 @@ use display_interface::WriteOnlyDataCommand;
 use crate::mode::displaymode::DisplayModeTrait;
 
 /// Raw display mode
-pub struct RawMode<DI>
+pub struct RawMode<DI, BUF = ()>
 where
     DI: WriteOnlyDataCommand,
 {
     pub display: Display<DI>,
-    #[cfg(feature = "buffered")]
-    pub buffer: &'static mut [u8],
+    /// Framebuffer storage handed on to buffered modes, `()` if there is none
+    pub buffer: BUF,
 }
 
-impl<DI> DisplayModeTrait<DI> for RawMode<DI>
+impl<DI, BUF> DisplayModeTrait<DI, BUF> for RawMode<DI, BUF>
 where
     DI: WriteOnlyDataCommand,
 {
     /// Create new RawMode instance
-    #[cfg(not(feature = "buffered"))]
-    fn new(display: Display<DI>) -> Self {
-        RawMode { display }
-    }
-
-    #[cfg(feature = "buffered")]
-    fn new(display: Display<DI>, buffer: &'static mut [u8]) -> Self {
+    fn new(display: Display<DI>, buffer: BUF) -> Self {
         RawMode { display, buffer }
     }
 
-    #[cfg(not(feature = "buffered"))]
-    /// Release all resources used by RawMode
-    fn release(self) -> Display<DI> {
-        self.display
-    }
-
-    #[cfg(feature = "buffered")]
     /// Release all resources used by RawMode
-    fn release(self) -> (Display<DI>, &'static mut [u8]) {
+    fn release(self) -> (Display<DI>, BUF) {
         (self.display, self.buffer)
     }
 }
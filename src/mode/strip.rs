@@ -0,0 +1,221 @@
+//! Strip rendering mode for displays driven from little RAM
+//!
+//! A full framebuffer needs 32 KiB for a 128 x 128 display. Strip mode instead owns a buffer of
+//! a few rows and renders the frame band by band: for every band the render closure draws the
+//! whole scene into a [`Band`], which only keeps the pixels inside its rows, and the finished
+//! band is streamed to the display before the next one is drawn. A 4 KiB buffer holds 16 rows of
+//! a 128 pixel wide display, so a full frame takes 8 passes over the scene.
+
+use crate::display::{rows_area, Display};
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+use hal::delay::DelayNs;
+use hal::digital::OutputPin;
+
+use crate::mode::displaymode::DisplayModeTrait;
+use crate::properties::DisplayRotation;
+
+extern crate embedded_graphics_core;
+use self::embedded_graphics_core::pixelcolor::{raw::RawU16, Rgb565};
+use self::embedded_graphics_core::prelude::{Dimensions, DrawTarget, Pixel, Point, RawData, Size};
+use self::embedded_graphics_core::primitives::Rectangle;
+
+/// Bytes in one row of the widest supported display
+pub(crate) const MAX_ROW_BYTES: usize = 128 * 2;
+
+/// Strip rendering mode for the display
+pub struct StripMode<DI, BUF>
+where
+    DI: WriteOnlyDataCommand,
+{
+    display: Display<DI>,
+    buffer: BUF,
+}
+
+impl<DI, BUF> DisplayModeTrait<DI, BUF> for StripMode<DI, BUF>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Create new StripMode instance
+    fn new(display: Display<DI>, buffer: BUF) -> Self {
+        StripMode { display, buffer }
+    }
+
+    /// Release all resources used by StripMode
+    fn release(self) -> (Display<DI>, BUF) {
+        (self.display, self.buffer)
+    }
+}
+
+impl<DI, BUF> StripMode<DI, BUF>
+where
+    DI: WriteOnlyDataCommand,
+    BUF: AsMut<[u8]>,
+{
+    /// Render a full frame. `draw` is called once per band with a [`Band`] covering the next
+    /// rows of the display, and should draw the whole scene into it in display coordinates.
+    /// Pixels outside the band are discarded. Fails with `DisplayError::OutOfBoundsError` if the
+    /// buffer cannot hold a single row.
+    pub fn render<F>(&mut self, mut draw: F) -> Result<(), DisplayError>
+    where
+        F: FnMut(&mut Band<'_>),
+    {
+        let (width, height) = self.display.get_dimensions();
+        let rotation = self.display.get_rotation();
+        let buffer = self.buffer.as_mut();
+        let band_rows = band_rows(buffer.len(), width)?;
+
+        let mut top = 0;
+        while top < height {
+            let rows = band_rows.min(height - top);
+            let mut band = Band::new(buffer, width, top, rows);
+            draw(&mut band);
+
+            let (start, end) = rows_area(rotation, width, top, rows);
+            self.display.set_draw_area(start, end)?;
+            self.display.draw(band.into_bytes())?;
+
+            top += rows;
+        }
+
+        Ok(())
+    }
+
+    /// Clear the display
+    pub fn clear(&mut self) -> Result<(), DisplayError> {
+        self.display.clear()
+    }
+
+    /// Reset display
+    pub fn reset<RST, DELAY>(&mut self, rst: &mut RST, delay: &mut DELAY) -> Result<(), RST::Error>
+    where
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        rst.set_high()?;
+        delay.delay_ms(1);
+        rst.set_low()?;
+        delay.delay_ms(10);
+        rst.set_high()?;
+        Ok(())
+    }
+
+    /// Display is set up in column mode, i.e. a byte walks down a column of 8 pixels from
+    /// column 0 on the left, to column _n_ on the right
+    pub fn init(&mut self) -> Result<(), DisplayError> {
+        self.display.init()?;
+        Ok(())
+    }
+
+    /// Set the display rotation
+    pub fn set_rotation(&mut self, rot: DisplayRotation) -> Result<(), DisplayError> {
+        self.display.set_rotation(rot)
+    }
+
+    /// Get display dimensions, taking into account the current rotation of the display
+    pub fn get_dimensions(&self) -> (u8, u8) {
+        self.display.get_dimensions()
+    }
+}
+
+/// Number of rows of the given width that fit into a buffer of `len` bytes
+pub(crate) fn band_rows(len: usize, width: u8) -> Result<u8, DisplayError> {
+    match len / (width as usize * 2) {
+        0 => Err(DisplayError::OutOfBoundsError),
+        rows => Ok(rows.min(u8::MAX as usize) as u8),
+    }
+}
+
+/// A horizontal band of the display, backed by the strip buffer
+///
+/// Drawing uses display coordinates. The bounding box only covers the rows of the band, so
+/// embedded-graphics clips everything else away.
+pub struct Band<'a> {
+    buffer: &'a mut [u8],
+    width: u8,
+    top: u8,
+    rows: u8,
+}
+
+impl<'a> Band<'a> {
+    /// Create a band over the start of `buffer`, cleared to black
+    pub(crate) fn new(buffer: &'a mut [u8], width: u8, top: u8, rows: u8) -> Self {
+        let buffer = &mut buffer[..width as usize * rows as usize * 2];
+        buffer.fill(0);
+        Band {
+            buffer,
+            width,
+            top,
+            rows,
+        }
+    }
+
+    /// The band contents as big endian RGB565, ready for `Display::draw`
+    pub(crate) fn into_bytes(self) -> &'a [u8] {
+        self.buffer
+    }
+
+    /// First display row covered by the band
+    pub fn top(&self) -> u8 {
+        self.top
+    }
+
+    /// Number of display rows covered by the band
+    pub fn rows(&self) -> u8 {
+        self.rows
+    }
+
+    fn set_pixel(&mut self, x: u32, y: u32, color: u16) {
+        let idx = ((y - self.top as u32) as usize * self.width as usize + x as usize) * 2;
+        self.buffer[idx] = (color >> 8) as u8;
+        self.buffer[idx + 1] = color as u8;
+    }
+}
+
+impl Dimensions for Band<'_> {
+    fn bounding_box(&self) -> Rectangle {
+        Rectangle::new(
+            Point::new(0, self.top as i32),
+            Size::new(self.width as u32, self.rows as u32),
+        )
+    }
+}
+
+impl DrawTarget for Band<'_> {
+    type Color = Rgb565;
+    type Error = ();
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let bb = self.bounding_box();
+
+        pixels
+            .into_iter()
+            .filter(|Pixel(pos, _)| bb.contains(*pos))
+            .for_each(|Pixel(pos, color)| {
+                self.set_pixel(pos.x as u32, pos.y as u32, RawU16::from(color).into_inner())
+            });
+
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let area = area.intersection(&self.bounding_box());
+        let color = RawU16::from(color).into_inner();
+        let (hi, lo) = ((color >> 8) as u8, color as u8);
+
+        for y in area.rows() {
+            let start = ((y - self.top as i32) as usize * self.width as usize
+                + area.top_left.x as usize)
+                * 2;
+            let end = start + area.size.width as usize * 2;
+            for pixel in self.buffer[start..end].chunks_exact_mut(2) {
+                pixel[0] = hi;
+                pixel[1] = lo;
+            }
+        }
+
+        Ok(())
+    }
+}
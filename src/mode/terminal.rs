@@ -0,0 +1,408 @@
+//! Terminal mode
+//!
+//! A text console drawing 8x8 pixel characters straight to the display RAM, so no framebuffer is
+//! needed. Text wraps at the right edge of the display when the next character arrives, so a line
+//! filling the whole width followed by a line break doesn't leave a blank line, and once the cursor moves past the last
+//! line the display is scrolled up by one line with the hardware start line, which only costs
+//! clearing the newly exposed line.
+//!
+//! 90º and 270º rotations are not supported, as the start line can only scroll the display RAM
+//! vertically.
+//!
+//! ```rust,no_run
+//! # use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+//! # struct Iface;
+//! # impl WriteOnlyDataCommand for Iface {
+//! #     fn send_commands(&mut self, _: DataFormat<'_>) -> Result<(), DisplayError> { Ok(()) }
+//! #     fn send_data(&mut self, _: DataFormat<'_>) -> Result<(), DisplayError> { Ok(()) }
+//! # }
+//! # let interface = Iface;
+//! use core::fmt::Write;
+//! use ssd1351::builder::Builder;
+//! use ssd1351::mode::TerminalMode;
+//!
+//! let mut display: TerminalMode<_> = Builder::new().connect_interface(interface).into();
+//! display.init().unwrap();
+//!
+//! writeln!(display, "Hello, world!").unwrap();
+//! ```
+
+use core::fmt;
+
+use crate::display::Display;
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+use hal::delay::DelayNs;
+use hal::digital::OutputPin;
+
+use crate::mode::displaymode::DisplayModeTrait;
+use crate::mode::strip::MAX_ROW_BYTES;
+use crate::properties::DisplayRotation;
+
+extern crate embedded_graphics_core;
+use self::embedded_graphics_core::pixelcolor::{raw::RawU16, Rgb565, RgbColor};
+use self::embedded_graphics_core::prelude::RawData;
+
+/// Width and height of a character in pixels
+const CHAR_SIZE: u8 = 8;
+
+/// Number of rows in the display RAM, which the start line wraps around
+const RAM_ROWS: u8 = 128;
+
+/// Columns a tab advances the cursor to a multiple of
+const TAB_WIDTH: u8 = 4;
+
+/// Terminal mode for the display
+pub struct TerminalMode<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    display: Display<DI>,
+    cursor: (u8, u8),
+    /// Whether the last column was printed to, so the next character goes on the next line
+    wrap_pending: bool,
+    start_line: u8,
+    foreground: Rgb565,
+    background: Rgb565,
+}
+
+impl<DI> DisplayModeTrait<DI> for TerminalMode<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Create new TerminalMode instance drawing white on black
+    fn new(display: Display<DI>, _: ()) -> Self {
+        TerminalMode {
+            display,
+            cursor: (0, 0),
+            wrap_pending: false,
+            start_line: 0,
+            foreground: Rgb565::WHITE,
+            background: Rgb565::BLACK,
+        }
+    }
+
+    /// Release all resources used by TerminalMode
+    fn release(self) -> (Display<DI>, ()) {
+        (self.display, ())
+    }
+}
+
+impl<DI> TerminalMode<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Clear the display to the background colour and move the cursor to the top left corner
+    pub fn clear(&mut self) -> Result<(), DisplayError> {
+        self.set_start_line(0)?;
+        self.cursor = (0, 0);
+        self.wrap_pending = false;
+
+        let (width, height) = self.display.get_dimensions();
+        self.fill_rows(0, height, width)
+    }
+
+    /// Reset display
+    pub fn reset<RST, DELAY>(&mut self, rst: &mut RST, delay: &mut DELAY) -> Result<(), RST::Error>
+    where
+        RST: OutputPin,
+        DELAY: DelayNs,
+    {
+        rst.set_high()?;
+        delay.delay_ms(1);
+        rst.set_low()?;
+        delay.delay_ms(10);
+        rst.set_high()?;
+        Ok(())
+    }
+
+    /// Initialise the display and clear it. Fails with `DisplayError::InvalidFormatError` if the
+    /// display is rotated by 90º or 270º.
+    pub fn init(&mut self) -> Result<(), DisplayError> {
+        check_rotation(self.display.get_rotation())?;
+        self.display.init()?;
+        self.start_line = 0;
+        self.clear()
+    }
+
+    /// Set the display rotation and clear the display. Fails with
+    /// `DisplayError::InvalidFormatError` for 90º and 270º rotations.
+    pub fn set_rotation(&mut self, rot: DisplayRotation) -> Result<(), DisplayError> {
+        check_rotation(rot)?;
+        self.display.set_rotation(rot)?;
+        self.clear()
+    }
+
+    /// Get display dimensions, taking into account the current rotation of the display
+    pub fn get_dimensions(&self) -> (u8, u8) {
+        self.display.get_dimensions()
+    }
+
+    /// Get the number of character columns and lines that fit on the display
+    pub fn get_char_dimensions(&self) -> (u8, u8) {
+        let (width, height) = self.display.get_dimensions();
+        (width / CHAR_SIZE, height / CHAR_SIZE)
+    }
+
+    /// Get the foreground and background colours
+    pub fn colors(&self) -> (Rgb565, Rgb565) {
+        (self.foreground, self.background)
+    }
+
+    /// Set the colours characters are drawn in from now on. Text already on the display is not
+    /// redrawn.
+    pub fn set_colors(&mut self, foreground: Rgb565, background: Rgb565) {
+        self.foreground = foreground;
+        self.background = background;
+    }
+
+    /// Get the cursor position as `(column, line)`
+    pub fn get_position(&self) -> (u8, u8) {
+        self.cursor
+    }
+
+    /// Move the cursor to `(column, line)`. Fails with `DisplayError::OutOfBoundsError` if the
+    /// position is outside the display.
+    pub fn set_position(&mut self, column: u8, line: u8) -> Result<(), DisplayError> {
+        let (columns, lines) = self.get_char_dimensions();
+        if column >= columns || line >= lines {
+            return Err(DisplayError::OutOfBoundsError);
+        }
+        self.cursor = (column, line);
+        self.wrap_pending = false;
+        Ok(())
+    }
+
+    /// Print a character at the cursor and advance it, handling `\n`, `\r` and `\t`. Characters
+    /// missing from the built-in ASCII font are shown as `?`. After the last column the cursor
+    /// stays put until the next character, which goes on the next line.
+    pub fn print_char(&mut self, c: char) -> Result<(), DisplayError> {
+        let (columns, _) = self.get_char_dimensions();
+        match c {
+            '\n' => self.new_line(),
+            '\r' => {
+                self.cursor.0 = 0;
+                self.wrap_pending = false;
+                Ok(())
+            }
+            '\t' => {
+                let column = (self.cursor.0 / TAB_WIDTH + 1) * TAB_WIDTH;
+                if column >= columns {
+                    self.new_line()
+                } else {
+                    self.cursor.0 = column;
+                    Ok(())
+                }
+            }
+            _ => {
+                if self.wrap_pending {
+                    self.new_line()?;
+                }
+                self.draw_glyph(glyph(c))?;
+                if self.cursor.0 + 1 < columns {
+                    self.cursor.0 += 1;
+                } else {
+                    self.wrap_pending = true;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Move the cursor to the start of the next line, scrolling the display up by a line if the
+    /// cursor is on the last line
+    fn new_line(&mut self) -> Result<(), DisplayError> {
+        let (width, height) = self.display.get_dimensions();
+        let (_, lines) = self.get_char_dimensions();
+
+        self.cursor.0 = 0;
+        self.wrap_pending = false;
+        if self.cursor.1 + 1 < lines {
+            self.cursor.1 += 1;
+            return Ok(());
+        }
+
+        self.set_start_line((self.start_line + CHAR_SIZE) % RAM_ROWS)?;
+        self.fill_rows(height - CHAR_SIZE, CHAR_SIZE, width)
+    }
+
+    /// Display RAM row showing display row `y`
+    fn ram_row(&self, y: u8) -> u8 {
+        ((self.start_line as u16 + y as u16) % RAM_ROWS as u16) as u8
+    }
+
+    /// Send the start line, scrolling the display RAM up by `line` rows
+    fn set_start_line(&mut self, line: u8) -> Result<(), DisplayError> {
+        self.start_line = line;
+        self.display.set_start_line(line)
+    }
+
+    /// Fill `rows` display rows starting at `top` with the background colour. `top` and `rows`
+    /// are multiples of the character height, so each line maps to contiguous RAM rows.
+    fn fill_rows(&mut self, top: u8, rows: u8, width: u8) -> Result<(), DisplayError> {
+        let color = RawU16::from(self.background).into_inner().to_be_bytes();
+        let mut row = [0u8; MAX_ROW_BYTES];
+        row.chunks_exact_mut(2)
+            .for_each(|pixel| pixel.copy_from_slice(&color));
+
+        for line in (top..top + rows).step_by(CHAR_SIZE as usize) {
+            let ram_row = self.ram_row(line);
+            self.display
+                .set_draw_area((0, ram_row), (width, ram_row + CHAR_SIZE))?;
+            for _ in 0..CHAR_SIZE {
+                self.display.draw(&row[..width as usize * 2])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Draw a glyph at the cursor
+    fn draw_glyph(&mut self, glyph: &[u8; 8]) -> Result<(), DisplayError> {
+        let x = self.cursor.0 * CHAR_SIZE;
+        let ram_row = self.ram_row(self.cursor.1 * CHAR_SIZE);
+        let (fg, bg) = (
+            RawU16::from(self.foreground).into_inner().to_be_bytes(),
+            RawU16::from(self.background).into_inner().to_be_bytes(),
+        );
+
+        let mut pixels = [0u8; CHAR_SIZE as usize * CHAR_SIZE as usize * 2];
+        for (i, pixel) in pixels.chunks_exact_mut(2).enumerate() {
+            let on = glyph[i / 8] & (1 << (i % 8)) != 0;
+            pixel.copy_from_slice(if on { &fg } else { &bg });
+        }
+
+        self.display
+            .set_draw_area((x, ram_row), (x + CHAR_SIZE, ram_row + CHAR_SIZE))?;
+        self.display.draw(&pixels)
+    }
+}
+
+impl<DI> fmt::Write for TerminalMode<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        s.chars()
+            .try_for_each(|c| self.print_char(c))
+            .map_err(|_| fmt::Error)
+    }
+}
+
+/// Hardware scrolling only works along the display RAM rows
+fn check_rotation(rot: DisplayRotation) -> Result<(), DisplayError> {
+    match rot {
+        DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => Ok(()),
+        DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => {
+            Err(DisplayError::InvalidFormatError)
+        }
+    }
+}
+
+/// Glyph of a character in the built-in font
+fn glyph(c: char) -> &'static [u8; 8] {
+    let index = match c {
+        ' '..='~' => c as usize - ' ' as usize,
+        _ => '?' as usize - ' ' as usize,
+    };
+    &FONT[index]
+}
+
+/// 8x8 font covering printable ASCII, one byte per row with the least significant bit on the
+/// left. Based on the public domain font8x8 by Daniel Hepper.
+#[rustfmt::skip]
+const FONT: [[u8; 8]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x18, 0x3C, 0x3C, 0x18, 0x18, 0x00, 0x18, 0x00], // '!'
+    [0x36, 0x36, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '"'
+    [0x36, 0x36, 0x7F, 0x36, 0x7F, 0x36, 0x36, 0x00], // '#'
+    [0x0C, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x0C, 0x00], // '$'
+    [0x00, 0x63, 0x33, 0x18, 0x0C, 0x66, 0x63, 0x00], // '%'
+    [0x1C, 0x36, 0x1C, 0x6E, 0x3B, 0x33, 0x6E, 0x00], // '&'
+    [0x06, 0x06, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00], // '''
+    [0x18, 0x0C, 0x06, 0x06, 0x06, 0x0C, 0x18, 0x00], // '('
+    [0x06, 0x0C, 0x18, 0x18, 0x18, 0x0C, 0x06, 0x00], // ')'
+    [0x00, 0x66, 0x3C, 0xFF, 0x3C, 0x66, 0x00, 0x00], // '*'
+    [0x00, 0x0C, 0x0C, 0x3F, 0x0C, 0x0C, 0x00, 0x00], // '+'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x06], // ','
+    [0x00, 0x00, 0x00, 0x3F, 0x00, 0x00, 0x00, 0x00], // '-'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x00], // '.'
+    [0x60, 0x30, 0x18, 0x0C, 0x06, 0x03, 0x01, 0x00], // '/'
+    [0x3E, 0x63, 0x73, 0x7B, 0x6F, 0x67, 0x3E, 0x00], // '0'
+    [0x0C, 0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x3F, 0x00], // '1'
+    [0x1E, 0x33, 0x30, 0x1C, 0x06, 0x33, 0x3F, 0x00], // '2'
+    [0x1E, 0x33, 0x30, 0x1C, 0x30, 0x33, 0x1E, 0x00], // '3'
+    [0x38, 0x3C, 0x36, 0x33, 0x7F, 0x30, 0x78, 0x00], // '4'
+    [0x3F, 0x03, 0x1F, 0x30, 0x30, 0x33, 0x1E, 0x00], // '5'
+    [0x1C, 0x06, 0x03, 0x1F, 0x33, 0x33, 0x1E, 0x00], // '6'
+    [0x3F, 0x33, 0x30, 0x18, 0x0C, 0x0C, 0x0C, 0x00], // '7'
+    [0x1E, 0x33, 0x33, 0x1E, 0x33, 0x33, 0x1E, 0x00], // '8'
+    [0x1E, 0x33, 0x33, 0x3E, 0x30, 0x18, 0x0E, 0x00], // '9'
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x00], // ':'
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x06], // ';'
+    [0x18, 0x0C, 0x06, 0x03, 0x06, 0x0C, 0x18, 0x00], // '<'
+    [0x00, 0x00, 0x3F, 0x00, 0x00, 0x3F, 0x00, 0x00], // '='
+    [0x06, 0x0C, 0x18, 0x30, 0x18, 0x0C, 0x06, 0x00], // '>'
+    [0x1E, 0x33, 0x30, 0x18, 0x0C, 0x00, 0x0C, 0x00], // '?'
+    [0x3E, 0x63, 0x7B, 0x7B, 0x7B, 0x03, 0x1E, 0x00], // '@'
+    [0x0C, 0x1E, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x00], // 'A'
+    [0x3F, 0x66, 0x66, 0x3E, 0x66, 0x66, 0x3F, 0x00], // 'B'
+    [0x3C, 0x66, 0x03, 0x03, 0x03, 0x66, 0x3C, 0x00], // 'C'
+    [0x1F, 0x36, 0x66, 0x66, 0x66, 0x36, 0x1F, 0x00], // 'D'
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x46, 0x7F, 0x00], // 'E'
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x06, 0x0F, 0x00], // 'F'
+    [0x3C, 0x66, 0x03, 0x03, 0x73, 0x66, 0x7C, 0x00], // 'G'
+    [0x33, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x33, 0x00], // 'H'
+    [0x1E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // 'I'
+    [0x78, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E, 0x00], // 'J'
+    [0x67, 0x66, 0x36, 0x1E, 0x36, 0x66, 0x67, 0x00], // 'K'
+    [0x0F, 0x06, 0x06, 0x06, 0x46, 0x66, 0x7F, 0x00], // 'L'
+    [0x63, 0x77, 0x7F, 0x7F, 0x6B, 0x63, 0x63, 0x00], // 'M'
+    [0x63, 0x67, 0x6F, 0x7B, 0x73, 0x63, 0x63, 0x00], // 'N'
+    [0x1C, 0x36, 0x63, 0x63, 0x63, 0x36, 0x1C, 0x00], // 'O'
+    [0x3F, 0x66, 0x66, 0x3E, 0x06, 0x06, 0x0F, 0x00], // 'P'
+    [0x1E, 0x33, 0x33, 0x33, 0x3B, 0x1E, 0x38, 0x00], // 'Q'
+    [0x3F, 0x66, 0x66, 0x3E, 0x36, 0x66, 0x67, 0x00], // 'R'
+    [0x1E, 0x33, 0x07, 0x0E, 0x38, 0x33, 0x1E, 0x00], // 'S'
+    [0x3F, 0x2D, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // 'T'
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x3F, 0x00], // 'U'
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], // 'V'
+    [0x63, 0x63, 0x63, 0x6B, 0x7F, 0x77, 0x63, 0x00], // 'W'
+    [0x63, 0x63, 0x36, 0x1C, 0x1C, 0x36, 0x63, 0x00], // 'X'
+    [0x33, 0x33, 0x33, 0x1E, 0x0C, 0x0C, 0x1E, 0x00], // 'Y'
+    [0x7F, 0x63, 0x31, 0x18, 0x4C, 0x66, 0x7F, 0x00], // 'Z'
+    [0x1E, 0x06, 0x06, 0x06, 0x06, 0x06, 0x1E, 0x00], // '['
+    [0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x40, 0x00], // '\'
+    [0x1E, 0x18, 0x18, 0x18, 0x18, 0x18, 0x1E, 0x00], // ']'
+    [0x08, 0x1C, 0x36, 0x63, 0x00, 0x00, 0x00, 0x00], // '^'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF], // '_'
+    [0x0C, 0x0C, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00], // '`'
+    [0x00, 0x00, 0x1E, 0x30, 0x3E, 0x33, 0x6E, 0x00], // 'a'
+    [0x07, 0x06, 0x06, 0x3E, 0x66, 0x66, 0x3B, 0x00], // 'b'
+    [0x00, 0x00, 0x1E, 0x33, 0x03, 0x33, 0x1E, 0x00], // 'c'
+    [0x38, 0x30, 0x30, 0x3E, 0x33, 0x33, 0x6E, 0x00], // 'd'
+    [0x00, 0x00, 0x1E, 0x33, 0x3F, 0x03, 0x1E, 0x00], // 'e'
+    [0x1C, 0x36, 0x06, 0x0F, 0x06, 0x06, 0x0F, 0x00], // 'f'
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x1F], // 'g'
+    [0x07, 0x06, 0x36, 0x6E, 0x66, 0x66, 0x67, 0x00], // 'h'
+    [0x0C, 0x00, 0x0E, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // 'i'
+    [0x30, 0x00, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E], // 'j'
+    [0x07, 0x06, 0x66, 0x36, 0x1E, 0x36, 0x67, 0x00], // 'k'
+    [0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // 'l'
+    [0x00, 0x00, 0x33, 0x7F, 0x7F, 0x6B, 0x63, 0x00], // 'm'
+    [0x00, 0x00, 0x1F, 0x33, 0x33, 0x33, 0x33, 0x00], // 'n'
+    [0x00, 0x00, 0x1E, 0x33, 0x33, 0x33, 0x1E, 0x00], // 'o'
+    [0x00, 0x00, 0x3B, 0x66, 0x66, 0x3E, 0x06, 0x0F], // 'p'
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x78], // 'q'
+    [0x00, 0x00, 0x3B, 0x6E, 0x66, 0x06, 0x0F, 0x00], // 'r'
+    [0x00, 0x00, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x00], // 's'
+    [0x08, 0x0C, 0x3E, 0x0C, 0x0C, 0x2C, 0x18, 0x00], // 't'
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x33, 0x6E, 0x00], // 'u'
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], // 'v'
+    [0x00, 0x00, 0x63, 0x6B, 0x7F, 0x7F, 0x36, 0x00], // 'w'
+    [0x00, 0x00, 0x63, 0x36, 0x1C, 0x36, 0x63, 0x00], // 'x'
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x3E, 0x30, 0x1F], // 'y'
+    [0x00, 0x00, 0x3F, 0x19, 0x0C, 0x26, 0x3F, 0x00], // 'z'
+    [0x38, 0x0C, 0x0C, 0x07, 0x0C, 0x0C, 0x38, 0x00], // '{'
+    [0x18, 0x18, 0x18, 0x00, 0x18, 0x18, 0x18, 0x00], // '|'
+    [0x07, 0x0C, 0x0C, 0x38, 0x0C, 0x0C, 0x07, 0x00], // '}'
+    [0x6E, 0x3B, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '~'
+];
@@ -11,8 +11,8 @@ pub use display_interface_spi::SPIInterface;
 #[cfg(feature = "async")]
 pub use crate::async_builder::AsyncBuilder;
 #[cfg(feature = "async")]
-pub use crate::async_display::AsyncDisplay;
+pub use crate::async_display::{AsyncDisplay, RecoveryPolicy, TimeoutError};
 #[cfg(feature = "async")]
-pub use crate::async_interface::AsyncWriteOnlyDataCommand;
+pub use crate::async_interface::{AsyncWriteOnlyDataCommand, SendAsyncWriteOnlyDataCommand};
 #[cfg(feature = "async")]
-pub use crate::mode::AsyncGraphicsMode;
+pub use crate::mode::{AsyncBufferedGraphicsMode, AsyncDirectGraphicsMode, AsyncGraphicsMode};
@@ -1,9 +1,11 @@
 //! Display attributes
 
+use crate::command::Command;
+
 /// Display rotation.
 ///
 /// Note that 90º and 270º rotations are not supported by
-// [`TerminalMode`](../mode/terminal/struct.TerminalMode.html).
+/// [`TerminalMode`](../mode/terminal/struct.TerminalMode.html).
 #[derive(Clone, Copy)]
 pub enum DisplayRotation {
     /// No rotation, normal display
@@ -16,6 +18,18 @@ pub enum DisplayRotation {
     Rotate270,
 }
 
+impl DisplayRotation {
+    /// Remap command that puts the display into this rotation
+    pub(crate) fn remap(&self) -> Command<'static> {
+        match *self {
+            DisplayRotation::Rotate0 => Command::SetRemap(false, false, true),
+            DisplayRotation::Rotate90 => Command::SetRemap(true, true, true),
+            DisplayRotation::Rotate180 => Command::SetRemap(false, true, false),
+            DisplayRotation::Rotate270 => Command::SetRemap(true, false, false),
+        }
+    }
+}
+
 /// Display size enumeration
 #[derive(Clone, Copy)]
 pub enum DisplaySize {
@@ -35,9 +49,23 @@ impl DisplaySize {
         }
     }
 
-    /// Get total number of pixels
-    pub(crate) fn num_pixels(&self) -> usize {
-        let (w, h) = self.dimensions();
-        w as usize * h as usize
+    /// Number of bytes a framebuffer for this size needs, two per pixel. Usable in const
+    /// contexts, e.g. `[u8; DisplaySize::Display128x128.buffer_size()]`.
+    pub const fn buffer_size(&self) -> usize {
+        match *self {
+            DisplaySize::Display128x128 => 128 * 128 * 2,
+            DisplaySize::Display128x96 => 128 * 96 * 2,
+        }
+    }
+
+    /// Number of bytes an indexed framebuffer with `bits` bits per pixel needs for this size,
+    /// e.g. `[u8; DisplaySize::Display128x128.indexed_buffer_size(4)]`.
+    pub const fn indexed_buffer_size(&self, bits: usize) -> usize {
+        self.buffer_size() / 2 * bits / 8
+    }
+
+    /// Number of bytes a monochrome framebuffer for this size needs, one bit per pixel
+    pub const fn mono_buffer_size(&self) -> usize {
+        self.indexed_buffer_size(1)
     }
 }
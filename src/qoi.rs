@@ -0,0 +1,167 @@
+//! Streaming [QOI](https://qoiformat.org) image decoder
+//!
+//! Images are decoded pixel by pixel straight from their encoded data, e.g. in flash, so only the
+//! decoder state of a few hundred bytes is kept in RAM. Pixels come out as RGB565, the alpha
+//! channel is ignored. The graphics modes draw whole images with
+//! [`DirectGraphicsMode::draw_qoi`](../mode/graphics/struct.DirectGraphicsMode.html#method.draw_qoi).
+//!
+//! ```rust
+//! use ssd1351::qoi::QoiDecoder;
+//!
+//! // 2 by 1 pixels, both red
+//! let image = [
+//!     b'q', b'o', b'i', b'f', 0, 0, 0, 2, 0, 0, 0, 1, 3, 0,
+//!     0xFE, 0xFF, 0x00, 0x00, // RGB
+//!     0xC0, // run of 1
+//!     0, 0, 0, 0, 0, 0, 0, 1,
+//! ];
+//!
+//! let mut decoder = QoiDecoder::new(&image).unwrap();
+//! assert_eq!((decoder.width(), decoder.height()), (2, 1));
+//!
+//! let mut row = [0u8; 4];
+//! decoder.read(&mut row).unwrap();
+//! assert_eq!(row, [0xF8, 0x00, 0xF8, 0x00]);
+//! ```
+
+use display_interface::DisplayError;
+
+const MAGIC: &[u8; 4] = b"qoif";
+const HEADER_SIZE: usize = 14;
+
+const OP_RGB: u8 = 0xFE;
+const OP_RGBA: u8 = 0xFF;
+const OP_INDEX: u8 = 0x00;
+const OP_DIFF: u8 = 0x40;
+const OP_LUMA: u8 = 0x80;
+const TAG_MASK: u8 = 0xC0;
+
+/// Decoder reading the pixels of a QOI image in row-major order. Malformed or truncated data is
+/// reported as `DisplayError::InvalidFormatError`.
+pub struct QoiDecoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+    width: u32,
+    height: u32,
+    remaining: u64,
+    pixel: [u8; 4],
+    run: u8,
+    index: [[u8; 4]; 64],
+}
+
+impl<'a> QoiDecoder<'a> {
+    /// Create a decoder for the QOI image in `data`, checking its header
+    pub fn new(data: &'a [u8]) -> Result<Self, DisplayError> {
+        let header = data
+            .get(..HEADER_SIZE)
+            .ok_or(DisplayError::InvalidFormatError)?;
+        let channels = header[12];
+        if &header[..4] != MAGIC || !(3..=4).contains(&channels) {
+            return Err(DisplayError::InvalidFormatError);
+        }
+        let width = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+        let height = u32::from_be_bytes([header[8], header[9], header[10], header[11]]);
+
+        Ok(QoiDecoder {
+            data,
+            pos: HEADER_SIZE,
+            width,
+            height,
+            remaining: width as u64 * height as u64,
+            pixel: [0, 0, 0, 255],
+            run: 0,
+            index: [[0; 4]; 64],
+        })
+    }
+
+    /// Image width in pixels
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Image height in pixels
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Decode the next pixel as RGB565
+    pub fn next_pixel(&mut self) -> Result<u16, DisplayError> {
+        let [r, g, b, _] = self.decode()?;
+        Ok((r as u16 & 0xF8) << 8 | (g as u16 & 0xFC) << 3 | b as u16 >> 3)
+    }
+
+    /// Decode the next `buf.len() / 2` pixels into `buf` as big endian RGB565, the layout
+    /// `Display::draw` expects
+    pub fn read(&mut self, buf: &mut [u8]) -> Result<(), DisplayError> {
+        for bytes in buf.chunks_exact_mut(2) {
+            bytes.copy_from_slice(&self.next_pixel()?.to_be_bytes());
+        }
+        Ok(())
+    }
+
+    /// Skip the next `pixels` pixels
+    pub fn skip(&mut self, pixels: u32) -> Result<(), DisplayError> {
+        for _ in 0..pixels {
+            self.decode()?;
+        }
+        Ok(())
+    }
+
+    fn decode(&mut self) -> Result<[u8; 4], DisplayError> {
+        if self.remaining == 0 {
+            return Err(DisplayError::InvalidFormatError);
+        }
+        self.remaining -= 1;
+
+        if self.run > 0 {
+            self.run -= 1;
+            return Ok(self.pixel);
+        }
+
+        let op = self.byte()?;
+        let [r, g, b, a] = self.pixel;
+        self.pixel = match op {
+            OP_RGB => [self.byte()?, self.byte()?, self.byte()?, a],
+            OP_RGBA => [self.byte()?, self.byte()?, self.byte()?, self.byte()?],
+            _ => match op & TAG_MASK {
+                OP_INDEX => self.index[op as usize],
+                OP_DIFF => [
+                    r.wrapping_add((op >> 4) & 0x03).wrapping_sub(2),
+                    g.wrapping_add((op >> 2) & 0x03).wrapping_sub(2),
+                    b.wrapping_add(op & 0x03).wrapping_sub(2),
+                    a,
+                ],
+                OP_LUMA => {
+                    let dg = (op & 0x3F).wrapping_sub(32);
+                    let next = self.byte()?;
+                    [
+                        r.wrapping_add(dg).wrapping_add(next >> 4).wrapping_sub(8),
+                        g.wrapping_add(dg),
+                        b.wrapping_add(dg).wrapping_add(next & 0x0F).wrapping_sub(8),
+                        a,
+                    ]
+                }
+                // OP_RUN, the run lengths 63 and 64 are taken by OP_RGB and OP_RGBA
+                _ => {
+                    self.run = op & 0x3F;
+                    self.pixel
+                }
+            },
+        };
+
+        let [r, g, b, a] = self.pixel;
+        let hash = (r as usize * 3 + g as usize * 5 + b as usize * 7 + a as usize * 11) % 64;
+        self.index[hash] = self.pixel;
+
+        Ok(self.pixel)
+    }
+
+    fn byte(&mut self) -> Result<u8, DisplayError> {
+        let byte = *self
+            .data
+            .get(self.pos)
+            .ok_or(DisplayError::InvalidFormatError)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+}
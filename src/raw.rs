@@ -0,0 +1,380 @@
+//! Helpers for drawing raw RGB565 pixel data
+//!
+//! Planning what part of an image is visible, where it goes and which bytes to send is shared by
+//! the blocking and async modes, which are left with the transfers themselves. Streams hand out
+//! the bytes one transfer at a time, converting them into a caller provided chunk if needed.
+
+extern crate embedded_graphics_core;
+use self::embedded_graphics_core::geometry::{Point, Size};
+use self::embedded_graphics_core::primitives::{PointsIter, Rectangle};
+use crate::geometry::Area;
+use crate::mode::graphics::ram_area;
+use crate::properties::{DisplayRotation, DisplaySize};
+#[cfg(feature = "images")]
+use crate::qoi::QoiDecoder;
+#[cfg(feature = "images")]
+use crate::rle::{Packet, RleDecoder};
+use display_interface::DisplayError;
+
+/// Element of raw RGB565 pixel data
+pub(crate) trait RawPixel: Copy {
+    /// Number of elements holding a pixel
+    const PER_PIXEL: usize;
+
+    /// Colour of the pixel held by `pixel`
+    fn color(pixel: &[Self]) -> u16;
+
+    /// `pixels` as big endian bytes, `None` if they need converting
+    fn as_bytes(pixels: &[Self]) -> Option<&[u8]>;
+
+    /// Write `pixels` to `out` as big endian bytes
+    fn copy_be(pixels: &[Self], out: &mut [u8]);
+}
+
+impl RawPixel for u8 {
+    const PER_PIXEL: usize = 2;
+
+    fn color(pixel: &[Self]) -> u16 {
+        u16::from_be_bytes([pixel[0], pixel[1]])
+    }
+
+    fn as_bytes(pixels: &[Self]) -> Option<&[u8]> {
+        Some(pixels)
+    }
+
+    fn copy_be(pixels: &[Self], out: &mut [u8]) {
+        out.copy_from_slice(pixels);
+    }
+}
+
+impl RawPixel for u16 {
+    const PER_PIXEL: usize = 1;
+
+    fn color(pixel: &[Self]) -> u16 {
+        pixel[0]
+    }
+
+    fn as_bytes(_: &[Self]) -> Option<&[u8]> {
+        None
+    }
+
+    fn copy_be(pixels: &[Self], out: &mut [u8]) {
+        for (bytes, pixel) in out.chunks_exact_mut(2).zip(pixels) {
+            bytes.copy_from_slice(&pixel.to_be_bytes());
+        }
+    }
+}
+
+/// Check `data` holds exactly the pixels of `area`
+pub(crate) fn check_len<T: RawPixel>(area: &Rectangle, data: &[T]) -> Result<(), DisplayError> {
+    let pixels = area.size.width as usize * area.size.height as usize;
+    if data.len() != pixels * T::PER_PIXEL {
+        return Err(DisplayError::OutOfBoundsError);
+    }
+    Ok(())
+}
+
+/// Part of `area` visible within `bounds`, as the area on the display and the matching area
+/// relative to the top left corner of `area`. `None` if nothing is visible, or the visible part
+/// starts too far into `area` to be addressed.
+pub(crate) fn clip(area: &Rectangle, bounds: &Rectangle) -> Option<(Rectangle, Rectangle)> {
+    // Nothing past the bounds is visible, so huge areas are cut short before their corners overflow
+    let end = bounds.top_left + bounds.size;
+    let shorten = |len: u32, start: i32, end: i32| len.min(end.saturating_sub(start).max(0) as u32);
+    let size = Size::new(
+        shorten(area.size.width, area.top_left.x, end.x),
+        shorten(area.size.height, area.top_left.y, end.y),
+    );
+    let visible = Rectangle::new(area.top_left, size).intersection(bounds);
+    if visible.is_zero_sized() {
+        return None;
+    }
+    let offset = Point::new(
+        visible.top_left.x.checked_sub(area.top_left.x)?,
+        visible.top_left.y.checked_sub(area.top_left.y)?,
+    );
+    let source = Rectangle::new(offset, visible.size);
+    Some((visible, source))
+}
+
+/// Draw window covering the part of `area` visible within `bounds` on a display of `size` with
+/// `rotation`, along with that part relative to the top left corner of `area`. `None` if nothing
+/// is visible.
+pub(crate) fn window(
+    area: &Rectangle,
+    bounds: &Rectangle,
+    size: DisplaySize,
+    rotation: DisplayRotation,
+) -> Result<Option<(Area, Rectangle)>, DisplayError> {
+    let Some((visible, source)) = clip(area, bounds) else {
+        return Ok(None);
+    };
+    let window = ram_area(size, rotation, &visible).ok_or(DisplayError::OutOfBoundsError)?;
+    Ok(Some((window, source)))
+}
+
+/// Byte lengths of the transfers sending `count` pixels of `color` from `chunk`, which is filled
+/// with as many of them as fit
+pub(crate) fn solid(chunk: &mut [u8], color: u16, count: usize) -> impl Iterator<Item = usize> {
+    let pixels = count.min(chunk.len() / 2);
+    for bytes in chunk[..pixels * 2].chunks_exact_mut(2) {
+        bytes.copy_from_slice(&color.to_be_bytes());
+    }
+    (0..count)
+        .step_by(pixels.max(1))
+        .map(move |sent| (count - sent).min(pixels) * 2)
+}
+
+/// Big endian bytes of the pixels of raw data covering a source area, row by row. Byte data
+/// spanning full rows goes out in one transfer.
+pub(crate) struct RawStream<'a, T, R> {
+    rows: R,
+    row: &'a [T],
+}
+
+/// Stream the pixels of `data`, holding rows `width` pixels wide, covering `source`
+pub(crate) fn raw_stream<'a, T: RawPixel>(
+    data: &'a [T],
+    width: usize,
+    source: &Rectangle,
+) -> RawStream<'a, T, impl Iterator<Item = &'a [T]>> {
+    RawStream {
+        rows: rows(data, width, source, true),
+        row: &[],
+    }
+}
+
+impl<'a, T: RawPixel, R: Iterator<Item = &'a [T]>> RawStream<'a, T, R> {
+    /// Bytes of the next transfer, straight from the data or converted into `chunk`
+    pub(crate) fn next<'c>(&mut self, chunk: &'c mut [u8]) -> Option<&'c [u8]>
+    where
+        'a: 'c,
+    {
+        if self.row.is_empty() {
+            let row = self.rows.next()?;
+            if let Some(bytes) = T::as_bytes(row) {
+                return Some(bytes);
+            }
+            self.row = row;
+        }
+        let len = self.row.len().min(chunk.len() / 2 * T::PER_PIXEL);
+        let (pixels, rest) = self.row.split_at(len);
+        self.row = rest;
+        let chunk = &mut chunk[..len / T::PER_PIXEL * 2];
+        T::copy_be(pixels, chunk);
+        Some(chunk)
+    }
+}
+
+/// Pixels of a QOI image covering a source area, decoded row by row
+#[cfg(feature = "images")]
+pub(crate) struct QoiStream<'a> {
+    decoder: QoiDecoder<'a>,
+    left: u32,
+    width: u32,
+    right: u32,
+    rows: u32,
+    /// Pixels of the current row still to read
+    remaining: usize,
+}
+
+#[cfg(feature = "images")]
+impl<'a> QoiStream<'a> {
+    /// Stream the pixels of `decoder` covering `source`, skipping the rows above it
+    pub(crate) fn new(
+        mut decoder: QoiDecoder<'a>,
+        source: &Rectangle,
+    ) -> Result<Self, DisplayError> {
+        let (x, y) = (source.top_left.x as u32, source.top_left.y as u32);
+        for _ in 0..y {
+            decoder.skip(decoder.width())?;
+        }
+        Ok(QoiStream {
+            left: x,
+            width: source.size.width,
+            right: decoder.width() - x - source.size.width,
+            rows: source.size.height,
+            remaining: 0,
+            decoder,
+        })
+    }
+
+    /// Bytes of the next transfer decoded into `chunk`, `None` after the last row
+    pub(crate) fn next<'c>(
+        &mut self,
+        chunk: &'c mut [u8],
+    ) -> Result<Option<&'c [u8]>, DisplayError> {
+        if self.remaining == 0 {
+            if self.rows == 0 {
+                return Ok(None);
+            }
+            self.rows -= 1;
+            self.decoder.skip(self.left)?;
+            self.remaining = self.width as usize;
+        }
+        let len = self.remaining.min(chunk.len() / 2) * 2;
+        let chunk = &mut chunk[..len];
+        self.decoder.read(chunk)?;
+        self.remaining -= len / 2;
+        if self.remaining == 0 {
+            self.decoder.skip(self.right)?;
+        }
+        Ok(Some(chunk))
+    }
+}
+
+/// Visible part of a run-length encoded packet
+#[cfg(feature = "images")]
+pub(crate) enum Span<'a> {
+    /// Big endian pixels to send as they are
+    Pixels(&'a [u8]),
+    /// `len` pixels of `color`
+    Solid { color: u16, len: usize },
+}
+
+/// Visible parts of the packets of a run-length encoded image covering a source area
+#[cfg(feature = "images")]
+pub(crate) struct RleStream<'a> {
+    decoder: RleDecoder<'a>,
+    start: usize,
+    end: usize,
+    rows: u32,
+    /// Column the next packet starts at
+    column: usize,
+}
+
+#[cfg(feature = "images")]
+impl<'a> RleStream<'a> {
+    /// Stream the packets of `decoder` covering `source`, skipping the rows above it
+    pub(crate) fn new(
+        mut decoder: RleDecoder<'a>,
+        source: &Rectangle,
+    ) -> Result<Self, DisplayError> {
+        decoder.skip_rows(source.top_left.y as u16)?;
+        let start = source.top_left.x as usize;
+        Ok(RleStream {
+            start,
+            end: start + source.size.width as usize,
+            rows: source.size.height,
+            column: decoder.width() as usize,
+            decoder,
+        })
+    }
+
+    /// Next visible span, `None` after the last row
+    pub(crate) fn next(&mut self) -> Result<Option<Span<'a>>, DisplayError> {
+        loop {
+            if self.column >= self.decoder.width() as usize {
+                if self.rows == 0 {
+                    return Ok(None);
+                }
+                self.rows -= 1;
+                self.column = 0;
+            }
+            let packet = self.decoder.next_packet()?;
+            let column = self.column;
+            self.column += packet.len();
+
+            let (from, to) = (self.start.max(column), self.end.min(self.column));
+            if from < to {
+                return Ok(Some(match packet {
+                    Packet::Literal(bytes) => {
+                        Span::Pixels(&bytes[(from - column) * 2..(to - column) * 2])
+                    }
+                    Packet::Run { color, .. } => Span::Solid {
+                        color,
+                        len: to - from,
+                    },
+                }));
+            }
+        }
+    }
+}
+
+/// Slices of `data`, holding rows `width` pixels wide, covering `source`. One per row, or a single
+/// one if `merge` is set and `source` spans full rows.
+pub(crate) fn rows<'a, T: RawPixel>(
+    data: &'a [T],
+    width: usize,
+    source: &Rectangle,
+    merge: bool,
+) -> impl Iterator<Item = &'a [T]> {
+    let (x, y) = (source.top_left.x as usize, source.top_left.y as usize);
+    let (visible, height) = (source.size.width as usize, source.size.height as usize);
+    let stride = width * T::PER_PIXEL;
+
+    let (rows, len) = if merge && visible == width {
+        (1, height * stride)
+    } else {
+        (height, visible * T::PER_PIXEL)
+    };
+    let start = y * stride + x * T::PER_PIXEL;
+    (0..rows).map(move |row| &data[start + row * stride..][..len])
+}
+
+/// Colours of the pixels of `data`, holding rows `width` pixels wide, covering `source`
+fn colors<'a, T: RawPixel>(
+    data: &'a [T],
+    width: usize,
+    source: &Rectangle,
+) -> impl Iterator<Item = u16> + 'a {
+    rows(data, width, source, false).flat_map(|row| row.chunks_exact(T::PER_PIXEL).map(T::color))
+}
+
+/// Positions and colours of the pixels of `data`, holding the pixels of `area` row by row, visible
+/// within `bounds` and not of colour `key`
+pub(crate) fn opaque<'a, T: RawPixel>(
+    area: &Rectangle,
+    bounds: &Rectangle,
+    data: &'a [T],
+    key: u16,
+) -> impl Iterator<Item = (Point, u16)> + 'a {
+    let width = area.size.width as usize;
+    clip(area, bounds)
+        .into_iter()
+        .flat_map(move |(visible, source)| visible.points().zip(colors(data, width, &source)))
+        .filter(move |(_, color)| *color != key)
+}
+
+/// Copy `data`, holding the pixels of `area` row by row, into a framebuffer of rows `stride`
+/// pixels wide. Parts of the area outside `bounds` are skipped, as are pixels of colour `key`.
+/// A framebuffer too small for `bounds` is reported as `DisplayError::OutOfBoundsError`.
+pub(crate) fn copy_to_framebuffer<T: RawPixel>(
+    buffer: &mut [u8],
+    stride: usize,
+    bounds: &Rectangle,
+    area: &Rectangle,
+    data: &[T],
+    key: Option<u16>,
+) -> Result<(), DisplayError> {
+    check_len(area, data)?;
+    if buffer.len() < stride * bounds.size.height as usize * 2 {
+        return Err(DisplayError::OutOfBoundsError);
+    }
+    let Some((visible, source)) = clip(area, bounds) else {
+        return Ok(());
+    };
+    let width = area.size.width as usize;
+    let (x, y) = (visible.top_left.x as usize, visible.top_left.y as usize);
+
+    for (i, row) in rows(data, width, &source, false).enumerate() {
+        let start = ((y + i) * stride + x) * 2;
+        let target = &mut buffer[start..start + row.len() / T::PER_PIXEL * 2];
+        match key {
+            None => T::copy_be(row, target),
+            Some(key) => {
+                for (bytes, pixel) in target
+                    .chunks_exact_mut(2)
+                    .zip(row.chunks_exact(T::PER_PIXEL))
+                {
+                    let color = T::color(pixel);
+                    if color != key {
+                        bytes.copy_from_slice(&color.to_be_bytes());
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
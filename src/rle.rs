@@ -0,0 +1,222 @@
+//! Run-length encoded RGB565 images
+//!
+//! A cheap compressed format for assets in flash. Literal pixels are stored as big endian RGB565,
+//! the layout `Display::draw` expects, so they are sent straight from the encoded data, and runs of
+//! one colour are sent as chunked fills. The graphics modes draw whole images with
+//! [`DirectGraphicsMode::draw_rle`](../mode/graphics/struct.DirectGraphicsMode.html#method.draw_rle).
+//!
+//! The format starts with an 8 byte header: the magic `R565`, then the width and the height as big
+//! endian `u16`. Each row follows as packets covering exactly `width` pixels, packets never span
+//! rows:
+//!
+//! - `0x00..=0x7F`: literal, `n + 1` pixels follow
+//! - `0x80..=0xFF`: run, one pixel follows, repeated `(n & 0x7F) + 1` times
+//!
+//! Images are converted with [`encode`], e.g. from a build script, or with the `rle-encode` tool
+//! in `tools/` for PNG and BMP files.
+//!
+//! ```rust
+//! use ssd1351::rle::{encode, Packet, RleDecoder};
+//!
+//! let pixels = [0xF800, 0xF800, 0xF800, 0x001F, 0x07E0, 0x07E0];
+//! let mut data = [0u8; 32];
+//! let mut len = 0;
+//! encode(3, 2, &pixels, |bytes| {
+//!     data[len..len + bytes.len()].copy_from_slice(bytes);
+//!     len += bytes.len();
+//! })
+//! .unwrap();
+//!
+//! let mut decoder = RleDecoder::new(&data[..len]).unwrap();
+//! assert_eq!((decoder.width(), decoder.height()), (3, 2));
+//! assert_eq!(decoder.next_packet().unwrap(), Packet::Run { color: 0xF800, len: 3 });
+//! assert_eq!(decoder.next_packet().unwrap(), Packet::Literal(&[0x00, 0x1F]));
+//! assert_eq!(decoder.next_packet().unwrap(), Packet::Run { color: 0x07E0, len: 2 });
+//! ```
+
+use display_interface::DisplayError;
+
+const MAGIC: &[u8; 4] = b"R565";
+const HEADER_SIZE: usize = 8;
+
+/// Flag marking a run packet
+const RUN: u8 = 0x80;
+/// Most pixels held by a packet
+const MAX_PACKET: usize = 128;
+
+/// Pixels of a row decoded in one go
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Packet<'a> {
+    /// Pixels as big endian RGB565
+    Literal(&'a [u8]),
+    /// `len` pixels of `color`
+    Run {
+        /// RGB565 colour
+        color: u16,
+        /// Number of pixels
+        len: usize,
+    },
+}
+
+impl Packet<'_> {
+    /// Number of pixels covered
+    pub fn len(&self) -> usize {
+        match *self {
+            Packet::Literal(bytes) => bytes.len() / 2,
+            Packet::Run { len, .. } => len,
+        }
+    }
+
+    /// Whether the packet covers no pixels, never the case for valid data
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Decoder reading the packets of an image in row-major order. Malformed or truncated data is
+/// reported as `DisplayError::InvalidFormatError`.
+pub struct RleDecoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+    width: u16,
+    height: u16,
+    column: usize,
+    rows_left: u16,
+}
+
+impl<'a> RleDecoder<'a> {
+    /// Create a decoder for the image in `data`, checking its header
+    pub fn new(data: &'a [u8]) -> Result<Self, DisplayError> {
+        let header = data
+            .get(..HEADER_SIZE)
+            .ok_or(DisplayError::InvalidFormatError)?;
+        if &header[..4] != MAGIC {
+            return Err(DisplayError::InvalidFormatError);
+        }
+        let width = u16::from_be_bytes([header[4], header[5]]);
+        let height = u16::from_be_bytes([header[6], header[7]]);
+
+        Ok(RleDecoder {
+            data,
+            pos: HEADER_SIZE,
+            width,
+            height,
+            column: 0,
+            rows_left: if width == 0 { 0 } else { height },
+        })
+    }
+
+    /// Image width in pixels
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    /// Image height in pixels
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Decode the next packet
+    pub fn next_packet(&mut self) -> Result<Packet<'a>, DisplayError> {
+        if self.rows_left == 0 {
+            return Err(DisplayError::InvalidFormatError);
+        }
+
+        let header = self.bytes(1)?[0];
+        let len = (header & !RUN) as usize + 1;
+        if self.column + len > self.width as usize {
+            return Err(DisplayError::InvalidFormatError);
+        }
+        let packet = if header & RUN != 0 {
+            let color = self.bytes(2)?;
+            Packet::Run {
+                color: u16::from_be_bytes([color[0], color[1]]),
+                len,
+            }
+        } else {
+            Packet::Literal(self.bytes(len * 2)?)
+        };
+
+        self.column += len;
+        if self.column == self.width as usize {
+            self.column = 0;
+            self.rows_left -= 1;
+        }
+
+        Ok(packet)
+    }
+
+    /// Skip the next `rows` rows
+    pub fn skip_rows(&mut self, rows: u16) -> Result<(), DisplayError> {
+        for _ in 0..rows {
+            let mut column = 0;
+            while column < self.width as usize {
+                column += self.next_packet()?.len();
+            }
+        }
+        Ok(())
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], DisplayError> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or(DisplayError::InvalidFormatError)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+}
+
+/// Encode an image of `width` by `height` RGB565 `pixels` in row-major order, passing the encoded
+/// data to `write` piece by piece. Fails with `OutOfBoundsError` if `pixels` doesn't hold exactly
+/// `width * height` pixels.
+pub fn encode<F>(width: u16, height: u16, pixels: &[u16], mut write: F) -> Result<(), DisplayError>
+where
+    F: FnMut(&[u8]),
+{
+    if pixels.len() != width as usize * height as usize {
+        return Err(DisplayError::OutOfBoundsError);
+    }
+
+    write(MAGIC);
+    write(&width.to_be_bytes());
+    write(&height.to_be_bytes());
+
+    if width == 0 {
+        return Ok(());
+    }
+    for row in pixels.chunks_exact(width as usize) {
+        let mut rest = row;
+        while !rest.is_empty() {
+            let run = run_len(rest);
+            if run > 1 {
+                write(&[RUN | (run - 1) as u8]);
+                write(&rest[0].to_be_bytes());
+                rest = &rest[run..];
+                continue;
+            }
+
+            // Literal up to the start of the next run
+            let mut len = 1;
+            while len < rest.len().min(MAX_PACKET) && run_len(&rest[len..]) == 1 {
+                len += 1;
+            }
+            write(&[(len - 1) as u8]);
+            for pixel in &rest[..len] {
+                write(&pixel.to_be_bytes());
+            }
+            rest = &rest[len..];
+        }
+    }
+
+    Ok(())
+}
+
+/// Number of pixels at the start of `pixels` of the same colour, up to a packet
+fn run_len(pixels: &[u16]) -> usize {
+    pixels
+        .iter()
+        .take(MAX_PACKET)
+        .take_while(|&&pixel| pixel == pixels[0])
+        .count()
+}
@@ -0,0 +1,231 @@
+//! Checks framebuffer lengths are checked against the mode they are built for, buffered modes draw
+//! into framebuffers of any length without panicking, skipping what doesn't fit and refusing to
+//! flush them, and pixels are laid out in rows of the rotated display width
+
+#![cfg(feature = "graphics")]
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use embedded_graphics::pixelcolor::{Rgb565, RgbColor};
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+use ssd1351::builder::Builder;
+use ssd1351::mode::{BufferedGraphicsMode, Indexed4GraphicsMode, Indexed8GraphicsMode};
+use ssd1351::properties::{DisplayRotation, DisplaySize};
+
+/// Interface accepting everything
+struct Sink;
+
+impl WriteOnlyDataCommand for Sink {
+    fn send_commands(&mut self, _: DataFormat<'_>) -> Result<(), DisplayError> {
+        Ok(())
+    }
+
+    fn send_data(&mut self, _: DataFormat<'_>) -> Result<(), DisplayError> {
+        Ok(())
+    }
+}
+
+#[test]
+fn short_framebuffers_skip_pixels_and_fail_to_flush() {
+    // A strip buffer of two rows turned into a buffered mode
+    let mut display: BufferedGraphicsMode<_, _> = Builder::new()
+        .connect_interface_strip(Sink, [0u8; 512])
+        .unwrap()
+        .into();
+
+    display.set_pixel(127, 1, 0xABCD);
+    display.set_pixel(0, 2, 0xFFFF);
+    display.set_pixel(127, 127, 0xFFFF);
+    assert_eq!(display.fb()[510..], [0xAB, 0xCD]);
+
+    display
+        .fill_solid(
+            &Rectangle::new(Point::zero(), Size::new(128, 128)),
+            Rgb565::BLUE,
+        )
+        .unwrap();
+    assert!(display
+        .fb()
+        .chunks_exact(2)
+        .all(|pixel| pixel == [0x00, 0x1F]));
+
+    assert!(matches!(
+        display.draw_raw(&Rectangle::new(Point::zero(), Size::new(1, 1)), &[0xFF; 2]),
+        Err(DisplayError::OutOfBoundsError)
+    ));
+    assert!(matches!(
+        display.flush(),
+        Err(DisplayError::OutOfBoundsError)
+    ));
+    assert!(matches!(
+        display.clear(true),
+        Err(DisplayError::OutOfBoundsError)
+    ));
+}
+
+#[test]
+fn pixels_follow_the_display_width() {
+    let mut display: BufferedGraphicsMode<_, _> = Builder::new()
+        .with_size(DisplaySize::Display128x96)
+        .connect_interface_buffered(Sink, vec![0u8; DisplaySize::Display128x96.buffer_size()])
+        .unwrap()
+        .into();
+
+    display.set_pixel(3, 95, 0xABCD);
+    // Below the display, inside a 128 x 128 framebuffer
+    display.set_pixel(3, 96, 0xFFFF);
+
+    let idx = (95 * 128 + 3) * 2;
+    assert_eq!(display.fb()[idx..idx + 2], [0xAB, 0xCD]);
+    assert!(display.fb()[idx + 2..].iter().all(|&byte| byte == 0));
+}
+
+#[test]
+fn rotated_pixels_follow_the_logical_width() {
+    const FB_SIZE: usize = DisplaySize::Display128x96.buffer_size();
+    let mut display: BufferedGraphicsMode<_, _> = Builder::new()
+        .with_size(DisplaySize::Display128x96)
+        .with_rotation(DisplayRotation::Rotate90)
+        .connect_interface_buffered(Sink, vec![0u8; FB_SIZE])
+        .unwrap()
+        .into();
+    assert_eq!(display.size(), Size::new(96, 128));
+
+    display.set_pixel(95, 127, 0xABCD);
+    // Right of the rotated display, inside a 128 pixel wide row
+    display.set_pixel(96, 0, 0xFFFF);
+    assert_eq!(display.fb()[FB_SIZE - 2..], [0xAB, 0xCD]);
+    assert!(display.fb()[..FB_SIZE - 2].iter().all(|&byte| byte == 0));
+
+    display
+        .draw_raw_u16(
+            &Rectangle::new(Point::new(94, 126), Size::new(2, 2)),
+            &[1, 2, 3, 4],
+        )
+        .unwrap();
+    let row = 126 * 96 * 2;
+    assert_eq!(display.fb()[row + 188..row + 192], [0, 1, 0, 2]);
+    display.flush().unwrap();
+}
+
+#[test]
+fn indexed_framebuffers_match_their_format() {
+    const INDEX4: usize = DisplaySize::Display128x128.indexed_buffer_size(4);
+    const INDEX8: usize = DisplaySize::Display128x128.indexed_buffer_size(8);
+
+    let display: Result<Indexed8GraphicsMode<_, _>, _> =
+        Builder::new().connect_interface_indexed(Sink, [0u8; INDEX4]);
+    assert!(matches!(display, Err(DisplayError::OutOfBoundsError)));
+    let display: Result<Indexed4GraphicsMode<_, _>, _> =
+        Builder::new().connect_interface_indexed(Sink, [0u8; INDEX8]);
+    assert!(matches!(display, Err(DisplayError::OutOfBoundsError)));
+
+    let mut display: Indexed8GraphicsMode<_, _> = Builder::new()
+        .connect_interface_indexed(Sink, [0u8; INDEX8])
+        .unwrap();
+    display.set_index(127, 127, 200);
+    assert_eq!(display.fb()[INDEX8 - 1], 200);
+    display.flush().unwrap();
+
+    let mut display: Indexed4GraphicsMode<_, _> = Builder::new()
+        .connect_interface_indexed(Sink, [0u8; INDEX4])
+        .unwrap();
+    display.set_index(126, 127, 3);
+    display.set_index(127, 127, 200);
+    assert_eq!(display.fb()[INDEX4 - 1], 0x38);
+    display.flush().unwrap();
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn async_short_framebuffers_skip_pixels_and_fail_to_flush() {
+    use ssd1351::async_builder::AsyncBuilder;
+    use ssd1351::async_interface::BlockingAdapter;
+    use ssd1351::mode::async_displaymode::AsyncDisplayMode;
+    use ssd1351::mode::{AsyncBufferedGraphicsMode, AsyncDoubleBufferedGraphicsMode, AsyncRawMode};
+
+    // Buffers of two rows handed over by mode switching, which doesn't check their length
+    let display = AsyncBuilder::new().connect_display(BlockingAdapter::new(Sink));
+    let mut display: AsyncBufferedGraphicsMode<_, _> =
+        AsyncDisplayMode::<AsyncRawMode<_, _>>::new(display, [0u8; 512]).into();
+    display.set_pixel(127, 1, 0xABCD);
+    display.set_pixel(127, 127, 0xFFFF);
+    Pixel(Point::new(0, 100), Rgb565::BLUE)
+        .draw(&mut display)
+        .unwrap();
+    assert_eq!(display.fb()[510..], [0xAB, 0xCD]);
+    for result in [
+        block_on(display.flush()),
+        block_on(display.flush_chunked(64)),
+    ] {
+        assert!(matches!(result, Err(DisplayError::OutOfBoundsError)));
+    }
+
+    let display = AsyncBuilder::new().connect_display(BlockingAdapter::new(Sink));
+    let mut display: AsyncDoubleBufferedGraphicsMode<_, _> =
+        AsyncDisplayMode::<AsyncRawMode<_, _>>::new(display, [[0u8; 512]; 2]).into();
+    let mut back = display.back();
+    back.set_pixel(127, 1, 0xABCD);
+    back.set_pixel(127, 127, 0xFFFF);
+    Pixel(Point::new(0, 100), Rgb565::BLUE)
+        .draw(&mut back)
+        .unwrap();
+    assert_eq!(back.fb()[510..], [0xAB, 0xCD]);
+    assert!(matches!(
+        block_on(display.flush()),
+        Err(DisplayError::OutOfBoundsError)
+    ));
+    assert!(matches!(
+        block_on(display.swap_and_flush(|_| {})).1,
+        Err(DisplayError::OutOfBoundsError)
+    ));
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn async_rotated_pixels_follow_the_logical_width() {
+    use ssd1351::async_builder::AsyncBuilder;
+    use ssd1351::async_interface::BlockingAdapter;
+    use ssd1351::mode::{AsyncBufferedGraphicsMode, AsyncDoubleBufferedGraphicsMode};
+
+    const FB_SIZE: usize = DisplaySize::Display128x96.buffer_size();
+    let builder = AsyncBuilder::new()
+        .with_size(DisplaySize::Display128x96)
+        .with_rotation(DisplayRotation::Rotate270);
+
+    let mut display: AsyncBufferedGraphicsMode<_, _> = builder
+        .connect_interface_buffered(BlockingAdapter::new(Sink), vec![0u8; FB_SIZE])
+        .unwrap();
+    assert_eq!(display.size(), Size::new(96, 128));
+    display.set_pixel(95, 127, 0xABCD);
+    display.set_pixel(96, 0, 0xFFFF);
+    assert_eq!(display.fb()[FB_SIZE - 2..], [0xAB, 0xCD]);
+    assert!(display.fb()[..FB_SIZE - 2].iter().all(|&byte| byte == 0));
+    block_on(display.flush()).unwrap();
+
+    let mut display: AsyncDoubleBufferedGraphicsMode<_, _> = builder
+        .connect_interface_double_buffered(
+            BlockingAdapter::new(Sink),
+            vec![0u8; FB_SIZE],
+            vec![0u8; FB_SIZE],
+        )
+        .unwrap();
+    let mut back = display.back();
+    assert_eq!(back.size(), Size::new(96, 128));
+    back.set_pixel(95, 127, 0xABCD);
+    back.set_pixel(96, 0, 0xFFFF);
+    assert_eq!(back.fb()[FB_SIZE - 2..], [0xAB, 0xCD]);
+    assert!(back.fb()[..FB_SIZE - 2].iter().all(|&byte| byte == 0));
+}
+
+/// Poll `future` to completion
+#[cfg(feature = "async")]
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+    loop {
+        if let std::task::Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! Checks commands are encoded with their parameters range checked, and batches split long runs
+//! and stay unchanged when a command doesn't fit
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use ssd1351::command::{Command, CommandBatch, MAX_PARAMS};
+
+/// Interface recording every transfer, `true` for parameter bytes
+#[derive(Default)]
+struct Recorder {
+    sent: Vec<(bool, Vec<u8>)>,
+}
+
+impl WriteOnlyDataCommand for Recorder {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        match cmd {
+            DataFormat::U8(bytes) => self.sent.push((false, bytes.to_vec())),
+            _ => return Err(DisplayError::DataFormatNotImplemented),
+        }
+        Ok(())
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        match buf {
+            DataFormat::U8(bytes) => self.sent.push((true, bytes.to_vec())),
+            _ => return Err(DisplayError::DataFormatNotImplemented),
+        }
+        Ok(())
+    }
+}
+
+fn runs<const N: usize>(batch: &CommandBatch<N>) -> Vec<(bool, Vec<u8>)> {
+    batch
+        .runs()
+        .map(|(data, bytes)| (data, bytes.to_vec()))
+        .collect()
+}
+
+/// Gamma table increasing by 2 from `first`
+fn gamma(first: u8) -> [u8; MAX_PARAMS] {
+    core::array::from_fn(|i| first + 2 * i as u8)
+}
+
+#[test]
+fn parameters_are_range_checked() {
+    let invalid = [
+        Command::Column(10, 9),
+        Command::Column(0, 128),
+        Command::Row(0, 128),
+        Command::CommandLock(0x13),
+        Command::ClockDiv(0xFB),
+        Command::MuxRatio(14),
+        Command::StartLine(128),
+        Command::DisplayOffset(128),
+        Command::SetGpio(0x10),
+        Command::FunctionSelect(0x02),
+        Command::FunctionSelect(0x80),
+        Command::PreCharge(0x31),
+        Command::PreCharge(0x22),
+        Command::Vcomh(8),
+        Command::ContrastCurrent(16),
+        Command::PreCharge2(0),
+        Command::PreCharge2(16),
+        Command::PreChargeVoltage(32),
+        Command::HorizontalScroll(0x80, 0, 128, 0),
+        Command::HorizontalScroll(1, 0, 129, 0),
+        Command::HorizontalScroll(1, 128, 0, 0),
+        Command::HorizontalScroll(1, 0, 128, 4),
+    ];
+    for command in invalid {
+        assert!(matches!(
+            command.encode(),
+            Err(DisplayError::OutOfBoundsError)
+        ));
+    }
+
+    let valid = [
+        Command::Column(0, 0),
+        Command::Row(0, 127),
+        Command::CommandLock(0xB1),
+        Command::ClockDiv(0xFA),
+        Command::MuxRatio(15),
+        Command::FunctionSelect(0x01),
+        Command::PreCharge(0x32),
+        Command::PreCharge2(15),
+        Command::HorizontalScroll(1, 0, 128, 3),
+    ];
+    for command in valid {
+        assert!(command.encode().is_ok());
+    }
+}
+
+#[test]
+fn gamma_tables_are_checked() {
+    // 56 to 180
+    let table = gamma(56);
+    let encoded = Command::GammaTable(&table).encode().unwrap();
+    assert_eq!(encoded.parts(), (0xB8, &table[..]));
+
+    // 58 to 182
+    assert!(Command::GammaTable(&gamma(58)).encode().is_err());
+
+    let mut flat = gamma(0);
+    flat[10] = flat[9];
+    assert!(Command::GammaTable(&flat).encode().is_err());
+
+    let mut decreasing = gamma(0);
+    decreasing[10] = decreasing[9] - 1;
+    assert!(Command::GammaTable(&decreasing).encode().is_err());
+}
+
+#[test]
+fn long_runs_are_split() {
+    let mut batch = CommandBatch::<256>::new();
+    for _ in 0..130 {
+        batch.push(Command::StopScroll).unwrap();
+    }
+    batch.push(Command::Column(0, 127)).unwrap();
+
+    assert_eq!(
+        runs(&batch),
+        [
+            (false, vec![0x9E; 127]),
+            (false, vec![0x9E, 0x9E, 0x9E, 0x15]),
+            (true, vec![0, 127]),
+        ]
+    );
+
+    let expected = runs(&batch);
+    let mut recorder = Recorder::default();
+    batch.flush(&mut recorder).unwrap();
+    assert!(batch.is_empty());
+    assert_eq!(recorder.sent, expected);
+}
+
+#[test]
+fn failed_pushes_leave_the_batch_unchanged() {
+    let mut batch = CommandBatch::<9>::new();
+    batch
+        .push(Command::DisplayOn(false))
+        .unwrap()
+        .push(Command::ClockDiv(0xF1))
+        .unwrap();
+    let before = runs(&batch);
+
+    // Opcode fits, parameters don't
+    assert!(matches!(
+        batch.push(Command::Column(0, 127)),
+        Err(DisplayError::OutOfBoundsError)
+    ));
+    assert_eq!(runs(&batch), before);
+
+    // Out of range parameters
+    assert!(batch.push(Command::MuxRatio(128)).is_err());
+    assert_eq!(runs(&batch), before);
+
+    // The batch is still usable
+    batch.push(Command::StartLine(0)).unwrap();
+    assert_eq!(
+        runs(&batch),
+        [
+            (false, vec![0xAE, 0xB3]),
+            (true, vec![0xF1]),
+            (false, vec![0xA1]),
+            (true, vec![0]),
+        ]
+    );
+
+    // The opcode extends a command run, then the parameters don't fit
+    let mut batch = CommandBatch::<4>::new();
+    batch.push(Command::DisplayOn(false)).unwrap();
+    let before = runs(&batch);
+    assert!(batch.push(Command::ClockDiv(0xF1)).is_err());
+    assert_eq!(runs(&batch), before);
+    let mut recorder = Recorder::default();
+    batch.flush(&mut recorder).unwrap();
+    assert_eq!(recorder.sent, before);
+
+    let mut batch = CommandBatch::<4>::new();
+    let table = gamma(0);
+    assert!(batch.push(Command::GammaTable(&table)).is_err());
+    assert!(batch.is_empty());
+}
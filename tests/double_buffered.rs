@@ -0,0 +1,171 @@
+//! Checks double buffered flushes draw the next frame while the front buffer is in flight,
+//! alternate the buffers, recover from transfer errors as the recovery policy says and report them
+//! along with what was drawn
+
+#![cfg(feature = "async")]
+
+use std::cell::{Cell, RefCell};
+use std::future::{poll_fn, Future};
+use std::pin::pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+use display_interface::{DataFormat, DisplayError};
+use ssd1351::async_builder::AsyncBuilder;
+use ssd1351::async_display::RecoveryPolicy;
+use ssd1351::async_interface::AsyncWriteOnlyDataCommand;
+use ssd1351::mode::AsyncDoubleBufferedGraphicsMode;
+use ssd1351::properties::DisplaySize;
+
+/// Write RAM command, sent before the pixel data
+const WRITE_RAM: u8 = 0x5C;
+
+/// Transfer state shared with the test
+#[derive(Default)]
+struct Bus {
+    /// Whether a pixel data transfer is under way
+    in_flight: Cell<bool>,
+    /// Fail the next pixel data transfer, once it has been under way
+    fail: Cell<bool>,
+    /// First byte of each frame sent
+    frames: RefCell<Vec<u8>>,
+}
+
+/// Interface keeping pixel data transfers pending for a few polls, as a DMA transfer would
+#[derive(Default)]
+struct Dma {
+    bus: Rc<Bus>,
+    opcode: u8,
+}
+
+impl AsyncWriteOnlyDataCommand for Dma {
+    async fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        let DataFormat::U8(bytes) = cmd else {
+            return Err(DisplayError::DataFormatNotImplemented);
+        };
+        self.opcode = *bytes.last().unwrap();
+        Ok(())
+    }
+
+    async fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        let DataFormat::U8(bytes) = buf else {
+            return Err(DisplayError::DataFormatNotImplemented);
+        };
+        if self.opcode != WRITE_RAM {
+            return Ok(());
+        }
+
+        self.bus.in_flight.set(true);
+        let mut polls = 0;
+        poll_fn(|_| {
+            polls += 1;
+            if polls > 3 {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await;
+        self.bus.in_flight.set(false);
+
+        if self.bus.fail.take() {
+            return Err(DisplayError::BusWriteError);
+        }
+        self.bus.frames.borrow_mut().push(bytes[0]);
+        Ok(())
+    }
+}
+
+const FB_SIZE: usize = DisplaySize::Display128x128.buffer_size();
+
+/// Poll `future` to completion
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+/// Double buffered mode with the front buffer filled with 1 and the back buffer with 2
+fn double_buffered() -> (AsyncDoubleBufferedGraphicsMode<Dma, Vec<u8>>, Rc<Bus>) {
+    let dma = Dma::default();
+    let bus = dma.bus.clone();
+    let display = AsyncBuilder::new()
+        .connect_interface_double_buffered(dma, vec![1u8; FB_SIZE], vec![2u8; FB_SIZE])
+        .unwrap();
+    (display, bus)
+}
+
+#[test]
+fn frames_are_drawn_during_the_transfer() {
+    let (mut display, bus) = double_buffered();
+
+    // The back buffer goes out, the old front buffer is drawn into
+    let (drawn, sent) = block_on(display.swap_and_flush(|back| {
+        assert!(bus.in_flight.get());
+        assert!(back.fb().iter().all(|&byte| byte == 1));
+        back.fb_mut().fill(3);
+        "first"
+    }));
+    sent.unwrap();
+    assert_eq!(drawn, "first");
+    assert!(!bus.in_flight.get());
+    assert_eq!(*bus.frames.borrow(), [2]);
+    assert!(display.front().iter().all(|&byte| byte == 2));
+
+    // The frame just drawn goes out next, and the buffer sent last time is handed back
+    block_on(display.swap_and_flush(|back| {
+        assert!(bus.in_flight.get());
+        assert!(back.fb().iter().all(|&byte| byte == 2));
+        back.fb_mut().fill(4);
+    }))
+    .1
+    .unwrap();
+    assert_eq!(*bus.frames.borrow(), [2, 3]);
+
+    // A plain flush resends the front buffer
+    block_on(display.flush()).unwrap();
+    assert_eq!(*bus.frames.borrow(), [2, 3, 3]);
+
+    let (_, front, back) = display.release();
+    assert!(front.iter().all(|&byte| byte == 3));
+    assert!(back.iter().all(|&byte| byte == 4));
+}
+
+#[test]
+fn transfer_errors_follow_the_draw() {
+    let (mut display, bus) = double_buffered();
+    bus.fail.set(true);
+
+    let (drawn, sent) = block_on(display.swap_and_flush(|back| {
+        assert!(bus.in_flight.get());
+        back.fb_mut().fill(5);
+        "drawn"
+    }));
+    assert!(matches!(sent, Err(DisplayError::BusWriteError)));
+    assert_eq!(drawn, "drawn");
+    assert!(bus.frames.borrow().is_empty());
+
+    // The frame drawn is kept for the next swap
+    block_on(display.swap_and_flush(|_| {})).1.unwrap();
+    assert_eq!(*bus.frames.borrow(), [5]);
+}
+
+#[test]
+fn transfers_are_retried() {
+    let dma = Dma::default();
+    let bus = dma.bus.clone();
+    let mut display: AsyncDoubleBufferedGraphicsMode<_, _> = AsyncBuilder::new()
+        .with_recovery_policy(RecoveryPolicy::Retry(1))
+        .connect_interface_double_buffered(dma, vec![1u8; FB_SIZE], vec![2u8; FB_SIZE])
+        .unwrap();
+    bus.fail.set(true);
+
+    let (_, sent) = block_on(display.swap_and_flush(|back| back.fb_mut().fill(3)));
+    sent.unwrap();
+    assert_eq!(*bus.frames.borrow(), [2]);
+    assert!(display.front().iter().all(|&byte| byte == 2));
+}
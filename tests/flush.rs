@@ -0,0 +1,308 @@
+//! Checks chunked async flushes yield between chunks and recover from being dropped, and that
+//! flushes recover from errors and timeouts as the recovery policy says
+
+#![cfg(feature = "async")]
+
+use std::future::{pending, poll_fn, Future};
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use embedded_hal_async::delay::DelayNs;
+use ssd1351::async_builder::AsyncBuilder;
+use ssd1351::async_display::{RecoveryPolicy, TimeoutError};
+use ssd1351::async_interface::{AsyncWriteOnlyDataCommand, BlockingAdapter};
+
+/// Column address command, starting every draw area
+const COLUMN: u8 = 0x15;
+/// Write RAM command, ending every draw area
+const WRITE_RAM: u8 = 0x5C;
+/// Display on command, ending the init sequence
+const DISPLAY_ON: u8 = 0xAF;
+
+/// A transfer over the interface
+#[derive(Debug, PartialEq)]
+enum Sent {
+    Commands(Vec<u8>),
+    Data(Vec<u8>),
+}
+
+/// Interface recording every transfer
+#[derive(Default)]
+struct Recorder {
+    sent: Vec<Sent>,
+}
+
+impl WriteOnlyDataCommand for Recorder {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        match cmd {
+            DataFormat::U8(bytes) => self.sent.push(Sent::Commands(bytes.to_vec())),
+            _ => return Err(DisplayError::DataFormatNotImplemented),
+        }
+        Ok(())
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        match buf {
+            DataFormat::U8(bytes) => self.sent.push(Sent::Data(bytes.to_vec())),
+            _ => return Err(DisplayError::DataFormatNotImplemented),
+        }
+        Ok(())
+    }
+}
+
+impl Recorder {
+    /// The pixel data sent since the draw area was last set
+    fn pixels(&self) -> Vec<u8> {
+        let start = self
+            .sent
+            .iter()
+            .rposition(|sent| matches!(sent, Sent::Commands(cmd) if cmd.last() == Some(&WRITE_RAM)))
+            .map_or(0, |i| i + 1);
+        self.sent[start..]
+            .iter()
+            .filter_map(|sent| match sent {
+                Sent::Data(bytes) => Some(bytes.as_slice()),
+                Sent::Commands(_) => None,
+            })
+            .flatten()
+            .copied()
+            .collect()
+    }
+}
+
+/// Interface failing or hanging the first data transfers, recording the others
+#[derive(Default)]
+struct Flaky {
+    recorder: Recorder,
+    fail: usize,
+    hang: usize,
+}
+
+impl AsyncWriteOnlyDataCommand for Flaky {
+    async fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.recorder.send_commands(cmd)
+    }
+
+    async fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        if self.hang > 0 {
+            self.hang -= 1;
+            pending::<()>().await;
+        }
+        if self.fail > 0 {
+            self.fail -= 1;
+            return Err(DisplayError::BusWriteError);
+        }
+        self.recorder.send_data(buf)
+    }
+}
+
+/// Delay expiring on the second poll, whatever its length
+struct Ticks;
+
+impl DelayNs for Ticks {
+    async fn delay_ns(&mut self, _: u32) {
+        let mut polled = false;
+        poll_fn(|_| {
+            if polled {
+                Poll::Ready(())
+            } else {
+                polled = true;
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+/// Poll `future` to completion, returning its output and how often it was polled
+fn poll_count<F: Future>(future: F) -> (F::Output, usize) {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut polls = 1;
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return (output, polls);
+        }
+        polls += 1;
+    }
+}
+
+fn framebuffer() -> Vec<u8> {
+    (0..128 * 128 * 2).map(|i| i as u8).collect()
+}
+
+#[test]
+fn flush_chunked_yields_between_chunks() {
+    let mut display = AsyncBuilder::new()
+        .connect_interface_buffered(BlockingAdapter::new(Recorder::default()), framebuffer())
+        .unwrap();
+
+    let (result, polls) = poll_count(display.flush_chunked(4096));
+    result.unwrap();
+    // 32 KiB in 4 KiB chunks
+    assert_eq!(polls, 8);
+
+    let (display, buffer) = display.release();
+    let recorder = display.release().release();
+    assert_eq!(recorder.pixels(), buffer);
+}
+
+#[test]
+fn dropped_flush_sets_the_draw_area_again() {
+    let mut display = AsyncBuilder::new()
+        .connect_interface_buffered(BlockingAdapter::new(Recorder::default()), framebuffer())
+        .unwrap();
+
+    {
+        let mut flush = pin!(display.flush_chunked(4096));
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(flush.as_mut().poll(&mut cx).is_pending());
+    }
+
+    // The next flush starts over and sends the whole framebuffer
+    poll_count(display.flush_chunked(4096)).0.unwrap();
+    let (display, buffer) = display.release();
+    assert_eq!(display.release().release().pixels(), buffer);
+
+    // A raw draw after a dropped transfer starts at the beginning of the draw area again
+    let mut display =
+        AsyncBuilder::new().connect_display(BlockingAdapter::new(Recorder::default()));
+    poll_count(display.set_draw_area((0, 0), (4, 4))).0.unwrap();
+    {
+        let mut draw = pin!(display.draw_chunked(&[0xAB; 32], 8));
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(draw.as_mut().poll(&mut cx).is_pending());
+    }
+    poll_count(display.draw(&[0xCD; 2])).0.unwrap();
+
+    let recorder = display.release().release();
+    assert_eq!(recorder.pixels(), [0xCD; 2]);
+    let sent = recorder.sent;
+    let areas: Vec<_> = sent
+        .iter()
+        .enumerate()
+        .filter(|(_, sent)| matches!(sent, Sent::Commands(cmd) if cmd[0] == COLUMN))
+        .map(|(i, _)| i)
+        .collect();
+    assert_eq!(areas.len(), 2);
+    assert_eq!(sent[areas[0]..areas[0] + 5], sent[areas[1]..areas[1] + 5]);
+}
+
+#[test]
+fn flush_applies_the_recovery_policy() {
+    let flush = |policy, fail| {
+        let flaky = Flaky {
+            fail,
+            ..Flaky::default()
+        };
+        let mut display = AsyncBuilder::new()
+            .with_recovery_policy(policy)
+            .connect_interface_buffered(flaky, framebuffer())
+            .unwrap();
+        let result = poll_count(display.flush()).0;
+        let (display, buffer) = display.release();
+        (result, display.release().recorder, buffer)
+    };
+
+    let (result, _, _) = flush(RecoveryPolicy::Report, 1);
+    assert!(matches!(result, Err(DisplayError::BusWriteError)));
+
+    let (result, recorder, buffer) = flush(RecoveryPolicy::Retry(2), 2);
+    assert!(result.is_ok());
+    assert_eq!(recorder.pixels(), buffer);
+
+    let (result, _, _) = flush(RecoveryPolicy::Retry(2), 3);
+    assert!(matches!(result, Err(DisplayError::BusWriteError)));
+
+    let (result, recorder, buffer) = flush(RecoveryPolicy::Reinit, 1);
+    assert!(result.is_ok());
+    assert!(recorder.sent.contains(&Sent::Commands(vec![DISPLAY_ON])));
+    assert_eq!(recorder.pixels(), buffer);
+}
+
+#[test]
+fn flush_timeout_retries_hung_transfers() {
+    let flush = |policy| {
+        let flaky = Flaky {
+            hang: 1,
+            ..Flaky::default()
+        };
+        let mut display = AsyncBuilder::new()
+            .with_recovery_policy(policy)
+            .connect_interface_buffered(flaky, framebuffer())
+            .unwrap();
+        let result = poll_count(display.flush_timeout(&mut Ticks, 10)).0;
+        let (display, buffer) = display.release();
+        (result, display.release().recorder, buffer)
+    };
+
+    let (result, _, _) = flush(RecoveryPolicy::Report);
+    assert!(matches!(result, Err(TimeoutError::TimedOut)));
+
+    let (result, recorder, buffer) = flush(RecoveryPolicy::Retry(1));
+    assert!(result.is_ok());
+    assert_eq!(recorder.pixels(), buffer);
+}
+
+#[test]
+fn flush_timeout_limits_the_reinit() {
+    // The reinit completes within its own deadline, without clearing the display
+    let flaky = Flaky {
+        hang: 1,
+        ..Flaky::default()
+    };
+    let mut display = AsyncBuilder::new()
+        .with_recovery_policy(RecoveryPolicy::Reinit)
+        .connect_interface_buffered(flaky, framebuffer())
+        .unwrap();
+    let result = poll_count(display.flush_timeout(&mut Ticks, 10)).0;
+    assert!(result.is_ok());
+
+    let (display, buffer) = display.release();
+    let recorder = display.release().recorder;
+    assert!(recorder.sent.contains(&Sent::Commands(vec![DISPLAY_ON])));
+    assert_eq!(recorder.pixels(), buffer);
+
+    // The interface stalls during the reinit as well
+    let flaky = Flaky {
+        hang: 2,
+        ..Flaky::default()
+    };
+    let mut display = AsyncBuilder::new()
+        .with_recovery_policy(RecoveryPolicy::Reinit)
+        .connect_interface_buffered(flaky, framebuffer())
+        .unwrap();
+    let result = poll_count(display.flush_timeout(&mut Ticks, 10)).0;
+    assert!(matches!(result, Err(TimeoutError::TimedOut)));
+}
+
+#[test]
+fn operations_time_out() {
+    let flaky = Flaky {
+        hang: 1,
+        ..Flaky::default()
+    };
+    let mut display = AsyncBuilder::new().connect_display(flaky);
+    let result =
+        poll_count(display.with_timeout(&mut Ticks, 10, async |display| display.init().await));
+    assert!(matches!(result.0, Err(TimeoutError::TimedOut)));
+
+    // Failures are told apart from timeouts
+    let result = poll_count(display.with_timeout(&mut Ticks, 10, async |display| {
+        display.set_draw_area((0, 0), (0, 4)).await
+    }));
+    assert!(matches!(
+        result.0,
+        Err(TimeoutError::Display(DisplayError::OutOfBoundsError))
+    ));
+
+    // Operations completing in time return their result
+    let result = poll_count(display.with_timeout(&mut Ticks, 10, async |display| {
+        display.set_draw_area((0, 0), (4, 4)).await?;
+        display.draw(&[0xAB; 32]).await?;
+        Ok(display.get_dimensions())
+    }));
+    assert!(matches!(result.0, Ok((128, 128))));
+    assert_eq!(display.release().recorder.pixels(), [0xAB; 32]);
+}
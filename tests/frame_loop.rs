@@ -0,0 +1,179 @@
+//! Checks the frame loop paces frames on a fixed grid, skips frames when behind and measures
+//! frame timings
+
+#![cfg(feature = "async")]
+
+use std::cell::Cell;
+use std::future::{ready, Future};
+use std::pin::pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use ssd1351::async_builder::AsyncBuilder;
+use ssd1351::async_frame_loop::{FrameLoop, Monotonic};
+use ssd1351::async_interface::BlockingAdapter;
+
+/// Clock advancing only when told to, jumping to deadlines when waited on
+#[derive(Clone, Default)]
+struct FakeClock {
+    now: Rc<Cell<u64>>,
+    waits: Rc<Cell<u32>>,
+}
+
+impl FakeClock {
+    fn advance(&self, us: u64) {
+        self.now.set(self.now.get() + us);
+    }
+}
+
+impl Monotonic for FakeClock {
+    fn now_us(&self) -> u64 {
+        self.now.get()
+    }
+
+    fn wait_until(&mut self, deadline_us: u64) -> impl Future<Output = ()> {
+        self.waits.set(self.waits.get() + 1);
+        self.now.set(self.now.get().max(deadline_us));
+        ready(())
+    }
+}
+
+/// Interface taking 1 ms for every data transfer
+struct Slow {
+    clock: FakeClock,
+}
+
+impl WriteOnlyDataCommand for Slow {
+    fn send_commands(&mut self, _: DataFormat<'_>) -> Result<(), DisplayError> {
+        Ok(())
+    }
+
+    fn send_data(&mut self, _: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.clock.advance(1_000);
+        Ok(())
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+#[test]
+fn frames_are_paced_and_skipped() {
+    let clock = FakeClock::default();
+    let interface = BlockingAdapter::new(Slow {
+        clock: clock.clone(),
+    });
+    let mut display = AsyncBuilder::new()
+        .connect_interface_buffered(interface, [0u8; 32768])
+        .unwrap();
+    // 50 fps, a frame every 20 ms
+    let mut frames = FrameLoop::new(clock.clone(), 50);
+
+    let mut frame = |frames: &mut FrameLoop<FakeClock>, render_us: u64| {
+        let clock = clock.clone();
+        block_on(frames.frame(&mut display, async |_| {
+            let start = clock.now_us();
+            clock.advance(render_us);
+            start
+        }))
+        .unwrap()
+    };
+
+    // The first frame starts right away, later ones on a 20 ms grid. Flushing takes 3 ms, for the
+    // column and row arguments and the frame data
+    assert_eq!(frame(&mut frames, 5_000), 0);
+    assert_eq!(frame(&mut frames, 5_000), 20_000);
+    assert_eq!(frame(&mut frames, 5_000), 40_000);
+    let stats = frames.stats();
+    assert_eq!(stats.frames, 3);
+    assert_eq!(stats.skipped, 0);
+    assert_eq!(stats.render_us, 5_000);
+    assert_eq!(stats.flush_us, 3_000);
+    assert_eq!(stats.frame_us, 20_000);
+    assert_eq!(stats.fps(), 50);
+    assert_eq!(clock.waits.get(), 2);
+
+    // The frame at 60 ms ends at 108 ms, missing the frame at 80 ms. The next one starts late for
+    // the frame at 100 ms
+    assert_eq!(frame(&mut frames, 45_000), 60_000);
+    assert_eq!(frame(&mut frames, 5_000), 108_000);
+    let stats = frames.stats();
+    assert_eq!(stats.skipped, 1);
+    assert_eq!(stats.frame_us, 48_000);
+
+    // Back on the grid
+    assert_eq!(frame(&mut frames, 5_000), 120_000);
+    assert_eq!(frames.stats().skipped, 1);
+}
+
+#[test]
+fn rates_are_clamped() {
+    let clock = FakeClock::default();
+    let interface = BlockingAdapter::new(Slow {
+        clock: clock.clone(),
+    });
+    let mut display = AsyncBuilder::new()
+        .connect_interface_buffered(interface, [0u8; 32768])
+        .unwrap();
+
+    // A frame every microsecond, each of the 3 ms flushes skipping all but one of them
+    let mut frames = FrameLoop::new(clock.clone(), u32::MAX);
+    for _ in 0..3 {
+        block_on(frames.frame(&mut display, async |_| {})).unwrap();
+    }
+    let stats = frames.stats();
+    assert_eq!(stats.frames, 3);
+    assert_eq!(stats.skipped, 2 * 2_999);
+    assert_eq!(clock.waits.get(), 0);
+}
+
+#[test]
+fn direct_frames_have_nothing_to_flush() {
+    let clock = FakeClock::default();
+    let interface = BlockingAdapter::new(Slow {
+        clock: clock.clone(),
+    });
+    let mut display = AsyncBuilder::new().connect_interface(interface);
+
+    // The pixel goes out while rendering: the column and row arguments, then its color
+    let mut frames = FrameLoop::new(clock.clone(), 50);
+    for _ in 0..2 {
+        block_on(frames.frame(&mut display, async |display| {
+            display.set_pixel(1, 2, 0xf800).await
+        }))
+        .unwrap()
+        .unwrap();
+    }
+    let stats = frames.stats();
+    assert_eq!(stats.frames, 2);
+    assert_eq!(stats.render_us, 3_000);
+    assert_eq!(stats.flush_us, 0);
+    assert_eq!(stats.frame_us, 20_000);
+}
+
+#[test]
+fn double_buffered_frames_send_the_back_buffer() {
+    let clock = FakeClock::default();
+    let interface = BlockingAdapter::new(Slow {
+        clock: clock.clone(),
+    });
+    let mut display = AsyncBuilder::new()
+        .connect_interface_double_buffered(interface, [0u8; 32768], [0u8; 32768])
+        .unwrap();
+
+    let mut frames = FrameLoop::new(clock.clone(), 50);
+    block_on(frames.frame(&mut display, async |display| {
+        display.back().set_pixel(0, 0, 0xf800)
+    }))
+    .unwrap();
+    assert_eq!(&display.front()[..2], &[0xf8, 0x00]);
+    assert_eq!(frames.stats().flush_us, 3_000);
+}
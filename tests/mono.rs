@@ -0,0 +1,178 @@
+//! Checks monochrome flushes send only the rows changed since the last flush, expanded to the
+//! foreground and background colours
+
+#![cfg(feature = "graphics")]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use embedded_graphics::pixelcolor::{raw::RawU16, BinaryColor, Rgb565, RgbColor};
+use embedded_graphics::prelude::*;
+use ssd1351::builder::Builder;
+use ssd1351::mode::MonoGraphicsMode;
+use ssd1351::properties::DisplaySize;
+
+/// Row address command, followed by the first and last row of the draw area
+const ROW: u8 = 0x75;
+
+/// Draw window, with the rows it covers and the pixel data sent to it
+#[derive(Debug, Default, PartialEq)]
+struct Window {
+    rows: (u8, u8),
+    pixels: Vec<u8>,
+}
+
+/// Draw windows recorded so far, shared with the test
+type Windows = Rc<RefCell<Vec<Window>>>;
+
+/// Interface recording the draw windows set and the pixel data sent to each
+#[derive(Default)]
+struct Recorder {
+    windows: Windows,
+    opcode: u8,
+}
+
+impl WriteOnlyDataCommand for Recorder {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        let DataFormat::U8(bytes) = cmd else {
+            return Err(DisplayError::DataFormatNotImplemented);
+        };
+        self.opcode = *bytes.last().unwrap();
+        Ok(())
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        let DataFormat::U8(bytes) = buf else {
+            return Err(DisplayError::DataFormatNotImplemented);
+        };
+        match self.opcode {
+            ROW => self.windows.borrow_mut().push(Window {
+                rows: (bytes[0], bytes[1]),
+                pixels: Vec::new(),
+            }),
+            0x5C => self
+                .windows
+                .borrow_mut()
+                .last_mut()
+                .unwrap()
+                .pixels
+                .extend(bytes),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+const FB_SIZE: usize = DisplaySize::Display128x128.mono_buffer_size();
+
+/// Monochrome mode drawing red on blue, and the windows its flushes send
+fn mono_mode() -> (MonoGraphicsMode<Recorder, [u8; FB_SIZE]>, Windows) {
+    let recorder = Recorder::default();
+    let windows = recorder.windows.clone();
+    let mut display: MonoGraphicsMode<_, _> = Builder::new()
+        .connect_interface_mono(recorder, [0u8; FB_SIZE])
+        .unwrap()
+        .into();
+    display.set_colors(Rgb565::RED, Rgb565::BLUE);
+    (display, windows)
+}
+
+/// Big endian bytes of `color`
+fn be(color: Rgb565) -> [u8; 2] {
+    RawU16::from(color).into_inner().to_be_bytes()
+}
+
+/// Expected pixel data of a row with the pixels in `on` set
+fn row(on: &[usize]) -> Vec<u8> {
+    (0..128)
+        .flat_map(|x| {
+            be(if on.contains(&x) {
+                Rgb565::RED
+            } else {
+                Rgb565::BLUE
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn first_flush_sends_every_row() {
+    let (mut display, windows) = mono_mode();
+    display.set_pixel(0, 0, true);
+    display.set_pixel(127, 127, true);
+    display.flush().unwrap();
+
+    let windows = windows.take();
+    assert_eq!(windows.len(), 1);
+    assert_eq!(windows[0].rows, (0, 127));
+    assert_eq!(windows[0].pixels.len(), 128 * 128 * 2);
+    assert_eq!(windows[0].pixels[..256], row(&[0]));
+    assert_eq!(windows[0].pixels[127 * 256..], row(&[127]));
+}
+
+#[test]
+fn flush_sends_only_dirty_rows() {
+    let (mut display, windows) = mono_mode();
+    display.flush().unwrap();
+    windows.take();
+
+    // Nothing changed
+    display.flush().unwrap();
+    assert!(windows.borrow().is_empty());
+
+    // Two runs of dirty rows, one of them ending on the last row
+    display.set_pixel(3, 5, true);
+    display.set_pixel(8, 6, true);
+    display.set_pixel(15, 6, true);
+    Pixel(Point::new(127, 127), BinaryColor::On)
+        .draw(&mut display)
+        .unwrap();
+    // Out of bounds, leaving no row dirty
+    display.set_pixel(128, 20, true);
+    display.flush().unwrap();
+
+    assert_eq!(
+        windows.take(),
+        [
+            Window {
+                rows: (5, 6),
+                pixels: [row(&[3]), row(&[8, 15])].concat(),
+            },
+            Window {
+                rows: (127, 127),
+                pixels: row(&[127]),
+            },
+        ]
+    );
+
+    // Turning a pixel off dirties its row too
+    display.set_pixel(3, 5, false);
+    display.flush().unwrap();
+    assert_eq!(
+        windows.take(),
+        [Window {
+            rows: (5, 5),
+            pixels: row(&[]),
+        }]
+    );
+}
+
+#[test]
+fn colour_changes_resend_every_row() {
+    let (mut display, windows) = mono_mode();
+    display.set_pixel(64, 64, true);
+    display.flush().unwrap();
+    windows.take();
+
+    display.set_colors(Rgb565::GREEN, Rgb565::BLACK);
+    display.flush().unwrap();
+    let windows = windows.take();
+    assert_eq!(windows.len(), 1);
+    assert_eq!(windows[0].rows, (0, 127));
+
+    let pixel = (64 * 128 + 64) * 2;
+    assert_eq!(windows[0].pixels[pixel..pixel + 2], be(Rgb565::GREEN));
+    assert_eq!(windows[0].pixels[pixel + 2..pixel + 4], be(Rgb565::BLACK));
+    assert_eq!(windows[0].pixels[..2], be(Rgb565::BLACK));
+}
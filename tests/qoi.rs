@@ -0,0 +1,168 @@
+//! Checks the QOI decoder handles every op, and reports truncated or corrupt images as errors
+//! instead of panicking
+
+#![cfg(feature = "images")]
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use embedded_graphics::prelude::*;
+use ssd1351::builder::Builder;
+use ssd1351::mode::GraphicsMode;
+use ssd1351::qoi::QoiDecoder;
+
+/// Interface accepting everything
+struct Sink;
+
+impl WriteOnlyDataCommand for Sink {
+    fn send_commands(&mut self, _: DataFormat<'_>) -> Result<(), DisplayError> {
+        Ok(())
+    }
+
+    fn send_data(&mut self, _: DataFormat<'_>) -> Result<(), DisplayError> {
+        Ok(())
+    }
+}
+
+const END: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 1];
+
+/// Header of a `width` by `height` image with `channels` channels
+fn header(width: u32, height: u32, channels: u8) -> Vec<u8> {
+    let mut header = b"qoif".to_vec();
+    header.extend(width.to_be_bytes());
+    header.extend(height.to_be_bytes());
+    header.extend([channels, 0]);
+    header
+}
+
+/// 7 by 1 pixels using every op once, with the expected pixels as RGB565
+fn every_op() -> (Vec<u8>, [u16; 7]) {
+    let mut image = header(7, 1, 4);
+    image.extend([
+        0xFF, 0x10, 0x20, 0x30, 0x80, // RGBA, stored at index 32
+        0x76, // DIFF, red +1, green -1, blue 0
+        0xA8, 0xA4, // LUMA, green +8, red +10, blue +4
+        0xC1, // RUN of 2
+        0x20, // INDEX 32
+        0xFE, 0xF8, 0xFC, 0xF8, // RGB
+    ]);
+    image.extend(END);
+
+    let first = rgb565(0x10, 0x20, 0x30);
+    let luma = rgb565(0x1B, 0x27, 0x34);
+    let pixels = [
+        first,
+        rgb565(0x11, 0x1F, 0x30),
+        luma,
+        luma,
+        luma,
+        first,
+        rgb565(0xF8, 0xFC, 0xF8),
+    ];
+    (image, pixels)
+}
+
+fn rgb565(r: u8, g: u8, b: u8) -> u16 {
+    (r as u16 & 0xF8) << 8 | (g as u16 & 0xFC) << 3 | b as u16 >> 3
+}
+
+fn decode(data: &[u8]) -> Result<Vec<u16>, DisplayError> {
+    let mut decoder = QoiDecoder::new(data)?;
+    let pixels = decoder.width() as usize * decoder.height() as usize;
+    (0..pixels).map(|_| decoder.next_pixel()).collect()
+}
+
+fn graphics_mode() -> GraphicsMode<Sink> {
+    Builder::new().connect_interface(Sink).into()
+}
+
+#[test]
+fn every_op_is_decoded() {
+    let (image, pixels) = every_op();
+    assert_eq!(decode(&image).unwrap(), pixels);
+
+    // Differences wrap around, starting from opaque black
+    let mut image = header(1, 1, 3);
+    image.push(0x40);
+    image.extend(END);
+    assert_eq!(decode(&image).unwrap(), [rgb565(0xFE, 0xFE, 0xFE)]);
+}
+
+#[test]
+fn read_and_skip_follow_the_pixels() {
+    let (image, pixels) = every_op();
+    let mut decoder = QoiDecoder::new(&image).unwrap();
+    decoder.skip(2).unwrap();
+    let mut bytes = [0u8; 6];
+    decoder.read(&mut bytes).unwrap();
+    let expected: Vec<u8> = pixels[2..5].iter().flat_map(|p| p.to_be_bytes()).collect();
+    assert_eq!(bytes[..], expected);
+
+    // Past the last pixel
+    decoder.skip(2).unwrap();
+    assert!(matches!(
+        decoder.next_pixel(),
+        Err(DisplayError::InvalidFormatError)
+    ));
+}
+
+#[test]
+fn corrupt_headers_are_rejected() {
+    let (image, _) = every_op();
+    for len in 0..14 {
+        assert!(matches!(
+            QoiDecoder::new(&image[..len]),
+            Err(DisplayError::InvalidFormatError)
+        ));
+    }
+
+    let mut magic = image.clone();
+    magic[0] = b'Q';
+    let mut channels = image.clone();
+    channels[12] = 5;
+    for image in [magic, channels] {
+        assert!(matches!(
+            QoiDecoder::new(&image),
+            Err(DisplayError::InvalidFormatError)
+        ));
+    }
+}
+
+#[test]
+fn truncated_images_are_errors() {
+    let (image, _) = every_op();
+    // Cut anywhere in the ops, the end marker isn't needed
+    for len in 14..image.len() - END.len() {
+        assert!(matches!(
+            decode(&image[..len]),
+            Err(DisplayError::InvalidFormatError)
+        ));
+    }
+    assert!(decode(&image[..image.len() - END.len()]).is_ok());
+
+    let mut display = graphics_mode();
+    for len in 0..image.len() - END.len() {
+        assert!(matches!(
+            display.draw_qoi(&image[..len], Point::zero()),
+            Err(DisplayError::InvalidFormatError)
+        ));
+    }
+    display.draw_qoi(&image, Point::new(125, 127)).unwrap();
+}
+
+#[test]
+fn oversized_images_are_errors() {
+    // Claims far more pixels than the data holds
+    let mut image = header(u32::MAX, u32::MAX, 4);
+    image.extend([0xFE, 0xFF, 0x00, 0x00, 0xFD]);
+    image.extend(END);
+
+    let mut decoder = QoiDecoder::new(&image).unwrap();
+    assert!(decoder.skip(1000).is_err());
+
+    let mut display = graphics_mode();
+    for top_left in [Point::zero(), Point::new(-1000, -1000)] {
+        assert!(matches!(
+            display.draw_qoi(&image, top_left),
+            Err(DisplayError::InvalidFormatError)
+        ));
+    }
+}
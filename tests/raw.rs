@@ -0,0 +1,266 @@
+//! Checks raw pixel data is clipped to the display, keyed pixels are left transparent and data of
+//! the wrong length is rejected before anything is drawn
+
+#![cfg(feature = "graphics")]
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use embedded_graphics::pixelcolor::{Rgb565, RgbColor};
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+use ssd1351::builder::Builder;
+use ssd1351::mode::displaymode::DisplayModeTrait;
+use ssd1351::mode::{BufferedGraphicsMode, GraphicsMode};
+use ssd1351::properties::DisplaySize;
+
+/// Column address command
+const COLUMN: u8 = 0x15;
+/// Row address command
+const ROW: u8 = 0x75;
+/// Write RAM command
+const WRITE_RAM: u8 = 0x5C;
+
+/// Colour keyed out in the tests
+const KEY: u16 = 0xF81F;
+
+/// Interface simulating the display RAM, written through draw windows in the default rotation
+struct Panel {
+    ram: Vec<Option<u16>>,
+    opcode: u8,
+    columns: (u8, u8),
+    rows: (u8, u8),
+    pointer: (u8, u8),
+    commands: usize,
+}
+
+impl Default for Panel {
+    fn default() -> Self {
+        Panel {
+            ram: vec![None; 128 * 128],
+            opcode: 0,
+            columns: (0, 127),
+            rows: (0, 127),
+            pointer: (0, 0),
+            commands: 0,
+        }
+    }
+}
+
+impl WriteOnlyDataCommand for Panel {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        let DataFormat::U8(bytes) = cmd else {
+            return Err(DisplayError::DataFormatNotImplemented);
+        };
+        self.opcode = *bytes.last().unwrap();
+        self.commands += bytes.len();
+        Ok(())
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        let DataFormat::U8(bytes) = buf else {
+            return Err(DisplayError::DataFormatNotImplemented);
+        };
+        match self.opcode {
+            COLUMN => self.columns = (bytes[0], bytes[1]),
+            ROW => {
+                self.rows = (bytes[0], bytes[1]);
+                self.pointer = (self.columns.0, self.rows.0);
+            }
+            WRITE_RAM => {
+                for pixel in bytes.chunks_exact(2) {
+                    let (x, y) = self.pointer;
+                    self.ram[y as usize * 128 + x as usize] =
+                        Some(u16::from_be_bytes([pixel[0], pixel[1]]));
+                    self.pointer = if x < self.columns.1 {
+                        (x + 1, y)
+                    } else if y < self.rows.1 {
+                        (self.columns.0, y + 1)
+                    } else {
+                        (self.columns.0, self.rows.0)
+                    };
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Panel {
+    /// Pixels written, with their position
+    fn written(&self) -> Vec<((usize, usize), u16)> {
+        self.ram
+            .iter()
+            .enumerate()
+            .filter_map(|(i, pixel)| pixel.map(|pixel| ((i % 128, i / 128), pixel)))
+            .collect()
+    }
+}
+
+/// 4 by 3 pixels numbered row by row from 1, as big endian bytes and as `u16`
+fn pixels() -> (Vec<u8>, Vec<u16>) {
+    let pixels: Vec<u16> = (1..=12).collect();
+    let bytes = pixels
+        .iter()
+        .flat_map(|pixel| pixel.to_be_bytes())
+        .collect();
+    (bytes, pixels)
+}
+
+/// 4 by 2 pixels with the key colour in between
+fn keyed() -> Vec<u16> {
+    vec![1, KEY, 2, 3, KEY, KEY, 4, KEY]
+}
+
+fn graphics_mode() -> GraphicsMode<Panel> {
+    Builder::new().connect_interface(Panel::default()).into()
+}
+
+fn panel(display: GraphicsMode<Panel>) -> Panel {
+    display.release().0.release()
+}
+
+fn buffered_mode() -> BufferedGraphicsMode<Panel, Vec<u8>> {
+    Builder::new()
+        .connect_interface_buffered(
+            Panel::default(),
+            vec![0u8; DisplaySize::Display128x128.buffer_size()],
+        )
+        .unwrap()
+        .into()
+}
+
+/// Colour of the framebuffer pixel at `x`, `y`
+fn fb_pixel(display: &BufferedGraphicsMode<Panel, Vec<u8>>, x: usize, y: usize) -> u16 {
+    let idx = (y * 128 + x) * 2;
+    u16::from_be_bytes([display.fb()[idx], display.fb()[idx + 1]])
+}
+
+#[test]
+fn direct_draws_are_clipped() {
+    let (bytes, words) = pixels();
+    let area = Rectangle::new(Point::new(-1, 126), Size::new(4, 3));
+    let expected = [
+        ((0, 126), 2),
+        ((1, 126), 3),
+        ((2, 126), 4),
+        ((0, 127), 6),
+        ((1, 127), 7),
+        ((2, 127), 8),
+    ];
+
+    let mut display = graphics_mode();
+    display.draw_raw(&area, &bytes).unwrap();
+    assert_eq!(panel(display).written(), expected);
+
+    let mut display = graphics_mode();
+    display.draw_raw_u16(&area, &words).unwrap();
+    assert_eq!(panel(display).written(), expected);
+
+    // Entirely off the display
+    let mut display = graphics_mode();
+    let area = Rectangle::new(Point::new(128, 0), Size::new(4, 3));
+    display.draw_raw(&area, &bytes).unwrap();
+    let panel = panel(display);
+    assert!(panel.written().is_empty());
+    assert_eq!(panel.commands, 0);
+}
+
+#[test]
+fn direct_draws_skip_the_key_colour() {
+    let words = keyed();
+    let bytes: Vec<u8> = words.iter().flat_map(|pixel| pixel.to_be_bytes()).collect();
+    let key = Rgb565::MAGENTA;
+
+    // Each run of opaque pixels lands where it belongs
+    let area = Rectangle::new(Point::new(10, 20), Size::new(4, 2));
+    let expected = [((10, 20), 1), ((12, 20), 2), ((13, 20), 3), ((12, 21), 4)];
+    let mut display = graphics_mode();
+    display.draw_raw_keyed(&area, &bytes, key).unwrap();
+    assert_eq!(panel(display).written(), expected);
+
+    let mut display = graphics_mode();
+    display.draw_raw_u16_keyed(&area, &words, key).unwrap();
+    assert_eq!(panel(display).written(), expected);
+
+    // Clipped at the right edge
+    let mut display = graphics_mode();
+    let area = Rectangle::new(Point::new(126, 10), Size::new(4, 2));
+    display.draw_raw_u16_keyed(&area, &words, key).unwrap();
+    assert_eq!(panel(display).written(), [((126, 10), 1)]);
+}
+
+#[test]
+fn buffered_draws_are_clipped_and_keyed() {
+    let (bytes, _) = pixels();
+    let mut display = buffered_mode();
+    display
+        .draw_raw(
+            &Rectangle::new(Point::new(125, -1), Size::new(4, 3)),
+            &bytes,
+        )
+        .unwrap();
+    assert_eq!(fb_pixel(&display, 125, 0), 5);
+    assert_eq!(fb_pixel(&display, 127, 1), 11);
+    assert_eq!(fb_pixel(&display, 0, 1), 0);
+    assert_eq!(fb_pixel(&display, 0, 2), 0);
+
+    // Keyed pixels keep what was drawn before
+    let area = Rectangle::new(Point::new(124, 0), Size::new(4, 2));
+    display
+        .draw_raw_u16_keyed(&area, &keyed(), Rgb565::MAGENTA)
+        .unwrap();
+    let row: Vec<_> = (124..128).map(|x| fb_pixel(&display, x, 0)).collect();
+    assert_eq!(row, [1, 5, 2, 3]);
+    let row: Vec<_> = (124..128).map(|x| fb_pixel(&display, x, 1)).collect();
+    assert_eq!(row, [0, 9, 4, 11]);
+
+    // Only the flush reaches the panel
+    display.flush().unwrap();
+    let panel = display.release().0.release();
+    assert_eq!(panel.written().len(), 128 * 128);
+    assert_eq!(panel.ram[128 + 125], Some(9));
+}
+
+#[test]
+fn wrong_lengths_are_rejected() {
+    let (bytes, words) = pixels();
+    let area = Rectangle::new(Point::new(10, 10), Size::new(4, 3));
+    let short = Rectangle::new(Point::new(10, 10), Size::new(4, 2));
+    let wide = Rectangle::new(Point::new(10, 10), Size::new(u32::MAX, 3));
+    let key = Rgb565::MAGENTA;
+
+    let mut display = graphics_mode();
+    for area in [short, wide] {
+        let results = [
+            display.draw_raw(&area, &bytes),
+            display.draw_raw_u16(&area, &words),
+            display.draw_raw_keyed(&area, &bytes, key),
+            display.draw_raw_u16_keyed(&area, &words, key),
+        ];
+        for result in results {
+            assert!(matches!(result, Err(DisplayError::OutOfBoundsError)));
+        }
+    }
+    // Half a pixel
+    assert!(matches!(
+        display.draw_raw(&area, &bytes[..23]),
+        Err(DisplayError::OutOfBoundsError)
+    ));
+    let panel = panel(display);
+    assert!(panel.written().is_empty());
+    assert_eq!(panel.commands, 0);
+
+    let mut display = buffered_mode();
+    for area in [short, wide] {
+        let results = [
+            display.draw_raw(&area, &bytes),
+            display.draw_raw_u16(&area, &words),
+            display.draw_raw_keyed(&area, &bytes, key),
+            display.draw_raw_u16_keyed(&area, &words, key),
+        ];
+        for result in results {
+            assert!(matches!(result, Err(DisplayError::OutOfBoundsError)));
+        }
+    }
+    assert!(display.fb().iter().all(|&byte| byte == 0));
+}
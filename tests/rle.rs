@@ -0,0 +1,176 @@
+//! Checks run-length encoded images survive a round trip, and malformed data is reported as an
+//! error instead of panicking
+
+#![cfg(feature = "images")]
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use embedded_graphics::prelude::*;
+use ssd1351::builder::Builder;
+use ssd1351::mode::displaymode::DisplayModeTrait;
+use ssd1351::mode::GraphicsMode;
+use ssd1351::rle::{encode, Packet, RleDecoder};
+
+/// Interface recording the pixel data sent
+#[derive(Default)]
+struct Recorder {
+    opcode: u8,
+    pixels: Vec<u8>,
+}
+
+impl WriteOnlyDataCommand for Recorder {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        let DataFormat::U8(bytes) = cmd else {
+            return Err(DisplayError::DataFormatNotImplemented);
+        };
+        self.opcode = *bytes.last().unwrap();
+        Ok(())
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        let DataFormat::U8(bytes) = buf else {
+            return Err(DisplayError::DataFormatNotImplemented);
+        };
+        if self.opcode == 0x5C {
+            self.pixels.extend(bytes);
+        }
+        Ok(())
+    }
+}
+
+fn encoded(width: u16, height: u16, pixels: &[u16]) -> Vec<u8> {
+    let mut data = Vec::new();
+    encode(width, height, pixels, |bytes| data.extend(bytes)).unwrap();
+    data
+}
+
+/// Decode every pixel of `data`
+fn decode(data: &[u8]) -> Result<Vec<u16>, DisplayError> {
+    let mut decoder = RleDecoder::new(data)?;
+    let mut pixels = Vec::new();
+    for _ in 0..decoder.height() {
+        let mut column = 0;
+        while column < decoder.width() as usize {
+            let packet = decoder.next_packet()?;
+            match packet {
+                Packet::Literal(bytes) => pixels.extend(
+                    bytes
+                        .chunks_exact(2)
+                        .map(|pixel| u16::from_be_bytes([pixel[0], pixel[1]])),
+                ),
+                Packet::Run { color, len } => pixels.extend(std::iter::repeat_n(color, len)),
+            }
+            column += packet.len();
+        }
+    }
+    Ok(pixels)
+}
+
+/// 300 by 3 pixels mixing runs and literals longer than a packet
+fn image() -> Vec<u16> {
+    let mut pixels = vec![0xF800; 300];
+    pixels.extend((0..300).map(|x| x as u16));
+    pixels.extend((0..300).map(|x| if x % 150 < 140 { 0x07E0 } else { x }));
+    pixels
+}
+
+fn graphics_mode() -> GraphicsMode<Recorder> {
+    Builder::new().connect_interface(Recorder::default()).into()
+}
+
+#[test]
+fn images_survive_a_round_trip() {
+    let pixels = image();
+    let data = encoded(300, 3, &pixels);
+    assert_eq!(decode(&data).unwrap(), pixels);
+
+    // Runs and literals are split at 128 pixels and at the ends of rows
+    let mut decoder = RleDecoder::new(&data).unwrap();
+    let lens: Vec<_> = (0..6)
+        .map(|_| decoder.next_packet().unwrap().len())
+        .collect();
+    assert_eq!(lens, [128, 128, 44, 128, 128, 44]);
+
+    assert!(matches!(
+        encode(300, 2, &pixels, |_| {}),
+        Err(DisplayError::OutOfBoundsError)
+    ));
+}
+
+#[test]
+fn draws_send_the_visible_pixels() {
+    let pixels: Vec<u16> = (0..4 * 3).collect();
+    let data = encoded(4, 3, &pixels);
+
+    let mut display = graphics_mode();
+    display.draw_rle(&data, Point::new(-1, 126)).unwrap();
+    let sent = display.release().0.release().pixels;
+    let expected: Vec<u8> = [1, 2, 3, 5, 6, 7]
+        .iter()
+        .flat_map(|&pixel: &u16| pixel.to_be_bytes())
+        .collect();
+    assert_eq!(sent, expected);
+}
+
+#[test]
+fn malformed_headers_are_rejected() {
+    let data = encoded(2, 1, &[1, 2]);
+    for len in 0..8 {
+        assert!(matches!(
+            RleDecoder::new(&data[..len]),
+            Err(DisplayError::InvalidFormatError)
+        ));
+    }
+    let mut magic = data.clone();
+    magic[3] = b'6';
+    assert!(matches!(
+        RleDecoder::new(&magic),
+        Err(DisplayError::InvalidFormatError)
+    ));
+
+    // No rows to read
+    let mut decoder = RleDecoder::new(b"R565\0\0\0\x05").unwrap();
+    assert!(matches!(
+        decoder.next_packet(),
+        Err(DisplayError::InvalidFormatError)
+    ));
+}
+
+#[test]
+fn malformed_packets_are_errors() {
+    let header = b"R565\0\x04\0\x02";
+    let malformed: [&[u8]; 4] = [
+        // Literal of 5 pixels in a 4 pixel row
+        &[0x04, 0, 1, 0, 2, 0, 3, 0, 4, 0, 5],
+        // Run spanning two rows
+        &[0x82, 0, 1, 0x84, 0, 2],
+        // Truncated literal
+        &[0x03, 0, 1, 0, 2],
+        // Truncated run
+        &[0x83, 0],
+    ];
+    for packets in malformed {
+        let data = [&header[..], packets].concat();
+        assert!(matches!(
+            decode(&data),
+            Err(DisplayError::InvalidFormatError)
+        ));
+        let mut display = graphics_mode();
+        assert!(matches!(
+            display.draw_rle(&data, Point::zero()),
+            Err(DisplayError::InvalidFormatError)
+        ));
+    }
+
+    // Every truncation of a valid image
+    let data = encoded(300, 3, &image());
+    let mut display = graphics_mode();
+    for len in 0..data.len() {
+        assert!(decode(&data[..len]).is_err());
+        assert!(display.draw_rle(&data[..len], Point::new(-100, 0)).is_err());
+    }
+
+    // Packets past the last row
+    let mut decoder = RleDecoder::new(&data).unwrap();
+    decoder.skip_rows(3).unwrap();
+    assert!(decoder.next_packet().is_err());
+}
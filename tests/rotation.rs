@@ -0,0 +1,512 @@
+//! Checks every pixel of every rotation lands where it should on the panel
+//!
+//! `Panel` models the controller: it tracks the draw window and address increment in display RAM
+//! and maps RAM to the glass using the remap settings, relative to `Rotate0`.
+
+#![cfg(feature = "graphics")]
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use embedded_graphics::pixelcolor::{raw::RawU16, Rgb565};
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+use ssd1351::builder::Builder;
+use ssd1351::canvas::Canvas;
+use ssd1351::display::Display;
+use ssd1351::geometry::{self, Area};
+use ssd1351::mode::displaymode::DisplayModeTrait;
+use ssd1351::mode::GraphicsMode;
+use ssd1351::properties::{DisplayRotation, DisplaySize};
+
+const ROTATIONS: [DisplayRotation; 4] = [
+    DisplayRotation::Rotate0,
+    DisplayRotation::Rotate90,
+    DisplayRotation::Rotate180,
+    DisplayRotation::Rotate270,
+];
+
+const SIZES: [DisplaySize; 2] = [DisplaySize::Display128x128, DisplaySize::Display128x96];
+
+/// Remap bits set for `Rotate0`: reverse COM scan
+const ROTATE0_REMAP: u8 = 0x10;
+
+struct Panel {
+    remap: u8,
+    opcode: u8,
+    /// Number of times RAM writes were started, i.e. draw windows set
+    windows: usize,
+    columns: (u8, u8),
+    rows: (u8, u8),
+    pointer: (u8, u8),
+    high: Option<u8>,
+    ram: Vec<Option<u16>>,
+}
+
+impl Panel {
+    fn new() -> Self {
+        Panel {
+            remap: ROTATE0_REMAP,
+            opcode: 0,
+            windows: 0,
+            columns: (0, 127),
+            rows: (0, 127),
+            pointer: (0, 0),
+            high: None,
+            ram: vec![None; 128 * 128],
+        }
+    }
+
+    /// Glass position of the RAM cell at `column`, `row` on a panel `height` rows tall
+    fn glass(&self, column: u8, row: u8, height: u8) -> (u8, u8) {
+        let changed = self.remap ^ ROTATE0_REMAP;
+        let x = if changed & 0x02 != 0 {
+            127 - column
+        } else {
+            column
+        };
+        let y = if changed & 0x10 != 0 {
+            height - 1 - row
+        } else {
+            row
+        };
+        (x, y)
+    }
+
+    fn write(&mut self, value: u16) {
+        let (x, y) = self.pointer;
+        self.ram[y as usize * 128 + x as usize] = Some(value);
+
+        let vertical = self.remap & 0x01 != 0;
+        let (columns, rows) = (self.columns, self.rows);
+        let next = |pos: u8, (start, end): (u8, u8)| if pos == end { start } else { pos + 1 };
+        self.pointer = match (vertical, x == columns.1, y == rows.1) {
+            (false, false, _) => (x + 1, y),
+            (false, true, _) => (columns.0, next(y, rows)),
+            (true, _, false) => (x, y + 1),
+            (true, _, true) => (next(x, columns), rows.0),
+        };
+    }
+}
+
+impl WriteOnlyDataCommand for Panel {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        if let DataFormat::U8(bytes) = cmd {
+            // Commands without parameters are sent together, only the last one takes data
+            self.opcode = *bytes.last().unwrap();
+            if self.opcode == 0x5C {
+                self.windows += 1;
+                self.pointer = (self.columns.0, self.rows.0);
+                self.high = None;
+            }
+        }
+        Ok(())
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        let DataFormat::U8(bytes) = buf else {
+            panic!("unexpected data format");
+        };
+        match self.opcode {
+            0x15 => self.columns = (bytes[0], bytes[1]),
+            0x75 => self.rows = (bytes[0], bytes[1]),
+            0xA0 => self.remap = bytes[0] & 0x13,
+            0x5C => {
+                for &byte in bytes {
+                    match self.high.take() {
+                        None => self.high = Some(byte),
+                        Some(high) => self.write(u16::from_be_bytes([high, byte])),
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Glass position of the logical pixel at `x`, `y` when the display is rotated clockwise
+fn rotated(x: u8, y: u8, size: DisplaySize, rotation: DisplayRotation) -> (u8, u8) {
+    let (width, height) = size.dimensions();
+    match rotation {
+        DisplayRotation::Rotate0 => (x, y),
+        DisplayRotation::Rotate90 => (width - 1 - y, x),
+        DisplayRotation::Rotate180 => (width - 1 - x, height - 1 - y),
+        DisplayRotation::Rotate270 => (y, height - 1 - x),
+    }
+}
+
+fn degrees(rotation: DisplayRotation) -> u32 {
+    match rotation {
+        DisplayRotation::Rotate0 => 0,
+        DisplayRotation::Rotate90 => 90,
+        DisplayRotation::Rotate180 => 180,
+        DisplayRotation::Rotate270 => 270,
+    }
+}
+
+/// Colour encoding the logical position of a pixel
+fn color(x: u8, y: u8) -> u16 {
+    u16::from_be_bytes([x, y])
+}
+
+fn graphics_mode(size: DisplaySize, rotation: DisplayRotation) -> GraphicsMode<Panel> {
+    let mut display: GraphicsMode<_> = Builder::new()
+        .with_size(size)
+        .connect_interface(Panel::new())
+        .into();
+    display.set_rotation(rotation).unwrap();
+    display
+}
+
+/// Colours shown on the glass, row by row
+fn glass(panel: &Panel, size: DisplaySize) -> Vec<Option<u16>> {
+    let (width, height) = size.dimensions();
+    let mut glass = vec![None; width as usize * height as usize];
+    for row in 0..height {
+        for column in 0..width {
+            let (x, y) = panel.glass(column, row, height);
+            glass[y as usize * width as usize + x as usize] =
+                panel.ram[row as usize * 128 + column as usize];
+        }
+    }
+    glass
+}
+
+/// Check every pixel on the glass shows the colour of the logical pixel rotated onto it
+fn check_glass(panel: Panel, size: DisplaySize, rotation: DisplayRotation) {
+    let (width, height) = size.dimensions();
+    let glass = glass(&panel, size);
+
+    let (logical_width, logical_height) = match rotation {
+        DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (width, height),
+        DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => (height, width),
+    };
+    for y in 0..logical_height {
+        for x in 0..logical_width {
+            let (gx, gy) = rotated(x, y, size, rotation);
+            assert_eq!(
+                glass[gy as usize * width as usize + gx as usize],
+                Some(color(x, y)),
+                "pixel ({}, {}) rotated by {} on {:?}",
+                x,
+                y,
+                degrees(rotation),
+                size.dimensions()
+            );
+        }
+    }
+}
+
+fn logical_pixels(size: DisplaySize, rotation: DisplayRotation) -> impl Iterator<Item = (u8, u8)> {
+    let (width, height) = match rotation {
+        DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => size.dimensions(),
+        DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => {
+            let (w, h) = size.dimensions();
+            (h, w)
+        }
+    };
+    (0..height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+}
+
+#[test]
+fn set_pixel_rotates_every_pixel() {
+    for size in SIZES {
+        for rotation in ROTATIONS {
+            let mut display = graphics_mode(size, rotation);
+            for (x, y) in logical_pixels(size, rotation) {
+                display.set_pixel(x as u32, y as u32, color(x, y));
+            }
+            check_glass(display.release().0.release(), size, rotation);
+        }
+    }
+}
+
+#[test]
+fn draw_iter_rotates_every_pixel() {
+    for size in SIZES {
+        for rotation in ROTATIONS {
+            let mut display = graphics_mode(size, rotation);
+            let pixels = logical_pixels(size, rotation).map(|(x, y)| {
+                Pixel(
+                    Point::new(x as i32, y as i32),
+                    Rgb565::from(RawU16::new(color(x, y))),
+                )
+            });
+            display.draw_iter(pixels).unwrap();
+            check_glass(display.release().0.release(), size, rotation);
+        }
+    }
+}
+
+#[test]
+fn fill_contiguous_rotates_every_pixel() {
+    for size in SIZES {
+        for rotation in ROTATIONS {
+            let mut display = graphics_mode(size, rotation);
+            let area = display.bounding_box();
+            let colors =
+                logical_pixels(size, rotation).map(|(x, y)| Rgb565::from(RawU16::new(color(x, y))));
+            display.fill_contiguous(&area, colors).unwrap();
+            check_glass(display.release().0.release(), size, rotation);
+        }
+    }
+}
+
+/// Canvas with every pixel coloured by its position on the canvas
+fn canvas() -> Canvas<32, 16> {
+    let mut canvas = Canvas::new();
+    for y in 0..16 {
+        for x in 0..32 {
+            canvas.set_pixel(x as u32, y as u32, color(x, y));
+        }
+    }
+    canvas
+}
+
+/// Positions of the canvas partly off the top left and the bottom right of a display rotated by
+/// `rotation`, and fully off it, along with the draw windows each blit should set
+fn blit_positions(size: DisplaySize, rotation: DisplayRotation) -> [(Point, usize); 3] {
+    let (width, height) = match rotation {
+        DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => size.dimensions(),
+        DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => {
+            let (w, h) = size.dimensions();
+            (h, w)
+        }
+    };
+    [
+        (Point::new(-8, -4), 1),
+        (Point::new(width as i32 - 20, height as i32 - 10), 1),
+        (Point::new(-32, 0), 0),
+    ]
+}
+
+/// Check the glass shows the visible part of `canvas()` blitted at `top_left`, and nothing else
+fn check_blit(panel: &Panel, size: DisplaySize, rotation: DisplayRotation, top_left: Point) {
+    let (width, _) = size.dimensions();
+    let glass = glass(panel, size);
+    for (x, y) in logical_pixels(size, rotation) {
+        let (cx, cy) = (x as i32 - top_left.x, y as i32 - top_left.y);
+        let expected =
+            ((0..32).contains(&cx) && (0..16).contains(&cy)).then(|| color(cx as u8, cy as u8));
+        let (gx, gy) = rotated(x, y, size, rotation);
+        assert_eq!(
+            glass[gy as usize * width as usize + gx as usize],
+            expected,
+            "pixel ({}, {}) of a canvas at {:?} rotated by {} on {:?}",
+            x,
+            y,
+            top_left,
+            degrees(rotation),
+            size.dimensions()
+        );
+    }
+}
+
+#[test]
+fn blit_clips_and_rotates_canvases() {
+    let canvas = canvas();
+    for size in SIZES {
+        for rotation in ROTATIONS {
+            for (top_left, windows) in blit_positions(size, rotation) {
+                let mut display = graphics_mode(size, rotation);
+                display.blit(&canvas, top_left).unwrap();
+
+                let panel = display.release().0.release();
+                assert_eq!(panel.windows, windows);
+                check_blit(&panel, size, rotation, top_left);
+            }
+        }
+    }
+}
+
+#[test]
+fn size_follows_rotation() {
+    for size in SIZES {
+        for rotation in ROTATIONS {
+            let display = graphics_mode(size, rotation);
+            let (width, height) = display.get_dimensions();
+            assert_eq!(display.size(), Size::new(width as u32, height as u32));
+        }
+    }
+}
+
+#[test]
+fn out_of_range_pixels_are_skipped() {
+    for size in SIZES {
+        for rotation in ROTATIONS {
+            let mut display = graphics_mode(size, rotation);
+            let (width, height) = display.get_dimensions();
+            // Would wrap to column 44 if truncated to u8
+            display.set_pixel(300, 0, 0xFFFF);
+            display.set_pixel(width as u32, 0, 0xFFFF);
+            display.set_pixel(0, height as u32, 0xFFFF);
+            display.set_pixel(u32::MAX, u32::MAX, 0xFFFF);
+            display
+                .draw_iter([
+                    Pixel(Point::new(-1, 0), Rgb565::WHITE),
+                    Pixel(Point::new(0, height as i32), Rgb565::WHITE),
+                    Pixel(Point::new(width as i32, 0), Rgb565::WHITE),
+                ])
+                .unwrap();
+            display
+                .fill_solid(
+                    &Rectangle::new(Point::new(width as i32, 0), Size::new(10, 10)),
+                    Rgb565::WHITE,
+                )
+                .unwrap();
+
+            let panel = display.release().0.release();
+            assert!(panel.ram.iter().all(Option::is_none));
+        }
+    }
+}
+
+#[test]
+fn logical_points_round_trip() {
+    for size in SIZES {
+        for rotation in ROTATIONS {
+            let (width, height) = size.dimensions();
+            for (x, y) in logical_pixels(size, rotation) {
+                let point =
+                    geometry::Point::from_logical(x as u32, y as u32, size, rotation).unwrap();
+                assert!(point.x() < width && point.y() < height);
+                assert_eq!(point.to_logical(rotation), (x as u32, y as u32));
+            }
+        }
+    }
+}
+
+#[test]
+fn logical_areas_are_checked() {
+    for size in SIZES {
+        for rotation in ROTATIONS {
+            let (width, height) = match rotation {
+                DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => size.dimensions(),
+                DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => {
+                    let (w, h) = size.dimensions();
+                    (h, w)
+                }
+            };
+            let (width, height) = (width as u32, height as u32);
+
+            let full = Area::from_logical(0, 0, width, height, size, rotation).unwrap();
+            assert_eq!(full, Area::panel(size));
+
+            for area in [
+                (0, 0, 0, 1),
+                (0, 0, 1, 0),
+                (0, 0, width + 1, 1),
+                (0, 0, 1, height + 1),
+                (width, 0, 1, 1),
+                (1, 0, u32::MAX, 1),
+            ] {
+                let (x, y, w, h) = area;
+                assert!(
+                    Area::from_logical(x, y, w, h, size, rotation).is_err(),
+                    "{:?}",
+                    area
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn empty_draw_areas_are_rejected() {
+    let mut display = Display::new(
+        Panel::new(),
+        DisplaySize::Display128x128,
+        DisplayRotation::Rotate0,
+    );
+    assert!(display.set_draw_area((0, 0), (0, 0)).is_err());
+    assert!(display.set_draw_area((5, 5), (4, 10)).is_err());
+    assert!(display.set_draw_area((0, 0), (129, 1)).is_err());
+    assert!(display.set_draw_area((0, 0), (128, 128)).is_ok());
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn async_mode_rotates_every_pixel() {
+    use ssd1351::async_builder::AsyncBuilder;
+    use ssd1351::async_interface::BlockingAdapter;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    for size in SIZES {
+        for rotation in ROTATIONS {
+            let mut display = AsyncBuilder::new()
+                .with_size(size)
+                .connect_interface(BlockingAdapter::new(Panel::new()));
+            block_on(async {
+                display.set_rotation(rotation).await.unwrap();
+                display.set_pixel(300, 0, 0xFFFF).await.unwrap();
+                // Every other pixel with set_pixel, the rest as a run
+                let (set, drawn): (Vec<_>, Vec<_>) =
+                    logical_pixels(size, rotation).partition(|(x, y)| (x + y) % 2 == 0);
+                for (x, y) in set {
+                    display
+                        .set_pixel(x as u32, y as u32, color(x, y))
+                        .await
+                        .unwrap();
+                }
+                let pixels = drawn.into_iter().map(|(x, y)| {
+                    Pixel(
+                        Point::new(x as i32, y as i32),
+                        Rgb565::from(RawU16::new(color(x, y))),
+                    )
+                });
+                display.draw_iter_async(pixels).await.unwrap();
+            });
+            check_glass(display.release().release().release(), size, rotation);
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn async_blit_clips_and_rotates_canvases() {
+    use ssd1351::async_builder::AsyncBuilder;
+    use ssd1351::async_interface::BlockingAdapter;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    let canvas = canvas();
+    for size in SIZES {
+        for rotation in ROTATIONS {
+            for (top_left, windows) in blit_positions(size, rotation) {
+                let mut display = AsyncBuilder::new()
+                    .with_size(size)
+                    .connect_interface(BlockingAdapter::new(Panel::new()));
+                block_on(async {
+                    display.set_rotation(rotation).await.unwrap();
+                    display.blit(&canvas, top_left).await.unwrap();
+                });
+
+                let panel = display.release().release().release();
+                assert_eq!(panel.windows, windows);
+                check_blit(&panel, size, rotation, top_left);
+            }
+        }
+    }
+}
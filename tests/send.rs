@@ -0,0 +1,171 @@
+//! Checks the async modes return `Send` futures for `Send` interfaces
+//!
+//! The generic functions only compile if the futures are `Send` for any interface implementing
+//! `SendAsyncWriteOnlyDataCommand`, the tests then drive displays from other threads.
+
+#![cfg(all(feature = "async", feature = "graphics"))]
+
+use std::future::Future;
+use std::pin::pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use embedded_graphics::pixelcolor::{Rgb565, RgbColor};
+use embedded_graphics::prelude::{Point, Size};
+use embedded_graphics::primitives::Rectangle;
+use ssd1351::async_builder::AsyncBuilder;
+use ssd1351::async_display::AsyncDisplay;
+use ssd1351::async_interface::{BlockingAdapter, SendAsyncWriteOnlyDataCommand};
+use ssd1351::mode::{AsyncBufferedGraphicsMode, AsyncGraphicsMode};
+
+/// Interface recording the data bytes sent
+#[derive(Clone, Default)]
+struct Recorder {
+    data: Arc<Mutex<Vec<u8>>>,
+}
+
+impl WriteOnlyDataCommand for Recorder {
+    fn send_commands(&mut self, _: DataFormat<'_>) -> Result<(), DisplayError> {
+        Ok(())
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        match buf {
+            DataFormat::U8(bytes) => self.data.lock().unwrap().extend_from_slice(bytes),
+            _ => return Err(DisplayError::DataFormatNotImplemented),
+        }
+        Ok(())
+    }
+}
+
+fn assert_send<T: Send>(_: T) {}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+#[allow(dead_code)]
+fn display_futures_are_send<DI: SendAsyncWriteOnlyDataCommand + Send>(
+    display: &mut AsyncDisplay<DI>,
+) {
+    assert_send(display.init());
+    assert_send(display.clear());
+    assert_send(display.set_draw_area((0, 0), (8, 8)));
+    assert_send(display.draw(&[0; 2]));
+}
+
+#[allow(dead_code)]
+fn graphics_futures_are_send<DI: SendAsyncWriteOnlyDataCommand + Send>(
+    display: &mut AsyncGraphicsMode<DI>,
+) {
+    let area = Rectangle::new(Point::zero(), Size::new(2, 1));
+    assert_send(display.init());
+    assert_send(display.clear());
+    assert_send(display.set_pixel(0, 0, 0xFFFF));
+    assert_send(display.fill_solid_async(&area, Rgb565::RED));
+    assert_send(display.fill_contiguous_async(&area, [Rgb565::RED; 2]));
+    assert_send(display.draw_raw(&area, &[0; 4]));
+}
+
+#[allow(dead_code)]
+fn buffered_futures_are_send<DI: SendAsyncWriteOnlyDataCommand + Send>(
+    display: &mut AsyncBufferedGraphicsMode<DI, [u8; 32768]>,
+) {
+    assert_send(display.init());
+    assert_send(display.flush());
+    assert_send(display.flush_chunked(1024));
+}
+
+/// A task owning a display across `.await`s, as spawned on a multithreaded executor
+async fn task<DI: SendAsyncWriteOnlyDataCommand + Send>(mut display: AsyncGraphicsMode<DI>) {
+    display.init().await.unwrap();
+    display
+        .fill_solid_async(
+            &Rectangle::new(Point::zero(), Size::new(2, 1)),
+            Rgb565::WHITE,
+        )
+        .await
+        .unwrap();
+}
+
+#[test]
+fn display_runs_on_another_thread() {
+    let recorder = Recorder::default();
+    let display = AsyncBuilder::new().connect_interface(BlockingAdapter::new(recorder.clone()));
+
+    thread::spawn(move || block_on(task(display)))
+        .join()
+        .unwrap();
+
+    assert!(recorder.data.lock().unwrap().ends_with(&[0xFF; 4]));
+}
+
+#[test]
+fn yielding_adapter_yields_between_chunks() {
+    let recorder = Recorder::default();
+    let mut display: AsyncGraphicsMode<_> =
+        AsyncBuilder::new().connect_interface(BlockingAdapter::yielding(recorder.clone(), 3));
+    let pixels = [0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0];
+    let area = Rectangle::new(Point::zero(), Size::new(4, 1));
+
+    let mut polls = 1;
+    {
+        let mut draw = pin!(display.draw_raw(&area, &pixels));
+        let mut cx = Context::from_waker(Waker::noop());
+        while draw.as_mut().poll(&mut cx).is_pending() {
+            polls += 1;
+        }
+    }
+
+    // 8 bytes in chunks of 3, yielding twice
+    assert_eq!(polls, 3);
+    assert!(recorder.data.lock().unwrap().ends_with(&pixels));
+
+    // Futures are Send when the wrapped interface is
+    thread::spawn(move || {
+        block_on(async move {
+            display.init().await.unwrap();
+            display
+                .fill_solid_async(&area, Rgb565::WHITE)
+                .await
+                .unwrap();
+        })
+    })
+    .join()
+    .unwrap();
+    assert!(recorder.data.lock().unwrap().ends_with(&[0xFF; 4]));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn boxed_interfaces_share_a_type() {
+    use ssd1351::async_interface::DynSendInterface;
+
+    let recorders = [Recorder::default(), Recorder::default()];
+    let displays: Vec<AsyncGraphicsMode<DynSendInterface<'static>>> = recorders
+        .iter()
+        .map(|recorder| {
+            AsyncBuilder::new().connect_interface(DynSendInterface::new(BlockingAdapter::new(
+                recorder.clone(),
+            )))
+        })
+        .collect();
+
+    for display in displays {
+        thread::spawn(move || block_on(task(display)))
+            .join()
+            .unwrap();
+    }
+
+    for recorder in &recorders {
+        assert!(recorder.data.lock().unwrap().ends_with(&[0xFF; 4]));
+    }
+}
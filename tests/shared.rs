@@ -0,0 +1,151 @@
+//! Checks regions of a shared display clip and translate what they draw, and flushes send the
+//! rows they changed
+
+#![cfg(all(feature = "embassy", feature = "graphics"))]
+
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+use embedded_graphics::pixelcolor::{Rgb565, RgbColor};
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{PrimitiveStyle, Rectangle};
+use ssd1351::async_builder::AsyncBuilder;
+use ssd1351::async_interface::BlockingAdapter;
+use ssd1351::async_shared::SharedDisplay;
+
+/// Row address command, followed by the first and last row of the draw area
+const ROW: u8 = 0x75;
+/// Write RAM command, ending every draw area
+const WRITE_RAM: u8 = 0x5C;
+
+/// A transfer over the interface
+#[derive(Debug, PartialEq)]
+enum Sent {
+    Commands(Vec<u8>),
+    Data(Vec<u8>),
+}
+
+/// Interface recording every transfer
+#[derive(Default)]
+struct Recorder {
+    sent: Vec<Sent>,
+}
+
+impl WriteOnlyDataCommand for Recorder {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        match cmd {
+            DataFormat::U8(bytes) => self.sent.push(Sent::Commands(bytes.to_vec())),
+            _ => return Err(DisplayError::DataFormatNotImplemented),
+        }
+        Ok(())
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        match buf {
+            DataFormat::U8(bytes) => self.sent.push(Sent::Data(bytes.to_vec())),
+            _ => return Err(DisplayError::DataFormatNotImplemented),
+        }
+        Ok(())
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+/// Raw colour of the pixel at `x`, `y` in a 128 pixel wide framebuffer
+fn pixel(fb: &[u8], x: usize, y: usize) -> u16 {
+    let idx = (y * 128 + x) * 2;
+    u16::from_be_bytes([fb[idx], fb[idx + 1]])
+}
+
+#[test]
+fn regions_clip_and_translate() {
+    let display = AsyncBuilder::new()
+        .connect_interface_buffered(BlockingAdapter::new(Recorder::default()), [0u8; 32768])
+        .unwrap();
+    let shared = SharedDisplay::<NoopRawMutex, _, _>::new(display);
+    let header = shared.region(Rectangle::new(Point::zero(), Size::new(128, 16)));
+    let footer = shared.region(Rectangle::new(Point::new(120, 100), Size::new(16, 10)));
+
+    block_on(async {
+        let mut target = header.lock().await;
+        Pixel(Point::new(3, 2), Rgb565::RED)
+            .draw(&mut target)
+            .unwrap();
+        // Outside the region
+        Pixel(Point::new(3, 16), Rgb565::RED)
+            .draw(&mut target)
+            .unwrap();
+        drop(target);
+
+        let mut target = footer.lock().await;
+        assert_eq!(target.bounding_box().size, Size::new(16, 10));
+        target.clear(Rgb565::BLUE).unwrap();
+    });
+
+    let fb = shared.into_inner().fb().to_vec();
+    assert_eq!(pixel(&fb, 3, 2), 0xF800);
+    assert_eq!(pixel(&fb, 3, 16), 0);
+    assert_eq!(pixel(&fb, 120, 100), 0x001F);
+    assert_eq!(pixel(&fb, 127, 109), 0x001F);
+    assert_eq!(pixel(&fb, 119, 100), 0);
+    assert_eq!(pixel(&fb, 120, 110), 0);
+}
+
+#[test]
+fn flush_sends_the_changed_rows() {
+    let display = AsyncBuilder::new()
+        .connect_interface_buffered(BlockingAdapter::new(Recorder::default()), [0u8; 32768])
+        .unwrap();
+    let shared = SharedDisplay::<NoopRawMutex, _, _>::new(display);
+    let top = shared.region(Rectangle::new(Point::new(0, 10), Size::new(64, 10)));
+    let bottom = shared.region(Rectangle::new(Point::new(64, 30), Size::new(64, 10)));
+
+    block_on(async {
+        // Nothing to send yet
+        shared.flush().await.unwrap();
+
+        Rectangle::new(Point::new(0, 2), Size::new(4, 2))
+            .into_styled(PrimitiveStyle::with_fill(Rgb565::GREEN))
+            .draw(&mut top.lock().await)
+            .unwrap();
+        Pixel(Point::new(0, 5), Rgb565::WHITE)
+            .draw(&mut bottom.lock().await)
+            .unwrap();
+
+        // Both regions drew, an owner task waiting for changes wakes up
+        shared.wait_for_changes().await;
+        bottom.flush().await.unwrap();
+        shared.flush().await.unwrap();
+    });
+
+    let (display, buffer) = shared.into_inner().release();
+    let sent = display.release().release().sent;
+
+    // One draw area covering rows 12 to 35, followed by those rows
+    let areas: Vec<_> = sent
+        .iter()
+        .enumerate()
+        .filter(|(_, sent)| **sent == Sent::Commands(vec![WRITE_RAM]))
+        .map(|(i, _)| i)
+        .collect();
+    assert_eq!(areas.len(), 1);
+    let row = sent
+        .iter()
+        .position(|sent| *sent == Sent::Commands(vec![ROW]));
+    assert_eq!(sent[row.unwrap() + 1], Sent::Data(vec![12, 35]));
+    assert_eq!(
+        sent[areas[0] + 1..],
+        [Sent::Data(buffer[12 * 256..36 * 256].to_vec())]
+    );
+}
@@ -0,0 +1,228 @@
+//! Checks the terminal wraps and breaks lines, scrolls with the hardware start line and refuses
+//! rotations it can't scroll
+
+#![cfg(feature = "graphics")]
+
+use core::fmt::Write;
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use ssd1351::builder::Builder;
+use ssd1351::mode::displaymode::DisplayModeTrait;
+use ssd1351::mode::TerminalMode;
+use ssd1351::properties::{DisplayRotation, DisplaySize};
+
+/// Column address command
+const COLUMN: u8 = 0x15;
+/// Row address command
+const ROW: u8 = 0x75;
+/// Display start line command
+const START_LINE: u8 = 0xA1;
+
+/// Interface recording every command with its parameters
+#[derive(Default)]
+struct Recorder {
+    commands: Vec<(u8, Vec<u8>)>,
+}
+
+impl WriteOnlyDataCommand for Recorder {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        let DataFormat::U8(bytes) = cmd else {
+            return Err(DisplayError::DataFormatNotImplemented);
+        };
+        self.commands
+            .extend(bytes.iter().map(|&opcode| (opcode, Vec::new())));
+        Ok(())
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        let DataFormat::U8(bytes) = buf else {
+            return Err(DisplayError::DataFormatNotImplemented);
+        };
+        if let Some((_, params)) = self.commands.last_mut() {
+            params.extend(bytes);
+        }
+        Ok(())
+    }
+}
+
+impl Recorder {
+    /// Start lines sent, in order
+    fn start_lines(&self) -> Vec<u8> {
+        self.commands
+            .iter()
+            .filter(|(opcode, _)| *opcode == START_LINE)
+            .map(|(_, params)| params[0])
+            .collect()
+    }
+
+    /// Draw windows set, in order, as the first and last column and row
+    fn windows(&self) -> Vec<((u8, u8), (u8, u8))> {
+        self.commands
+            .windows(2)
+            .filter(|pair| pair[0].0 == COLUMN && pair[1].0 == ROW)
+            .map(|pair| {
+                let (columns, rows) = (&pair[0].1, &pair[1].1);
+                ((columns[0], columns[1]), (rows[0], rows[1]))
+            })
+            .collect()
+    }
+
+    /// Top left corners of the windows characters were drawn into
+    fn glyphs(&self) -> Vec<(u8, u8)> {
+        self.windows()
+            .into_iter()
+            .filter(|(columns, _)| columns.1 - columns.0 == 7)
+            .map(|(columns, rows)| (columns.0, rows.0))
+            .collect()
+    }
+
+    /// First RAM rows of the full width windows cleared
+    fn cleared(&self) -> Vec<u8> {
+        self.windows()
+            .into_iter()
+            .filter(|(columns, _)| *columns == (0, 127))
+            .map(|(_, rows)| rows.0)
+            .collect()
+    }
+}
+
+fn terminal(size: DisplaySize) -> TerminalMode<Recorder> {
+    let mut display: TerminalMode<_> = Builder::new()
+        .with_size(size)
+        .connect_interface(Recorder::default())
+        .into();
+    display.init().unwrap();
+    display
+}
+
+fn recorder(display: TerminalMode<Recorder>) -> Recorder {
+    display.release().0.release()
+}
+
+#[test]
+fn text_wraps_at_the_right_edge() {
+    let mut display = terminal(DisplaySize::Display128x128);
+    assert_eq!(display.get_char_dimensions(), (16, 16));
+
+    write!(display, "{}", "A".repeat(17)).unwrap();
+    assert_eq!(display.get_position(), (1, 1));
+
+    let glyphs = recorder(display).glyphs();
+    assert_eq!(glyphs.len(), 17);
+    assert_eq!(glyphs[0], (0, 0));
+    assert_eq!(glyphs[15], (120, 0));
+    assert_eq!(glyphs[16], (0, 8));
+}
+
+#[test]
+fn full_width_lines_break_once() {
+    let init = recorder(terminal(DisplaySize::Display128x128)).start_lines();
+    let mut display = terminal(DisplaySize::Display128x128);
+    writeln!(display, "{}", "A".repeat(16)).unwrap();
+    assert_eq!(display.get_position(), (0, 1));
+
+    // Filling the last line doesn't scroll until the next character
+    display.set_position(0, 15).unwrap();
+    write!(display, "{}", "B".repeat(16)).unwrap();
+    assert_eq!(display.get_position(), (15, 15));
+
+    let sent = recorder(display);
+    assert_eq!(sent.start_lines(), init);
+    let glyphs = sent.glyphs();
+    assert_eq!(glyphs.len(), 32);
+    assert_eq!(glyphs[15], (120, 0));
+    assert_eq!(glyphs[16], (0, 120));
+
+    // A line break after a full last line scrolls once
+    let mut display = terminal(DisplaySize::Display128x128);
+    display.set_position(0, 15).unwrap();
+    writeln!(display, "{}", "B".repeat(16)).unwrap();
+    assert_eq!(display.get_position(), (0, 15));
+    assert_eq!(recorder(display).start_lines(), [&init[..], &[8]].concat());
+}
+
+#[test]
+fn newlines_and_control_characters_move_the_cursor() {
+    let mut display = terminal(DisplaySize::Display128x128);
+
+    write!(display, "ab\ncd").unwrap();
+    assert_eq!(display.get_position(), (2, 1));
+    write!(display, "\rx\ty").unwrap();
+    assert_eq!(display.get_position(), (5, 1));
+    // A tab past the last column breaks the line
+    display.set_position(14, 1).unwrap();
+    write!(display, "\t").unwrap();
+    assert_eq!(display.get_position(), (0, 2));
+
+    assert!(matches!(
+        display.set_position(16, 0),
+        Err(DisplayError::OutOfBoundsError)
+    ));
+
+    let glyphs = recorder(display).glyphs();
+    assert_eq!(glyphs, [(0, 0), (8, 0), (0, 8), (8, 8), (0, 8), (32, 8)]);
+}
+
+#[test]
+fn scrolling_moves_the_start_line() {
+    let mut display = terminal(DisplaySize::Display128x128);
+    // Move to the last line, then scroll through the whole display RAM once
+    write!(display, "{}", "\n".repeat(15)).unwrap();
+    write!(display, "{}", "\n".repeat(16)).unwrap();
+    assert_eq!(display.get_position(), (0, 15));
+    write!(display, "z").unwrap();
+
+    let recorder = recorder(display);
+    let start_lines = recorder.start_lines();
+    let expected: Vec<u8> = (1..=16).map(|line| (line * 8 % 128) as u8).collect();
+    assert_eq!(start_lines[start_lines.len() - 16..], expected);
+    // Each scroll clears the line exposed at the bottom, wrapping around the RAM
+    let cleared = recorder.cleared();
+    assert_eq!(
+        cleared[cleared.len() - 16..],
+        (0..16).map(|line| line * 8).collect::<Vec<_>>()
+    );
+    // The last line is back at the bottom of the RAM
+    assert_eq!(recorder.glyphs(), [(0, 120)]);
+}
+
+#[test]
+fn start_line_wraps_around_the_ram() {
+    let mut display = terminal(DisplaySize::Display128x96);
+    assert_eq!(display.get_char_dimensions(), (16, 12));
+
+    // Five scrolls put the last line at RAM row 40 + 88 = 128, wrapping to 0
+    write!(display, "{}", "\n".repeat(11 + 5)).unwrap();
+    write!(display, "z").unwrap();
+
+    let recorder = recorder(display);
+    let start_lines = recorder.start_lines();
+    assert_eq!(start_lines[start_lines.len() - 5..], [8, 16, 24, 32, 40]);
+    assert_eq!(recorder.cleared().last(), Some(&0));
+    assert_eq!(recorder.glyphs(), [(0, 0)]);
+}
+
+#[test]
+fn vertical_rotations_are_rejected() {
+    for rotation in [DisplayRotation::Rotate90, DisplayRotation::Rotate270] {
+        let mut display: TerminalMode<_> = Builder::new()
+            .with_rotation(rotation)
+            .connect_interface(Recorder::default())
+            .into();
+        assert!(matches!(
+            display.init(),
+            Err(DisplayError::InvalidFormatError)
+        ));
+        // Nothing was sent
+        assert!(recorder(display).commands.is_empty());
+
+        let mut display = terminal(DisplaySize::Display128x128);
+        assert!(matches!(
+            display.set_rotation(rotation),
+            Err(DisplayError::InvalidFormatError)
+        ));
+    }
+
+    let mut display = terminal(DisplaySize::Display128x128);
+    display.set_rotation(DisplayRotation::Rotate180).unwrap();
+}
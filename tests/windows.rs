@@ -0,0 +1,126 @@
+//! Checks pixel runs only set a draw window when the display RAM pointer isn't at their start
+//! already
+
+#![cfg(feature = "graphics")]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use embedded_graphics::pixelcolor::{Rgb565, RgbColor};
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+use ssd1351::builder::Builder;
+use ssd1351::mode::DirectGraphicsMode;
+
+/// Column address command
+const COLUMN: u8 = 0x15;
+/// Row address command
+const ROW: u8 = 0x75;
+
+/// Column and row address commands, and bytes of data sent
+#[derive(Default)]
+struct Counts {
+    columns: usize,
+    rows: usize,
+    data: usize,
+}
+
+/// Interface counting what it sends
+#[derive(Default)]
+struct Counter {
+    counts: Rc<RefCell<Counts>>,
+}
+
+impl WriteOnlyDataCommand for Counter {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        let DataFormat::U8(bytes) = cmd else {
+            return Err(DisplayError::DataFormatNotImplemented);
+        };
+        let mut counts = self.counts.borrow_mut();
+        counts.columns += bytes.iter().filter(|&&opcode| opcode == COLUMN).count();
+        counts.rows += bytes.iter().filter(|&&opcode| opcode == ROW).count();
+        Ok(())
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        let DataFormat::U8(bytes) = buf else {
+            return Err(DisplayError::DataFormatNotImplemented);
+        };
+        self.counts.borrow_mut().data += bytes.len();
+        Ok(())
+    }
+}
+
+/// Direct mode collecting runs of up to 4 pixels, and what it sends
+fn direct_mode() -> (DirectGraphicsMode<Counter, 8>, Rc<RefCell<Counts>>) {
+    let counter = Counter::default();
+    let counts = counter.counts.clone();
+    (Builder::new().connect_interface(counter).into(), counts)
+}
+
+/// Draw `points` and count the draw windows set
+fn windows(
+    display: &mut DirectGraphicsMode<Counter, 8>,
+    counts: &RefCell<Counts>,
+    points: &[(i32, i32)],
+) -> usize {
+    counts.take();
+    let pixels = points
+        .iter()
+        .map(|&(x, y)| Pixel(Point::new(x, y), Rgb565::WHITE));
+    display.draw_iter(pixels).unwrap();
+
+    let counts = counts.take();
+    assert_eq!(counts.columns, counts.rows);
+    // Every window takes 2 column and 2 row parameter bytes, the rest is pixel data
+    assert_eq!(counts.data - counts.columns * 4, points.len() * 2);
+    counts.columns
+}
+
+#[test]
+fn consecutive_pixels_share_a_window() {
+    let (mut display, counts) = direct_mode();
+
+    // Three runs along a row, the later ones continuing where the RAM pointer is
+    let row: Vec<_> = (0..10).map(|x| (x, 5)).collect();
+    assert_eq!(windows(&mut display, &counts, &row), 1);
+
+    // The next draw continues the row
+    assert_eq!(windows(&mut display, &counts, &[(10, 5), (11, 5)]), 0);
+
+    // Down a column
+    let column: Vec<_> = (0..10).map(|y| (20, y)).collect();
+    assert_eq!(windows(&mut display, &counts, &column), 1);
+}
+
+#[test]
+fn gaps_need_a_new_window() {
+    let (mut display, counts) = direct_mode();
+
+    assert_eq!(windows(&mut display, &counts, &[(0, 0), (2, 0), (4, 0)]), 3);
+    // Continuing where the last draw stopped, then going back and skipping ahead
+    assert_eq!(windows(&mut display, &counts, &[(5, 0), (4, 0), (6, 1)]), 2);
+
+    // A row window ends at the right edge, after which the pointer wraps to its start
+    let edge: Vec<_> = (124..128).map(|x| (x, 9)).collect();
+    assert_eq!(windows(&mut display, &counts, &edge), 1);
+    assert_eq!(windows(&mut display, &counts, &[(0, 10)]), 1);
+
+    // A run in another direction than the pointer moves in
+    assert_eq!(windows(&mut display, &counts, &[(1, 10), (1, 11)]), 1);
+}
+
+#[test]
+fn other_draws_reset_the_pointer() {
+    let (mut display, counts) = direct_mode();
+
+    assert_eq!(windows(&mut display, &counts, &[(0, 0), (1, 0)]), 1);
+    display
+        .fill_solid(
+            &Rectangle::new(Point::new(50, 50), Size::new(2, 2)),
+            Rgb565::RED,
+        )
+        .unwrap();
+    assert_eq!(windows(&mut display, &counts, &[(2, 0)]), 1);
+}
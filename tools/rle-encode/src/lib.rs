@@ -0,0 +1,65 @@
+//! Host side conversion of images into the ssd1351 run-length encoded RGB565 format
+//!
+//! Use it from a build script to convert assets for `include_bytes!`:
+//!
+//! ```rust,no_run
+//! let data = rle_encode::encode_file("assets/logo.png").unwrap();
+//! std::fs::write(std::env::var("OUT_DIR").unwrap() + "/logo.rle", data).unwrap();
+//! ```
+
+use image::{DynamicImage, ImageError};
+use std::path::Path;
+
+/// Errors converting an image
+#[derive(Debug)]
+pub enum Error {
+    /// The image couldn't be read or decoded
+    Image(ImageError),
+    /// The image is wider or taller than 65535 pixels
+    TooLarge,
+}
+
+impl From<ImageError> for Error {
+    fn from(error: ImageError) -> Self {
+        Error::Image(error)
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Image(error) => write!(f, "{}", error),
+            Error::TooLarge => write!(f, "image is larger than 65535 pixels in a dimension"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Convert an 8 bit per channel colour to RGB565
+pub fn rgb565(r: u8, g: u8, b: u8) -> u16 {
+    (r as u16 & 0xF8) << 8 | (g as u16 & 0xFC) << 3 | b as u16 >> 3
+}
+
+/// Encode `image`, dropping any alpha channel
+pub fn encode_image(image: &DynamicImage) -> Result<Vec<u8>, Error> {
+    let image = image.to_rgb8();
+    let width = u16::try_from(image.width()).map_err(|_| Error::TooLarge)?;
+    let height = u16::try_from(image.height()).map_err(|_| Error::TooLarge)?;
+    let pixels: Vec<u16> = image
+        .pixels()
+        .map(|pixel| rgb565(pixel[0], pixel[1], pixel[2]))
+        .collect();
+
+    let mut data = Vec::new();
+    ssd1351::rle::encode(width, height, &pixels, |bytes| {
+        data.extend_from_slice(bytes)
+    })
+    .expect("pixel count matches the image size");
+    Ok(data)
+}
+
+/// Read and encode the PNG or BMP image at `path`
+pub fn encode_file<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, Error> {
+    encode_image(&image::open(path)?)
+}
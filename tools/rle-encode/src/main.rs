@@ -0,0 +1,38 @@
+//! Converts a PNG or BMP image into the ssd1351 run-length encoded RGB565 format
+//!
+//! Usage: `rle-encode <input> <output>`
+
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().collect();
+    let [_, input, output] = args.as_slice() else {
+        eprintln!("usage: rle-encode <input.png|input.bmp> <output.rle>");
+        return ExitCode::FAILURE;
+    };
+
+    let data = match rle_encode::encode_file(input) {
+        Ok(data) => data,
+        Err(error) => {
+            eprintln!("{}: {}", input, error);
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(error) = std::fs::write(output, &data) {
+        eprintln!("{}: {}", output, error);
+        return ExitCode::FAILURE;
+    }
+
+    let width = u16::from_be_bytes([data[4], data[5]]);
+    let height = u16::from_be_bytes([data[6], data[7]]);
+    let raw = width as usize * height as usize * 2;
+    println!(
+        "{}: {}x{}, {} bytes ({} raw)",
+        output,
+        width,
+        height,
+        data.len(),
+        raw
+    );
+    ExitCode::SUCCESS
+}
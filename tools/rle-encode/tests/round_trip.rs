@@ -0,0 +1,80 @@
+//! Checks images converted by the tool decode back to their pixels with the driver's decoder
+
+use image::{DynamicImage, Rgb, RgbImage, Rgba, RgbaImage};
+use rle_encode::{encode_file, encode_image, rgb565};
+use ssd1351::rle::{Packet, RleDecoder};
+
+/// Decode every pixel of `data`
+fn decode(data: &[u8]) -> Vec<u16> {
+    let mut decoder = RleDecoder::new(data).unwrap();
+    let mut pixels = Vec::new();
+    for _ in 0..decoder.height() {
+        let mut column = 0;
+        while column < decoder.width() as usize {
+            let packet = decoder.next_packet().unwrap();
+            match packet {
+                Packet::Literal(bytes) => pixels.extend(
+                    bytes
+                        .chunks_exact(2)
+                        .map(|pixel| u16::from_be_bytes([pixel[0], pixel[1]])),
+                ),
+                Packet::Run { color, len } => pixels.extend(std::iter::repeat_n(color, len)),
+            }
+            column += packet.len();
+        }
+    }
+    pixels
+}
+
+/// 200 by 20 pixels, a gradient with a solid band
+fn image() -> RgbImage {
+    RgbImage::from_fn(200, 20, |x, y| {
+        if (5..10).contains(&y) || x < 150 && y == 12 {
+            Rgb([0x20, 0x80, 0xFF])
+        } else {
+            Rgb([x as u8, (x + y) as u8, y as u8 * 10])
+        }
+    })
+}
+
+fn expected(image: &RgbImage) -> Vec<u16> {
+    image
+        .pixels()
+        .map(|pixel| rgb565(pixel[0], pixel[1], pixel[2]))
+        .collect()
+}
+
+#[test]
+fn images_round_trip() {
+    let image = image();
+    let data = encode_image(&DynamicImage::ImageRgb8(image.clone())).unwrap();
+    assert_eq!(data[..8], *b"R565\0\xC8\0\x14");
+    assert_eq!(decode(&data), expected(&image));
+    // The solid rows compress to two runs each
+    assert!(data.len() < image.len() / 3 * 2);
+}
+
+#[test]
+fn alpha_is_dropped() {
+    let image = RgbaImage::from_fn(3, 2, |x, y| Rgba([0xF8, x as u8 * 8, y as u8 * 8, x as u8]));
+    let data = encode_image(&DynamicImage::ImageRgba8(image)).unwrap();
+    assert_eq!(
+        decode(&data),
+        [0xF800, 0xF840, 0xF880, 0xF801, 0xF841, 0xF881]
+    );
+}
+
+#[test]
+fn files_round_trip() {
+    let image = image();
+    let dir = std::env::temp_dir();
+    for name in ["rle-encode-round-trip.png", "rle-encode-round-trip.bmp"] {
+        let path = dir.join(name);
+        image.save(&path).unwrap();
+        let data = encode_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(decode(&data), expected(&image));
+    }
+
+    assert!(encode_file(dir.join("rle-encode-missing.png")).is_err());
+}